@@ -1,6 +1,11 @@
-import { WasmWorker } from '@wasmworker/sdk';
+import { WasmWorker, WasmWorkerPool } from '@wasmworker/sdk';
+// Generated from the example's exports by `wasmworker-bindgen`
+import { bindings, type Api, type Point } from '../../../examples/rust-add/bindings';
 
-let worker: WasmWorker | null = null;
+const MODULE_URL = '/examples/rust-add/dist/module.wasm';
+
+let worker: WasmWorker<Api> | null = null;
+let pool: WasmWorkerPool | null = null;
 
 // DOM elements
 const statusEl = document.getElementById('status') as HTMLDivElement;
@@ -11,13 +16,38 @@ const multiplyBtn = document.getElementById('multiply-btn') as HTMLButtonElement
 const doubleBtn = document.getElementById('double-btn') as HTMLButtonElement;
 const benchBtn = document.getElementById('bench-btn') as HTMLButtonElement;
 const concurrentBtn = document.getElementById('concurrent-btn') as HTMLButtonElement;
+const poolBtn = document.getElementById('pool-btn') as HTMLButtonElement;
+const checksumBtn = document.getElementById('checksum-btn') as HTMLButtonElement;
+const greetBtn = document.getElementById('greet-btn') as HTMLButtonElement;
+const boundsBtn = document.getElementById('bounds-btn') as HTMLButtonElement;
+const boundsInvalidBtn = document.getElementById('bounds-invalid-btn') as HTMLButtonElement;
+const pathLengthBtn = document.getElementById('path-length-btn') as HTMLButtonElement;
+const streamBtn = document.getElementById('stream-btn') as HTMLButtonElement;
+const cancelStartBtn = document.getElementById('cancel-start-btn') as HTMLButtonElement;
+const cancelAbortBtn = document.getElementById('cancel-abort-btn') as HTMLButtonElement;
+const timeoutBtn = document.getElementById('timeout-btn') as HTMLButtonElement;
+const describeBtn = document.getElementById('describe-btn') as HTMLButtonElement;
+const logBtn = document.getElementById('log-btn') as HTMLButtonElement;
+const hostBtn = document.getElementById('host-btn') as HTMLButtonElement;
+const scaleInput = document.getElementById('scale-input') as HTMLInputElement;
 const errorBtn = document.getElementById('error-btn') as HTMLButtonElement;
 const numAInput = document.getElementById('num-a') as HTMLInputElement;
 const numBInput = document.getElementById('num-b') as HTMLInputElement;
 const fibNInput = document.getElementById('fib-n') as HTMLInputElement;
+const checksumInput = document.getElementById('checksum-input') as HTMLInputElement;
 const basicResultEl = document.getElementById('basic-result') as HTMLDivElement;
 const benchmarkResultsEl = document.getElementById('benchmark-results') as HTMLDivElement;
 const concurrentResultEl = document.getElementById('concurrent-result') as HTMLDivElement;
+const poolResultEl = document.getElementById('pool-result') as HTMLDivElement;
+const checksumResultEl = document.getElementById('checksum-result') as HTMLDivElement;
+const boundsResultEl = document.getElementById('bounds-result') as HTMLDivElement;
+const pathLengthResultEl = document.getElementById('path-length-result') as HTMLDivElement;
+const streamResultEl = document.getElementById('stream-result') as HTMLDivElement;
+const cancelResultEl = document.getElementById('cancel-result') as HTMLDivElement;
+const timeoutResultEl = document.getElementById('timeout-result') as HTMLDivElement;
+const describeResultEl = document.getElementById('describe-result') as HTMLDivElement;
+const logResultEl = document.getElementById('log-result') as HTMLDivElement;
+const hostResultEl = document.getElementById('host-result') as HTMLDivElement;
 const errorResultEl = document.getElementById('error-result') as HTMLDivElement;
 
 // Set status
@@ -34,6 +64,18 @@ function setButtonsEnabled(enabled: boolean) {
   doubleBtn.disabled = !enabled;
   benchBtn.disabled = !enabled;
   concurrentBtn.disabled = !enabled;
+  poolBtn.disabled = !enabled;
+  checksumBtn.disabled = !enabled;
+  greetBtn.disabled = !enabled;
+  boundsBtn.disabled = !enabled;
+  boundsInvalidBtn.disabled = !enabled;
+  pathLengthBtn.disabled = !enabled;
+  streamBtn.disabled = !enabled;
+  cancelStartBtn.disabled = !enabled;
+  timeoutBtn.disabled = !enabled;
+  describeBtn.disabled = !enabled;
+  logBtn.disabled = !enabled;
+  hostBtn.disabled = !enabled;
   errorBtn.disabled = !enabled;
 }
 
@@ -45,7 +87,12 @@ initBtn.addEventListener('click', async () => {
 
     // Load the WASM module
     worker = await WasmWorker.load({
-      moduleUrl: '/examples/rust-add/dist/module.wasm',
+      moduleUrl: MODULE_URL,
+      bindings,
+      init: {
+        // Runs on the main thread when Rust calls `call_host("fetch_config", ...)`
+        fetch_config: async (key: string) => (key === 'scale' ? Number(scaleInput.value) : null),
+      },
     });
 
     setStatus('ready', 'Worker Ready');
@@ -61,7 +108,7 @@ addBtn.addEventListener('click', async () => {
   try {
     const a = parseInt(numAInput.value);
     const b = parseInt(numBInput.value);
-    const result = await worker!.call<{ a: number; b: number }, number>('add', { a, b });
+    const result = await worker!.api.add(a, b);
     basicResultEl.innerHTML = `<div class="result"><strong>Result:</strong> ${a} + ${b} = ${result}</div>`;
   } catch (error) {
     basicResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
@@ -72,7 +119,7 @@ subtractBtn.addEventListener('click', async () => {
   try {
     const a = parseInt(numAInput.value);
     const b = parseInt(numBInput.value);
-    const result = await worker!.call<{ a: number; b: number }, number>('subtract', { a, b });
+    const result = await worker!.api.subtract(a, b);
     basicResultEl.innerHTML = `<div class="result"><strong>Result:</strong> ${a} - ${b} = ${result}</div>`;
   } catch (error) {
     basicResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
@@ -83,7 +130,7 @@ multiplyBtn.addEventListener('click', async () => {
   try {
     const a = parseInt(numAInput.value);
     const b = parseInt(numBInput.value);
-    const result = await worker!.call<{ a: number; b: number }, number>('multiply', { a, b });
+    const result = await worker!.api.multiply(a, b);
     basicResultEl.innerHTML = `<div class="result"><strong>Result:</strong> ${a} × ${b} = ${result}</div>`;
   } catch (error) {
     basicResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
@@ -93,7 +140,7 @@ multiplyBtn.addEventListener('click', async () => {
 doubleBtn.addEventListener('click', async () => {
   try {
     const a = parseInt(numAInput.value);
-    const result = await worker!.call<number, number>('double', a);
+    const result = await worker!.api.double(a);
     basicResultEl.innerHTML = `<div class="result"><strong>Result:</strong> ${a} × 2 = ${result}</div>`;
   } catch (error) {
     basicResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
@@ -154,6 +201,156 @@ benchBtn.addEventListener('click', async () => {
   }
 });
 
+// Cancellation
+let fibController: AbortController | null = null;
+
+cancelStartBtn.addEventListener('click', async () => {
+  fibController = new AbortController();
+  cancelStartBtn.disabled = true;
+  cancelAbortBtn.disabled = false;
+  cancelResultEl.innerHTML = '<div class="result">Computing fib(45)...</div>';
+
+  const start = performance.now();
+  try {
+    const result = await worker!.call<number, bigint>('fib', 45, { signal: fibController.signal });
+    cancelResultEl.innerHTML = `<div class="result">fib(45) = ${result}</div>`;
+  } catch (error: any) {
+    const elapsed = (performance.now() - start).toFixed(0);
+    cancelResultEl.innerHTML = error.code === 'CANCELLED'
+      ? `<div class="result"><strong>Cancelled</strong> after ${elapsed}ms<br/>Code: <code>${error.code}</code></div>`
+      : `<div class="error-message">${error.message}</div>`;
+  } finally {
+    fibController = null;
+    cancelStartBtn.disabled = false;
+    cancelAbortBtn.disabled = true;
+  }
+});
+
+cancelAbortBtn.addEventListener('click', () => {
+  fibController?.abort();
+});
+
+// Timeouts
+timeoutBtn.addEventListener('click', async () => {
+  timeoutBtn.disabled = true;
+  timeoutResultEl.innerHTML = '<div class="result">Computing fib(50)...</div>';
+
+  try {
+    const result = await worker!.call<number, bigint>('fib', 50, { timeoutMs: 500 });
+    timeoutResultEl.innerHTML = `<div class="result">fib(50) = ${result}</div>`;
+  } catch (error: any) {
+    if (error.code !== 'TIMEOUT') {
+      timeoutResultEl.innerHTML = `<div class="error-message">${error.message}</div>`;
+      return;
+    }
+    // The stuck worker has been replaced, so the next call runs right away
+    const sum = await worker!.call<{ a: number; b: number }, number>('add', { a: 2, b: 3 });
+    timeoutResultEl.innerHTML = `<div class="result"><strong>Timed out</strong>, worker respawned<br/>Code: <code>${error.code}</code><br/>add(2, 3) = ${sum}</div>`;
+  } finally {
+    timeoutBtn.disabled = false;
+  }
+});
+
+// Byte buffers
+checksumBtn.addEventListener('click', async () => {
+  try {
+    const bytes = new TextEncoder().encode(checksumInput.value);
+    const result = await worker!.api.checksum(bytes, { transfer: [bytes.buffer] });
+    const hex = result.toString(16).padStart(8, '0');
+    checksumResultEl.innerHTML = `<div class="result"><strong>Adler-32:</strong> 0x${hex}</div>`;
+  } catch (error) {
+    checksumResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
+  }
+});
+
+greetBtn.addEventListener('click', async () => {
+  try {
+    const greeting = await worker!.api.greet(checksumInput.value);
+    checksumResultEl.innerHTML = `<div class="result"><strong>Result:</strong> ${greeting}</div>`;
+  } catch (error) {
+    checksumResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
+  }
+});
+
+// Structured data
+boundsBtn.addEventListener('click', async () => {
+  try {
+    const points: Point[] = Array.from({ length: 1000 }, () => ({
+      x: Math.random() * 200 - 100,
+      y: Math.random() * 200 - 100,
+    }));
+    const bounds = await worker!.api.bounds(points);
+    boundsResultEl.innerHTML = `
+      <div class="result">
+        <strong>${bounds.count} points</strong><br/>
+        min: (${bounds.min.x.toFixed(2)}, ${bounds.min.y.toFixed(2)})<br/>
+        max: (${bounds.max.x.toFixed(2)}, ${bounds.max.y.toFixed(2)})
+      </div>
+    `;
+  } catch (error) {
+    boundsResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
+  }
+});
+
+boundsInvalidBtn.addEventListener('click', async () => {
+  try {
+    await worker!.call('bounds', { x: 'not a list' }, { codec: 'json' });
+    boundsResultEl.innerHTML = '<div class="error-message">Expected an error but got success?!</div>';
+  } catch (error: any) {
+    boundsResultEl.innerHTML = `
+      <div class="result">
+        <strong>Rejected by serde!</strong><br/>
+        Code: <code>${error.code || 'N/A'}</code><br/>
+        Message: ${error.message}
+      </div>
+    `;
+  }
+});
+
+pathLengthBtn.addEventListener('click', async () => {
+  try {
+    // A random walk, large enough that JSON text would be several megabytes
+    const points: Point[] = [{ x: 0, y: 0 }];
+    for (let i = 1; i < 100_000; i++) {
+      const prev = points[i - 1];
+      points.push({ x: prev.x + Math.random() - 0.5, y: prev.y + Math.random() - 0.5 });
+    }
+
+    const start = performance.now();
+    const length = await worker!.api.path_length(points);
+    const elapsed = performance.now() - start;
+
+    pathLengthResultEl.innerHTML = `
+      <div class="result">
+        <strong>Length: ${length.toFixed(2)}</strong><br/>
+        ${points.length.toLocaleString()} points in ${elapsed.toFixed(1)}ms
+      </div>
+    `;
+  } catch (error) {
+    pathLengthResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
+  }
+});
+
+streamBtn.addEventListener('click', async () => {
+  try {
+    streamBtn.disabled = true;
+    const values: bigint[] = [];
+    for await (const value of worker!.api.fib_sequence(50)) {
+      values.push(value);
+      streamResultEl.innerHTML = `
+        <div class="result">
+          <strong>${values.length} chunks received</strong><br/>
+          Latest: ${value}
+        </div>
+      `;
+    }
+  } catch (error) {
+    streamResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
+  } finally {
+    streamBtn.disabled = false;
+  }
+});
+
 // Concurrent calls
 concurrentBtn.addEventListener('click', async () => {
   try {
@@ -189,6 +386,78 @@ concurrentBtn.addEventListener('click', async () => {
   }
 });
 
+// Worker pool
+poolBtn.addEventListener('click', async () => {
+  poolBtn.disabled = true;
+  const inputs = Array.from({ length: 8 }, () => 35);
+
+  try {
+    poolResultEl.innerHTML = '<div class="result">Running on one worker...</div>';
+    const singleStart = performance.now();
+    await Promise.all(inputs.map((n) => worker!.call<number, bigint>('fib', n)));
+    const singleTime = performance.now() - singleStart;
+
+    poolResultEl.innerHTML = '<div class="result">Running on the pool...</div>';
+    // Loaded on first use, so the module is only compiled once for all workers
+    pool ??= await WasmWorkerPool.load({ moduleUrl: MODULE_URL });
+    const poolStart = performance.now();
+    await Promise.all(inputs.map((n) => pool!.call<number, bigint>('fib', n)));
+    const poolTime = performance.now() - poolStart;
+
+    poolResultEl.innerHTML = `
+      <div class="result">
+        <strong>1 worker:</strong> ${singleTime.toFixed(0)}ms<br/>
+        <strong>${pool.size} workers:</strong> ${poolTime.toFixed(0)}ms<br/>
+        <strong>Speedup:</strong> ${(singleTime / poolTime).toFixed(2)}x
+      </div>
+    `;
+  } catch (error) {
+    poolResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
+  } finally {
+    poolBtn.disabled = false;
+  }
+});
+
+// Export manifest
+describeBtn.addEventListener('click', () => {
+  const rows = worker!.describe().map((entry) => {
+    const params = entry.params.map((param) => `${param.name}: ${param.type}`).join(', ');
+    const returns = entry.returns ? ` -> ${entry.returns}` : '';
+    const flags = [entry.codec, entry.stream ? 'stream' : null].filter(Boolean).join(', ');
+    return `<code>${entry.name}(${params})${returns}</code>${flags ? ` <em>(${flags})</em>` : ''}<br/>${entry.docs}`;
+  });
+  describeResultEl.innerHTML = `<div class="result">${rows.join('<br/><br/>')}</div>`;
+});
+
+// Guest logs
+logBtn.addEventListener('click', async () => {
+  const lines: string[] = [];
+  const stop = worker!.on('log', (record) => {
+    lines.push(`<code>${record.level}</code> ${record.message}`);
+  });
+
+  try {
+    await worker!.api.enable_logging(true);
+    await worker!.api.greet(checksumInput.value);
+    await worker!.api.checksum(new TextEncoder().encode(checksumInput.value));
+    logResultEl.innerHTML = `<div class="result">${lines.join('<br/>')}</div>`;
+  } catch (error) {
+    logResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
+  } finally {
+    stop();
+  }
+});
+
+// Host functions
+hostBtn.addEventListener('click', async () => {
+  try {
+    const result = await worker!.api.scaled(10);
+    hostResultEl.innerHTML = `<div class="result">scaled(10) = ${result}, using <code>fetch_config('scale')</code> from the page</div>`;
+  } catch (error) {
+    hostResultEl.innerHTML = `<div class="error-message">${error instanceof Error ? error.message : String(error)}</div>`;
+  }
+});
+
 // Error handling
 errorBtn.addEventListener('click', async () => {
   try {
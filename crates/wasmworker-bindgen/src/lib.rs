@@ -0,0 +1,380 @@
+//! Generate typed TypeScript bindings for a `wasmworker` guest crate.
+//!
+//! The generator reads the crate's source, finds every function annotated
+//! with `#[wasmworker::export]` and emits a TypeScript module with an `Api`
+//! interface holding one typed method per export, plus the `bindings`
+//! that implement it on top of `worker.call` and `worker.stream`:
+//!
+//! ```
+//! let ts = wasmworker_bindgen::generate_from_source(
+//!     r#"
+//!     /// Add two 32-bit integers
+//!     #[wasmworker::export]
+//!     pub fn add(a: i32, b: i32) -> i32 {
+//!         a + b
+//!     }
+//!     "#,
+//!     &wasmworker_bindgen::Options::default(),
+//! )
+//! .unwrap();
+//!
+//! assert!(ts.contains("add(a: number, b: number, options?: CallOptions): Promise<number>;"));
+//! ```
+//!
+//! Passing the bindings to `WasmWorker.load({ moduleUrl, bindings })` makes
+//! them available as `worker.api`, e.g. `await worker.api.add(2, 3)`.
+//!
+//! Codec exports are typed from the serde representation of their
+//! parameters and return value. Structs and enums defined in the crate get
+//! a matching interface or union type; `#[serde(...)]` attributes are not
+//! taken into account, and types from other crates become `unknown`.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+mod parse;
+mod types;
+
+use parse::{Crate, Export};
+use types::SerdeTypes;
+
+/// Errors returned while generating bindings.
+#[derive(Debug)]
+pub enum Error {
+    /// A source file could not be read.
+    Io { path: PathBuf, source: io::Error },
+    /// A source file is not valid Rust.
+    Parse { path: PathBuf, source: syn::Error },
+    /// An export has a signature `#[wasmworker::export]` does not accept.
+    Unsupported { function: String, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "failed to read {}: {source}", path.display()),
+            Error::Parse { path, source } => {
+                write!(f, "failed to parse {}: {source}", path.display())
+            }
+            Error::Unsupported { function, message } => {
+                write!(f, "cannot generate bindings for `{function}`: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Parse { source, .. } => Some(source),
+            Error::Unsupported { .. } => None,
+        }
+    }
+}
+
+/// Settings for the generated module.
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Module the SDK types are imported from.
+    pub sdk: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            sdk: "@wasmworker/sdk".into(),
+        }
+    }
+}
+
+/// Generate bindings for the crate whose root module is `root`, usually
+/// `src/lib.rs`. Modules declared with `mod name;` are read as well.
+pub fn generate(root: &Path, options: &Options) -> Result<String, Error> {
+    emit(&Crate::load(root)?, options)
+}
+
+/// Generate bindings for a single source file.
+///
+/// Modules declared with `mod name;` are skipped, as there are no files to
+/// read them from.
+pub fn generate_from_source(source: &str, options: &Options) -> Result<String, Error> {
+    emit(&Crate::parse(source)?, options)
+}
+
+/// JavaScript reserved words, which cannot be used as parameter names.
+const RESERVED: &[&str] = &[
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// A parameter name that is valid in JavaScript.
+fn param_name(name: &str) -> String {
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    if RESERVED.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_owned()
+    }
+}
+
+/// A property key for `name`, quoted unless it is a plain identifier.
+fn property_key(name: &str) -> String {
+    let mut chars = name.chars();
+    let is_ident = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_ident {
+        name.to_owned()
+    } else {
+        format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+/// The signature and implementation of one `Api` method.
+struct Method {
+    /// `name(params): Result`, as declared in the interface.
+    signature: String,
+    /// `name: (params) => ...`, as defined in the bindings.
+    binding: String,
+    /// TypeScript type of the `details` of an `APP_ERROR`, for exports
+    /// returning `Result`.
+    error: Option<String>,
+}
+
+fn method(export: &Export, serde: &mut SerdeTypes) -> Result<Method, Error> {
+    let unsupported = |message: String| Error::Unsupported {
+        function: export.function.clone(),
+        message,
+    };
+
+    let names: Vec<_> = export
+        .params
+        .iter()
+        .map(|(name, _)| param_name(name))
+        .collect();
+    let options = if names.iter().any(|name| name == "options") {
+        "callOptions"
+    } else {
+        "options"
+    };
+
+    let mut typed = Vec::new();
+    for ((_, ty), name) in export.params.iter().zip(&names) {
+        let ts = match export.codec {
+            Some(_) => serde.ts(ty),
+            None => types::direct_param(ty)
+                .ok_or_else(|| unsupported(format!("unsupported parameter type for `{name}`")))?
+                .to_owned(),
+        };
+        typed.push(format!("{name}: {ts}"));
+    }
+    typed.push(format!("{options}?: CallOptions"));
+
+    let mut untyped = names.clone();
+    untyped.push(options.to_owned());
+
+    let key = property_key(&export.name);
+    let export_name = format!(
+        "'{}'",
+        export.name.replace('\\', "\\\\").replace('\'', "\\'")
+    );
+
+    let (payload, call_options) = match export.codec {
+        None => {
+            let payload = if names.is_empty() {
+                "undefined".to_owned()
+            } else {
+                format!("[{}]", names.join(", "))
+            };
+            (payload, options.to_owned())
+        }
+        Some(codec) => {
+            let payload = match (export.params.as_slice(), names.as_slice()) {
+                ([], _) => "undefined".to_owned(),
+                // A single parameter receives the payload as a whole
+                ([_], [name]) => name.clone(),
+                // Several parameters are looked up by name
+                (params, names) => {
+                    let fields: Vec<_> = params
+                        .iter()
+                        .zip(names)
+                        .map(|((field, _), name)| {
+                            let field = field.strip_prefix("r#").unwrap_or(field);
+                            if field == name {
+                                name.clone()
+                            } else {
+                                format!("{}: {name}", property_key(field))
+                            }
+                        })
+                        .collect();
+                    format!("{{ {} }}", fields.join(", "))
+                }
+            };
+            (
+                payload,
+                format!("{{ ...{options}, codec: '{}' }}", codec.name()),
+            )
+        }
+    };
+
+    // `Err` is reported as an APP_ERROR, the promise resolves to `T`
+    let (output, error) = match &export.output {
+        Some(ty) if !export.stream => match types::result_types(ty) {
+            Some((ok, err)) => {
+                let ok = match ok {
+                    syn::Type::Tuple(tuple) if tuple.elems.is_empty() => None,
+                    ok => Some(ok.clone()),
+                };
+                // Direct exports send the error as text unless given a codec
+                let details = match (export.codec, export.error) {
+                    (None, None) => "string".to_owned(),
+                    _ => serde.ts(err),
+                };
+                (ok, Some(details))
+            }
+            None => (Some(ty.clone()), None),
+        },
+        output => (output.clone(), None),
+    };
+
+    let (result, body) = if export.stream {
+        let item = match (&export.output, export.codec) {
+            (None, _) => "unknown".to_owned(),
+            (Some(ty), codec) => {
+                let item = types::iterator_item(ty)
+                    .ok_or_else(|| unsupported("stream exports must return an iterator".into()))?;
+                match codec {
+                    Some(_) => serde.ts(item),
+                    None => types::direct_return(item)
+                        .ok_or_else(|| unsupported("unsupported stream item type".into()))?
+                        .to_owned(),
+                }
+            }
+        };
+        (
+            format!("AsyncIterable<{item}>"),
+            format!("target.stream<unknown, {item}>({export_name}, {payload}, {call_options})"),
+        )
+    } else {
+        let ts = match (&output, export.codec) {
+            (None, _) => "void".to_owned(),
+            (Some(ty), Some(_)) => serde.ts(ty),
+            (Some(ty), None) => types::direct_return(ty)
+                .ok_or_else(|| unsupported("unsupported return type".into()))?
+                .to_owned(),
+        };
+        (
+            format!("Promise<{ts}>"),
+            format!("target.call<unknown, {ts}>({export_name}, {payload}, {call_options})"),
+        )
+    };
+
+    Ok(Method {
+        signature: format!("{key}({}): {result}", typed.join(", ")),
+        binding: format!("{key}: ({}) =>\n    {body}", untyped.join(", ")),
+        error,
+    })
+}
+
+fn emit(krate: &Crate, options: &Options) -> Result<String, Error> {
+    let mut serde = SerdeTypes::new(krate);
+    let methods = krate
+        .exports
+        .iter()
+        .map(|export| method(export, &mut serde))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Declaring a type may reach more of them
+    let mut declarations = Vec::new();
+    let mut declared = 0;
+    while declared < serde.reached.len() {
+        let name = serde.reached[declared].clone();
+        declarations.push(serde.declare(&name));
+        declared += 1;
+    }
+
+    let mut out = String::new();
+    out.push_str("// Generated by wasmworker-bindgen, do not edit.\n\n");
+    out.push_str(&format!(
+        "import type {{ Bindings, CallOptions }} from '{}';\n\n",
+        options.sdk
+    ));
+
+    for declaration in declarations {
+        out.push_str(&declaration);
+        out.push('\n');
+    }
+
+    out.push_str("export interface Api {\n");
+    for (export, method) in krate.exports.iter().zip(&methods) {
+        let mut docs = export.docs.clone();
+        if let Some(error) = &method.error {
+            if docs.iter().any(|line| !line.trim().is_empty()) {
+                docs.push(String::new());
+            }
+            docs.push(format!(
+                "@throws WasmWorkerError with code `APP_ERROR` and `details` of type `{error}`"
+            ));
+        }
+        types::push_docs(&mut out, &docs, "  ");
+        out.push_str(&format!("  {};\n", method.signature));
+    }
+    out.push_str("}\n\n");
+
+    out.push_str("export const bindings: Bindings<Api> = (target) => ({\n");
+    for method in &methods {
+        out.push_str(&format!("  {},\n", method.binding));
+    }
+    out.push_str("});\n");
+
+    Ok(out)
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::{env, fs};
+
+use wasmworker_bindgen::Options;
+
+const USAGE: &str = "\
+Generate typed TypeScript bindings for a wasmworker guest crate
+
+Usage: wasmworker-bindgen <CRATE> [-o <FILE>] [--sdk <MODULE>]
+
+Arguments:
+  <CRATE>           Crate directory, or the root source file of the crate
+
+Options:
+  -o, --out <FILE>  Write the bindings to FILE instead of stdout
+      --sdk <MODULE>  Module to import SDK types from [default: @wasmworker/sdk]
+  -h, --help        Print this help";
+
+struct Args {
+    root: PathBuf,
+    out: Option<PathBuf>,
+    options: Options,
+}
+
+fn parse_args() -> Result<Option<Args>, String> {
+    let mut root = None;
+    let mut out = None;
+    let mut options = Options::default();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .ok_or_else(|| format!("missing value for `{flag}`"))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-o" | "--out" => out = Some(PathBuf::from(value(&arg)?)),
+            "--sdk" => options.sdk = value(&arg)?,
+            flag if flag.starts_with('-') => return Err(format!("unknown option `{flag}`")),
+            _ if root.is_some() => return Err(format!("unexpected argument `{arg}`")),
+            _ => root = Some(PathBuf::from(arg)),
+        }
+    }
+
+    let root = root.ok_or("missing <CRATE> argument")?;
+    // A crate directory is read from its library root
+    let root = if root.is_dir() {
+        root.join("src").join("lib.rs")
+    } else {
+        root
+    };
+    Ok(Some(Args { root, out, options }))
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let bindings = match wasmworker_bindgen::generate(&args.root, &args.options) {
+        Ok(bindings) => bindings,
+        Err(error) => {
+            eprintln!("error: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match args.out {
+        Some(path) => {
+            if let Err(error) = fs::write(&path, bindings) {
+                eprintln!("error: failed to write {}: {error}", path.display());
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{bindings}"),
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use syn::{Attribute, Expr, FnArg, Item, ItemFn, Lit, LitStr, Meta, Pat, ReturnType, Type};
+
+use crate::Error;
+
+/// Payload codecs selectable with `codec = "..."`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Codec {
+    Json,
+    MessagePack,
+}
+
+impl Codec {
+    /// The value of `CallOptions.codec` selecting this codec.
+    pub fn name(self) -> &'static str {
+        match self {
+            Codec::Json => "json",
+            Codec::MessagePack => "msgpack",
+        }
+    }
+}
+
+/// A function annotated with `#[wasmworker::export]`.
+pub struct Export {
+    /// Name of the rust function.
+    pub function: String,
+    /// Name of the wasm export.
+    pub name: String,
+    pub docs: Vec<String>,
+    pub params: Vec<(String, Type)>,
+    /// Return type, `None` for `()`.
+    pub output: Option<Type>,
+    pub codec: Option<Codec>,
+    /// Codec of the `Err` of a direct export, sent as text without one.
+    pub error: Option<Codec>,
+    pub stream: bool,
+}
+
+/// A type definition that codec payloads may refer to.
+pub enum TypeDef {
+    Struct(syn::ItemStruct),
+    Enum(syn::ItemEnum),
+}
+
+/// Exports and type definitions found in a guest crate.
+#[derive(Default)]
+pub struct Crate {
+    pub exports: Vec<Export>,
+    pub types: HashMap<String, TypeDef>,
+}
+
+impl Crate {
+    /// Read the crate rooted at `root`, following `mod name;` declarations.
+    pub fn load(root: &Path) -> Result<Self, Error> {
+        let mut krate = Crate::default();
+        krate.load_file(root, &module_dir(root))?;
+        Ok(krate)
+    }
+
+    /// Read a single source file. Out-of-line modules are skipped.
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        let file = syn::parse_file(source).map_err(|source| Error::Parse {
+            path: PathBuf::from("<source>"),
+            source,
+        })?;
+        let mut krate = Crate::default();
+        krate.collect(file.items, None)?;
+        Ok(krate)
+    }
+
+    fn load_file(&mut self, path: &Path, dir: &Path) -> Result<(), Error> {
+        let source = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        let file = syn::parse_file(&source).map_err(|source| Error::Parse {
+            path: path.to_owned(),
+            source,
+        })?;
+        self.collect(file.items, Some(dir))
+    }
+
+    /// Collect exports and types from `items`, whose out-of-line modules
+    /// live in `dir`.
+    fn collect(&mut self, items: Vec<Item>, dir: Option<&Path>) -> Result<(), Error> {
+        for item in items {
+            match item {
+                Item::Fn(func) => {
+                    if let Some(export) = Export::from_fn(&func)? {
+                        self.exports.push(export);
+                    }
+                }
+                Item::Struct(item) => {
+                    self.types
+                        .insert(item.ident.to_string(), TypeDef::Struct(item));
+                }
+                Item::Enum(item) => {
+                    self.types
+                        .insert(item.ident.to_string(), TypeDef::Enum(item));
+                }
+                Item::Mod(module) => {
+                    let name = module.ident.to_string();
+                    let path_attr = path_attribute(&module.attrs);
+                    match (module.content, dir) {
+                        (Some((_, items)), dir) => {
+                            let dir =
+                                dir.map(|dir| dir.join(path_attr.as_deref().unwrap_or(&name)));
+                            self.collect(items, dir.as_deref())?;
+                        }
+                        (None, Some(dir)) => {
+                            let path = match path_attr {
+                                Some(path) => dir.join(path),
+                                None => {
+                                    let file = dir.join(format!("{name}.rs"));
+                                    if file.exists() {
+                                        file
+                                    } else {
+                                        dir.join(&name).join("mod.rs")
+                                    }
+                                }
+                            };
+                            self.load_file(&path, &module_dir(&path))?;
+                        }
+                        (None, None) => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Directory holding the out-of-line modules declared in the file at `path`.
+fn module_dir(path: &Path) -> PathBuf {
+    let parent = path.parent().unwrap_or(Path::new(""));
+    match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some("lib" | "main" | "mod") | None => parent.to_owned(),
+        Some(stem) => parent.join(stem),
+    }
+}
+
+/// The value of a `#[path = "..."]` attribute.
+fn path_attribute(attrs: &[Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| match &attr.meta {
+        Meta::NameValue(meta) if meta.path.is_ident("path") => match &meta.value {
+            Expr::Lit(expr) => match &expr.lit {
+                Lit::Str(lit) => Some(lit.value()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// The lines of the doc comments in `attrs`.
+pub fn docs(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+                Expr::Lit(expr) => match &expr.lit {
+                    Lit::Str(lit) => Some(lit.value()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .flat_map(|doc| {
+            // `split` rather than `lines`, so an empty `///` stays a blank line
+            doc.split('\n')
+                .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Whether `attr` is `#[wasmworker::export]`, with or without arguments.
+fn is_export(attr: &Attribute) -> bool {
+    let segments: Vec<_> = attr
+        .path()
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    segments == ["wasmworker", "export"]
+}
+
+impl Export {
+    fn from_fn(func: &ItemFn) -> Result<Option<Self>, Error> {
+        let Some(attr) = func.attrs.iter().find(|attr| is_export(attr)) else {
+            return Ok(None);
+        };
+        let function = func.sig.ident.to_string();
+        let unsupported = |message: String| Error::Unsupported {
+            function: function.clone(),
+            message,
+        };
+
+        let mut name = None;
+        let mut codec = None;
+        let mut error = None;
+        let mut stream = false;
+        if let Meta::List(_) = &attr.meta {
+            attr.parse_nested_meta(|meta| {
+                let parse_codec = || {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    match lit.value().as_str() {
+                        "json" => Ok(Codec::Json),
+                        "msgpack" => Ok(Codec::MessagePack),
+                        _ => Err(meta.error("unknown codec")),
+                    }
+                };
+                if meta.path.is_ident("name") {
+                    name = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("codec") {
+                    codec = Some(parse_codec()?);
+                } else if meta.path.is_ident("error") {
+                    error = Some(parse_codec()?);
+                } else if meta.path.is_ident("stream") {
+                    stream = true;
+                } else {
+                    return Err(meta.error("unsupported argument"));
+                }
+                Ok(())
+            })
+            .map_err(|error| unsupported(error.to_string()))?;
+        }
+
+        let params = func
+            .sig
+            .inputs
+            .iter()
+            .map(|arg| match arg {
+                FnArg::Typed(pat_type) => match &*pat_type.pat {
+                    Pat::Ident(pat) => Ok((pat.ident.to_string(), (*pat_type.ty).clone())),
+                    _ => Err(unsupported("parameters must be plain identifiers".into())),
+                },
+                FnArg::Receiver(_) => Err(unsupported("methods cannot be exported".into())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let output = match &func.sig.output {
+            ReturnType::Type(_, ty) if !matches!(&**ty, Type::Tuple(tuple) if tuple.elems.is_empty()) => {
+                Some((**ty).clone())
+            }
+            _ => None,
+        };
+
+        Ok(Some(Export {
+            name: name.unwrap_or_else(|| function.clone()),
+            function,
+            docs: docs(&func.attrs),
+            params,
+            output,
+            codec,
+            error,
+            stream,
+        }))
+    }
+}
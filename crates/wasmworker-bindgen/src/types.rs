@@ -0,0 +1,320 @@
+use syn::{Fields, GenericArgument, PathArguments, Type, TypeParamBound};
+
+use crate::parse::{docs, Crate, TypeDef};
+
+/// The identifier of a single-segment path type such as `u32`.
+fn simple_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() && path.path.segments.len() == 1 => {
+            let segment = &path.path.segments[0];
+            segment
+                .arguments
+                .is_none()
+                .then(|| segment.ident.to_string())
+        }
+        Type::Paren(inner) => simple_name(&inner.elem),
+        Type::Group(inner) => simple_name(&inner.elem),
+        _ => None,
+    }
+}
+
+/// The type arguments of the last path segment, lifetimes left out.
+fn type_args(path: &syn::Path) -> Vec<&Type> {
+    match path.segments.last().map(|segment| &segment.arguments) {
+        Some(PathArguments::AngleBracketed(args)) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    simple_name(ty).as_deref() == Some("u8")
+}
+
+/// Whether `ty` is `&[u8]` or `Vec<u8>`.
+fn is_bytes(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) => {
+            matches!(&*reference.elem, Type::Slice(slice) if is_u8(&slice.elem))
+        }
+        Type::Path(path) => {
+            path.path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Vec")
+                && matches!(type_args(&path.path).as_slice(), [elem] if is_u8(elem))
+        }
+        _ => false,
+    }
+}
+
+/// Whether `ty` is `&str` or `String`.
+fn is_string(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) => simple_name(&reference.elem).as_deref() == Some("str"),
+        _ => simple_name(ty).as_deref() == Some("String"),
+    }
+}
+
+/// The TypeScript type of a direct export parameter.
+pub fn direct_param(ty: &Type) -> Option<&'static str> {
+    if is_bytes(ty) {
+        return Some("Uint8Array | ArrayBuffer");
+    }
+    if is_string(ty) {
+        return Some("string");
+    }
+    match simple_name(ty)?.as_str() {
+        "i32" | "u32" | "f32" | "f64" => Some("number"),
+        "i64" | "u64" => Some("bigint"),
+        "bool" => Some("boolean"),
+        _ => None,
+    }
+}
+
+/// The TypeScript type of a direct export's return value or stream item.
+///
+/// The runtime reads unsigned values and booleans as their Rust type, as it
+/// does for stream chunks, which are tagged with their kind.
+pub fn direct_return(ty: &Type) -> Option<&'static str> {
+    if is_bytes(ty) {
+        return Some("Uint8Array");
+    }
+    if is_string(ty) {
+        return Some("string");
+    }
+    match simple_name(ty)?.as_str() {
+        "i32" | "u32" | "f32" | "f64" => Some("number"),
+        "i64" | "u64" => Some("bigint"),
+        "bool" => Some("boolean"),
+        _ => None,
+    }
+}
+
+/// The `T` and `E` of a `Result<T, E>` returned by an export.
+pub fn result_types(ty: &Type) -> Option<(&Type, &Type)> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    match (segment.ident == "Result", type_args(&path.path).as_slice()) {
+        (true, [ok, err]) => Some((ok, err)),
+        _ => None,
+    }
+}
+
+/// The item type of an iterator returned by a stream export.
+pub fn iterator_item(ty: &Type) -> Option<&Type> {
+    let bounds = match ty {
+        Type::ImplTrait(ty) => &ty.bounds,
+        Type::TraitObject(ty) => &ty.bounds,
+        Type::Paren(inner) => return iterator_item(&inner.elem),
+        Type::Group(inner) => return iterator_item(&inner.elem),
+        Type::Array(array) => return Some(&array.elem),
+        Type::Path(path) => {
+            let segment = path.path.segments.last()?;
+            let args = type_args(&path.path);
+            return match (segment.ident.to_string().as_str(), args.as_slice()) {
+                ("Box", [inner]) => iterator_item(inner),
+                ("Vec" | "VecDeque" | "Option" | "HashSet" | "BTreeSet", [item]) => Some(item),
+                _ => None,
+            };
+        }
+        _ => return None,
+    };
+
+    bounds.iter().find_map(|bound| {
+        let TypeParamBound::Trait(bound) = bound else {
+            return None;
+        };
+        let PathArguments::AngleBracketed(args) = &bound.path.segments.last()?.arguments else {
+            return None;
+        };
+        args.args.iter().find_map(|arg| match arg {
+            GenericArgument::AssocType(assoc) if assoc.ident == "Item" => Some(&assoc.ty),
+            _ => None,
+        })
+    })
+}
+
+/// Maps serde types used by codec exports to TypeScript, collecting the
+/// crate's own types they refer to.
+pub struct SerdeTypes<'a> {
+    krate: &'a Crate,
+    /// Names of the crate types referenced so far, in order.
+    pub reached: Vec<String>,
+}
+
+impl<'a> SerdeTypes<'a> {
+    pub fn new(krate: &'a Crate) -> Self {
+        SerdeTypes {
+            krate,
+            reached: Vec::new(),
+        }
+    }
+
+    /// The TypeScript type of the serde representation of `ty`.
+    pub fn ts(&mut self, ty: &Type) -> String {
+        match ty {
+            Type::Paren(inner) => self.ts(&inner.elem),
+            Type::Group(inner) => self.ts(&inner.elem),
+            Type::Reference(reference) => self.ts(&reference.elem),
+            Type::Slice(slice) => self.array(&slice.elem),
+            Type::Array(array) => self.array(&array.elem),
+            Type::Tuple(tuple) if tuple.elems.is_empty() => "null".into(),
+            Type::Tuple(tuple) => {
+                let elems: Vec<_> = tuple.elems.iter().map(|elem| self.ts(elem)).collect();
+                format!("[{}]", elems.join(", "))
+            }
+            Type::Path(path) if path.qself.is_none() => self.path(&path.path),
+            _ => "unknown".into(),
+        }
+    }
+
+    fn array(&mut self, elem: &Type) -> String {
+        let elem = self.ts(elem);
+        if elem.contains(' ') {
+            format!("({elem})[]")
+        } else {
+            format!("{elem}[]")
+        }
+    }
+
+    fn path(&mut self, path: &syn::Path) -> String {
+        let Some(segment) = path.segments.last() else {
+            return "unknown".into();
+        };
+        let name = segment.ident.to_string();
+        let args = type_args(path);
+        match (name.as_str(), args.as_slice()) {
+            ("bool", []) => "boolean".into(),
+            (
+                "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize"
+                | "f32" | "f64",
+                [],
+            ) => "number".into(),
+            ("char" | "str" | "String", []) => "string".into(),
+            ("Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet", [elem]) => {
+                self.array(elem)
+            }
+            ("Option", [inner]) => format!("{} | null", self.ts(inner)),
+            ("Box" | "Rc" | "Arc" | "Cow", [inner]) => self.ts(inner),
+            ("HashMap" | "BTreeMap", [_, value]) => format!("Record<string, {}>", self.ts(value)),
+            (_, []) if self.krate.types.contains_key(&name) => {
+                if !self.reached.contains(&name) {
+                    self.reached.push(name.clone());
+                }
+                name
+            }
+            _ => "unknown".into(),
+        }
+    }
+
+    /// The declaration of the crate type `name`, which may reach more types.
+    pub fn declare(&mut self, name: &str) -> String {
+        let mut out = String::new();
+        match &self.krate.types[name] {
+            TypeDef::Struct(item) => {
+                push_docs(&mut out, &docs(&item.attrs), "");
+                match &item.fields {
+                    Fields::Named(fields) => {
+                        out.push_str(&format!("export interface {name} {{\n"));
+                        for field in &fields.named {
+                            push_docs(&mut out, &docs(&field.attrs), "  ");
+                            let ident = field.ident.as_ref().expect("named field");
+                            let ty = self.ts(&field.ty);
+                            out.push_str(&format!("  {ident}: {ty};\n"));
+                        }
+                        out.push_str("}\n");
+                    }
+                    Fields::Unnamed(fields) => {
+                        let elems: Vec<_> = fields
+                            .unnamed
+                            .iter()
+                            .map(|field| self.ts(&field.ty))
+                            .collect();
+                        // A newtype is serialized as its inner value
+                        let ty = match elems.as_slice() {
+                            [inner] => inner.clone(),
+                            _ => format!("[{}]", elems.join(", ")),
+                        };
+                        out.push_str(&format!("export type {name} = {ty};\n"));
+                    }
+                    Fields::Unit => out.push_str(&format!("export type {name} = null;\n")),
+                }
+            }
+            TypeDef::Enum(item) => {
+                push_docs(&mut out, &docs(&item.attrs), "");
+                out.push_str(&format!("export type {name} =\n"));
+                // Externally tagged, serde's default representation
+                for variant in &item.variants {
+                    let tag = &variant.ident;
+                    let ty = match &variant.fields {
+                        Fields::Unit => format!("'{tag}'"),
+                        Fields::Unnamed(fields) => {
+                            let elems: Vec<_> = fields
+                                .unnamed
+                                .iter()
+                                .map(|field| self.ts(&field.ty))
+                                .collect();
+                            match elems.as_slice() {
+                                [inner] => format!("{{ {tag}: {inner} }}"),
+                                _ => format!("{{ {tag}: [{}] }}", elems.join(", ")),
+                            }
+                        }
+                        Fields::Named(fields) => {
+                            let fields: Vec<_> = fields
+                                .named
+                                .iter()
+                                .map(|field| {
+                                    let ident = field.ident.as_ref().expect("named field");
+                                    format!("{ident}: {}", self.ts(&field.ty))
+                                })
+                                .collect();
+                            format!("{{ {tag}: {{ {} }} }}", fields.join("; "))
+                        }
+                    };
+                    out.push_str(&format!("  | {ty}\n"));
+                }
+                if item.variants.is_empty() {
+                    out.push_str("  never\n");
+                }
+                out.pop();
+                out.push_str(";\n");
+            }
+        }
+        out
+    }
+}
+
+/// Append `docs` as a JSDoc comment indented by `indent`.
+pub fn push_docs(out: &mut String, docs: &[String], indent: &str) {
+    // Drop blank lines at either end, as rustdoc does
+    let start = docs.iter().position(|line| !line.trim().is_empty());
+    let end = docs.iter().rposition(|line| !line.trim().is_empty());
+    let (Some(start), Some(end)) = (start, end) else {
+        return;
+    };
+    let docs = &docs[start..=end];
+
+    if let [line] = docs {
+        out.push_str(&format!("{indent}/** {} */\n", line.replace("*/", "*\\/")));
+        return;
+    }
+    out.push_str(&format!("{indent}/**\n"));
+    for line in docs {
+        let line = line.replace("*/", "*\\/");
+        if line.is_empty() {
+            out.push_str(&format!("{indent} *\n"));
+        } else {
+            out.push_str(&format!("{indent} * {line}\n"));
+        }
+    }
+    out.push_str(&format!("{indent} */\n"));
+}
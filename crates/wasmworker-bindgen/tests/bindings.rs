@@ -0,0 +1,206 @@
+use std::fs;
+
+use wasmworker_bindgen::{generate, generate_from_source, Error, Options};
+
+fn bindings(source: &str) -> String {
+    generate_from_source(source, &Options::default()).unwrap()
+}
+
+#[test]
+fn direct_exports_get_positional_parameters() {
+    let ts = bindings(
+        r#"
+        #[wasmworker::export]
+        pub fn scale(data: &[u8], factor: f64, wide: i64) -> Vec<u8> { todo!() }
+
+        #[wasmworker::export(name = "greet-user")]
+        pub fn greet(name: &str) -> String { todo!() }
+
+        #[wasmworker::export]
+        pub fn reset() {}
+        "#,
+    );
+
+    assert!(ts.contains(
+        "  scale(data: Uint8Array | ArrayBuffer, factor: number, wide: bigint, options?: CallOptions): Promise<Uint8Array>;\n"
+    ));
+    assert!(ts.contains("target.call<unknown, Uint8Array>('scale', [data, factor, wide], options)"));
+    assert!(ts.contains("  'greet-user'(name: string, options?: CallOptions): Promise<string>;\n"));
+    assert!(ts.contains("target.call<unknown, string>('greet-user', [name], options)"));
+    assert!(ts.contains("  reset(options?: CallOptions): Promise<void>;\n"));
+    assert!(ts.contains("target.call<unknown, void>('reset', undefined, options)"));
+}
+
+#[test]
+fn unsigned_and_bool_returns_are_typed_as_is() {
+    let ts = bindings(
+        r#"
+        #[wasmworker::export]
+        pub fn fib(n: u32) -> u64 { todo!() }
+
+        #[wasmworker::export]
+        pub fn checksum(data: &[u8]) -> u32 { todo!() }
+
+        #[wasmworker::export]
+        pub fn is_even(n: i32) -> bool { todo!() }
+        "#,
+    );
+
+    assert!(ts.contains("  fib(n: number, options?: CallOptions): Promise<bigint>;\n"));
+    assert!(ts.contains("target.call<unknown, bigint>('fib', [n], options),"));
+    assert!(ts.contains("target.call<unknown, number>('checksum', [data], options),"));
+    assert!(ts.contains("  is_even(n: number, options?: CallOptions): Promise<boolean>;\n"));
+    assert!(ts.contains("target.call<unknown, boolean>('is_even', [n], options),"));
+}
+
+#[test]
+fn codec_exports_declare_their_types() {
+    let ts = bindings(
+        r#"
+        use std::collections::HashMap;
+
+        /// A point
+        #[derive(Deserialize)]
+        pub struct Point { x: f64, y: f64 }
+
+        #[derive(Serialize)]
+        pub struct Id(u32);
+
+        #[derive(Serialize)]
+        pub enum Shape {
+            Empty,
+            Circle { radius: f64 },
+            Polygon(Vec<Point>),
+        }
+
+        #[wasmworker::export(codec = "json")]
+        pub fn classify(points: Vec<Point>, labels: Option<HashMap<String, Id>>) -> Shape { todo!() }
+
+        #[wasmworker::export(codec = "msgpack")]
+        pub fn centroid(points: Vec<Point>) -> (f64, f64) { todo!() }
+        "#,
+    );
+
+    assert!(
+        ts.contains("/** A point */\nexport interface Point {\n  x: number;\n  y: number;\n}\n")
+    );
+    assert!(ts.contains("export type Id = number;\n"));
+    assert!(ts.contains(
+        "export type Shape =\n  | 'Empty'\n  | { Circle: { radius: number } }\n  | { Polygon: Point[] };\n"
+    ));
+    assert!(ts.contains(
+        "  classify(points: Point[], labels: Record<string, Id> | null, options?: CallOptions): Promise<Shape>;\n"
+    ));
+    // Several parameters are sent as an object
+    assert!(ts.contains(
+        "target.call<unknown, Shape>('classify', { points, labels }, { ...options, codec: 'json' })"
+    ));
+    assert!(ts.contains(
+        "target.call<unknown, [number, number]>('centroid', points, { ...options, codec: 'msgpack' })"
+    ));
+}
+
+#[test]
+fn stream_exports_return_async_iterables() {
+    let ts = bindings(
+        r#"
+        #[wasmworker::export(stream)]
+        pub fn count(n: u32) -> impl Iterator<Item = u64> { 0..n as u64 }
+
+        #[wasmworker::export(stream, codec = "json")]
+        pub fn words(text: String) -> Vec<String> { todo!() }
+        "#,
+    );
+
+    assert!(ts.contains("  count(n: number, options?: CallOptions): AsyncIterable<bigint>;\n"));
+    assert!(ts.contains("target.stream<unknown, bigint>('count', [n], options)"));
+    assert!(ts.contains("  words(text: string, options?: CallOptions): AsyncIterable<string>;\n"));
+}
+
+#[test]
+fn reserved_parameter_names_are_renamed() {
+    let ts = bindings(
+        r#"
+        #[wasmworker::export]
+        pub fn pick(default: i32, options: i32) -> i32 { todo!() }
+        "#,
+    );
+
+    assert!(ts.contains(
+        "  pick(default_: number, options: number, callOptions?: CallOptions): Promise<number>;\n"
+    ));
+    assert!(ts.contains(
+        "pick: (default_, options, callOptions) =>\n    target.call<unknown, number>('pick', [default_, options], callOptions)"
+    ));
+}
+
+#[test]
+fn unsupported_signatures_are_reported() {
+    let error = generate_from_source(
+        r#"
+        #[wasmworker::export]
+        pub fn sum(values: Vec<i32>) -> i32 { todo!() }
+        "#,
+        &Options::default(),
+    )
+    .unwrap_err();
+
+    assert!(matches!(error, Error::Unsupported { ref function, .. } if function == "sum"));
+}
+
+#[test]
+fn modules_are_read_from_their_files() {
+    let dir = std::env::temp_dir().join(format!("wasmworker-bindgen-{}", std::process::id()));
+    fs::create_dir_all(dir.join("math")).unwrap();
+    fs::write(dir.join("lib.rs"), "mod math;\n").unwrap();
+    fs::write(dir.join("math.rs"), "mod ops;\n").unwrap();
+    fs::write(
+        dir.join("math").join("ops.rs"),
+        "#[wasmworker::export]\npub fn add(a: i32, b: i32) -> i32 { a + b }\n",
+    )
+    .unwrap();
+
+    let ts = generate(
+        &dir.join("lib.rs"),
+        &Options {
+            sdk: "../sdk".into(),
+        },
+    );
+    fs::remove_dir_all(&dir).unwrap();
+
+    let ts = ts.unwrap();
+    assert!(ts.contains("import type { Bindings, CallOptions } from '../sdk';\n"));
+    assert!(ts.contains("  add(a: number, b: number, options?: CallOptions): Promise<number>;\n"));
+}
+
+#[test]
+fn result_exports_resolve_to_the_ok_type() {
+    let ts = bindings(
+        r#"
+        #[derive(Serialize)]
+        pub enum MathError { DivisionByZero }
+
+        /// Divide two integers
+        #[wasmworker::export(error = "json")]
+        pub fn checked_div(a: i32, b: i32) -> Result<u32, MathError> { todo!() }
+
+        #[wasmworker::export]
+        pub fn parse_port(text: &str) -> Result<u32, ParseIntError> { todo!() }
+
+        #[wasmworker::export(codec = "json")]
+        pub fn save(name: String) -> Result<(), String> { todo!() }
+        "#,
+    );
+
+    assert!(ts.contains("export type MathError =\n  | 'DivisionByZero';\n"));
+    assert!(ts.contains(
+        "  /**\n   * Divide two integers\n   *\n   * @throws WasmWorkerError with code `APP_ERROR` and `details` of type `MathError`\n   */\n  checked_div(a: number, b: number, options?: CallOptions): Promise<number>;\n"
+    ));
+    assert!(ts.contains("target.call<unknown, number>('checked_div', [a, b], options),"));
+    assert!(ts.contains(
+        "  /** @throws WasmWorkerError with code `APP_ERROR` and `details` of type `string` */\n  parse_port(text: string, options?: CallOptions): Promise<number>;\n"
+    ));
+    assert!(ts.contains(
+        "  /** @throws WasmWorkerError with code `APP_ERROR` and `details` of type `string` */\n  save(name: string, options?: CallOptions): Promise<void>;\n"
+    ));
+}
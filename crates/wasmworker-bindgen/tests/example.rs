@@ -0,0 +1,14 @@
+use std::path::Path;
+
+use wasmworker_bindgen::{generate, Options};
+
+/// The bindings checked in next to the example must match its exports.
+/// Regenerate them with `examples/rust-add/build.sh` after changing them.
+#[test]
+fn example_bindings_are_up_to_date() {
+    let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/rust-add");
+    let generated = generate(&example.join("src/lib.rs"), &Options::default()).unwrap();
+    let checked_in = std::fs::read_to_string(example.join("bindings.ts")).unwrap();
+
+    assert_eq!(generated, checked_in);
+}
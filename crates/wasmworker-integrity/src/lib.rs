@@ -0,0 +1,73 @@
+//! Compute the Subresource Integrity digest of a compiled module.
+//!
+//! The runtime verifies `LoadOptions.integrity` against the module's bytes
+//! before compiling it. The digest uses the SRI format, the algorithm name
+//! followed by the base64 encoded hash:
+//!
+//! ```
+//! use wasmworker_integrity::{integrity, Algorithm};
+//!
+//! assert_eq!(
+//!     integrity(b"abc", Algorithm::Sha256),
+//!     "sha256-ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="
+//! );
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// A hash algorithm supported by the runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    Sha256,
+    /// Recommended by the SRI specification.
+    #[default]
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    /// Name used as the prefix of the digest.
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Sha384 => "sha384",
+            Algorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "sha256" => Ok(Algorithm::Sha256),
+            "sha384" => Ok(Algorithm::Sha384),
+            "sha512" => Ok(Algorithm::Sha512),
+            _ => Err(format!(
+                "unknown algorithm `{name}`, expected sha256, sha384 or sha512"
+            )),
+        }
+    }
+}
+
+/// The SRI digest of `bytes`, e.g. `sha384-<base64>`.
+pub fn integrity(bytes: &[u8], algorithm: Algorithm) -> String {
+    let hash = match algorithm {
+        Algorithm::Sha256 => Sha256::digest(bytes).to_vec(),
+        Algorithm::Sha384 => Sha384::digest(bytes).to_vec(),
+        Algorithm::Sha512 => Sha512::digest(bytes).to_vec(),
+    };
+    format!("{algorithm}-{}", STANDARD.encode(hash))
+}
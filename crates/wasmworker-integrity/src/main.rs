@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::{env, fs};
+
+use wasmworker_integrity::{integrity, Algorithm};
+
+const USAGE: &str = "\
+Print the Subresource Integrity digest of a built module, for `LoadOptions.integrity`
+
+Usage: wasmworker-integrity <FILE> [-a <ALGORITHM>]
+
+Arguments:
+  <FILE>                   Compiled module, e.g. dist/module.wasm
+
+Options:
+  -a, --algorithm <NAME>   sha256, sha384 or sha512 [default: sha384]
+  -h, --help               Print this help";
+
+struct Args {
+    path: PathBuf,
+    algorithm: Algorithm,
+}
+
+fn parse_args() -> Result<Option<Args>, String> {
+    let mut path = None;
+    let mut algorithm = Algorithm::default();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-a" | "--algorithm" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for `{arg}`"))?;
+                algorithm = name.parse()?;
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown option `{flag}`")),
+            _ if path.is_some() => return Err(format!("unexpected argument `{arg}`")),
+            _ => path = Some(PathBuf::from(arg)),
+        }
+    }
+
+    let path = path.ok_or("missing <FILE> argument")?;
+    Ok(Some(Args { path, algorithm }))
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match fs::read(&args.path) {
+        Ok(bytes) => {
+            println!("{}", integrity(&bytes, args.algorithm));
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: failed to read {}: {error}", args.path.display());
+            ExitCode::FAILURE
+        }
+    }
+}
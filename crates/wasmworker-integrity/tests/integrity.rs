@@ -0,0 +1,25 @@
+use wasmworker_integrity::{integrity, Algorithm};
+
+#[test]
+fn digests_use_the_sri_format() {
+    assert_eq!(
+        integrity(b"abc", Algorithm::Sha256),
+        "sha256-ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="
+    );
+    assert_eq!(
+        integrity(b"abc", Algorithm::Sha384),
+        "sha384-ywB1P0WjXou1oD1pmsZQBycsMqsO3tFjGotgWkP/W+2AhgcroefMI1i67KE0yCWn"
+    );
+    assert_eq!(
+        integrity(b"abc", Algorithm::Sha512),
+        "sha512-3a81oZNherrMQXNJriBBMRLm+k6JqX6iCp7u5ktV05ohkpkqJ0/BqDa6PCOj/uu9RU1EI2Q86A4qmslPpUyknw=="
+    );
+}
+
+#[test]
+fn algorithms_parse_from_their_names() {
+    assert_eq!("sha256".parse(), Ok(Algorithm::Sha256));
+    assert_eq!("sha384".parse(), Ok(Algorithm::Sha384));
+    assert_eq!(Algorithm::default(), Algorithm::Sha384);
+    assert!("md5".parse::<Algorithm>().is_err());
+}
@@ -0,0 +1,421 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{FnArg, Ident, ItemFn, LitStr, Pat, PatType, ReturnType, Type};
+
+use crate::manifest;
+use crate::types::{result_types, WireType};
+
+/// Payload codecs selectable with `codec = "..."`.
+enum Codec {
+    Json,
+    MessagePack,
+}
+
+impl Codec {
+    fn parse(lit: &LitStr) -> syn::Result<Self> {
+        match lit.value().as_str() {
+            "json" => Ok(Codec::Json),
+            "msgpack" => Ok(Codec::MessagePack),
+            _ => Err(syn::Error::new_spanned(
+                lit,
+                "unknown codec, expected \"json\" or \"msgpack\"",
+            )),
+        }
+    }
+
+    /// The name used for this codec in `codec = "..."` and `CallOptions`.
+    fn name(&self) -> &'static str {
+        match self {
+            Codec::Json => "json",
+            Codec::MessagePack => "msgpack",
+        }
+    }
+
+    /// Path to the codec type in the `wasmworker` crate.
+    fn path(&self) -> TokenStream {
+        match self {
+            Codec::Json => quote!(::wasmworker::codec::Json),
+            Codec::MessagePack => quote!(::wasmworker::codec::MessagePack),
+        }
+    }
+}
+
+/// Arguments accepted by `#[wasmworker::export(...)]`.
+#[derive(Default)]
+struct ExportArgs {
+    /// Name of the wasm export, defaults to the function name.
+    name: Option<LitStr>,
+    /// Codec used to decode the payload and encode the return value.
+    codec: Option<Codec>,
+    /// Codec used to encode the `Err` of a direct export, which is sent as
+    /// its `Display` text otherwise.
+    error: Option<Codec>,
+    /// Whether the export streams the items of its return value.
+    stream: bool,
+}
+
+impl ExportArgs {
+    fn parse(attr: TokenStream) -> syn::Result<Self> {
+        let mut args = ExportArgs::default();
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("name") {
+                args.name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("codec") {
+                args.codec = Some(Codec::parse(&meta.value()?.parse()?)?);
+                Ok(())
+            } else if meta.path.is_ident("error") {
+                args.error = Some(Codec::parse(&meta.value()?.parse()?)?);
+                Ok(())
+            } else if meta.path.is_ident("stream") {
+                args.stream = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported #[wasmworker::export] argument"))
+            }
+        });
+        syn::parse::Parser::parse2(parser, attr)?;
+        if args.error.is_some() && (args.codec.is_some() || args.stream) {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "`error = \"...\"` only applies to direct exports returning `Result`; \
+                 codec exports encode errors with their codec",
+            ));
+        }
+        Ok(args)
+    }
+}
+
+/// A single parameter of the exported function.
+struct Param {
+    ident: Ident,
+    wire: WireType,
+}
+
+pub fn expand(attr: TokenStream, func: ItemFn) -> syn::Result<TokenStream> {
+    let args = ExportArgs::parse(attr)?;
+    validate_signature(&func)?;
+
+    let fn_ident = &func.sig.ident;
+    let export_name = args
+        .name
+        .map(|name| name.value())
+        .unwrap_or_else(|| fn_ident.to_string());
+    let wrapper_ident = format_ident!("__wasmworker_export_{}", fn_ident);
+
+    let wrapper = match &args.codec {
+        None => expand_direct(
+            &func,
+            args.stream,
+            args.error.as_ref(),
+            &export_name,
+            &wrapper_ident,
+        )?,
+        Some(codec) => expand_codec(&func, codec, args.stream, &export_name, &wrapper_ident)?,
+    };
+    let manifest = manifest::expand(
+        &func,
+        &export_name,
+        args.codec.as_ref().map(Codec::name),
+        args.stream,
+    );
+
+    Ok(quote! {
+        #func
+
+        #wrapper
+
+        #manifest
+    })
+}
+
+/// Generate a wrapper whose parameters map one-to-one onto wasm values.
+fn expand_direct(
+    func: &ItemFn,
+    stream: bool,
+    error_codec: Option<&Codec>,
+    export_name: &str,
+    wrapper_ident: &Ident,
+) -> syn::Result<TokenStream> {
+    let params = func
+        .sig
+        .inputs
+        .iter()
+        .map(|arg| {
+            let pat_type = typed_arg(arg)?;
+            let wire = WireType::classify(&pat_type.ty)?;
+            if let WireType::Unit = wire {
+                return Err(syn::Error::new_spanned(
+                    &pat_type.ty,
+                    "`()` is not a valid parameter type",
+                ));
+            }
+            Ok(Param {
+                ident: param_ident(pat_type)?,
+                wire,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let fn_ident = &func.sig.ident;
+    let abi_params = params
+        .iter()
+        .map(|param| param.wire.abi_params(&param.ident));
+    let lifted = params.iter().map(|param| param.wire.lift(&param.ident));
+    let call = quote!(#fn_ident(#(#lifted),*));
+
+    // Buffer pointers are produced by the runtime via `ww_alloc`, so the
+    // wrapper may dereference them even though it is a safe function.
+    let allow_ptr_deref = params
+        .iter()
+        .any(|param| param.wire.is_buffer())
+        .then(|| quote!(#[allow(clippy::not_unsafe_ptr_arg_deref)]));
+
+    if stream {
+        let body = stream_items(func, call, |item| quote!(::wasmworker::emit(#item)));
+        return Ok(quote! {
+            #[doc(hidden)]
+            #[export_name = #export_name]
+            #allow_ptr_deref
+            pub extern "C" fn #wrapper_ident(#(#abi_params),*) {
+                ::wasmworker::__private::install_panic_hook();
+                #body
+            }
+        });
+    }
+
+    // `Result<T, E>` returns `T` and reports `Err` through the result slot
+    let result = match &func.sig.output {
+        ReturnType::Type(_, ty) => result_types(ty),
+        ReturnType::Default => None,
+    };
+
+    let ret_ty = match &func.sig.output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, ty) => Some(result.map_or(&**ty, |(ok, _)| ok)),
+    };
+
+    let ret = match ret_ty {
+        None => WireType::Unit,
+        Some(ty) => {
+            let wire = WireType::classify(ty)?;
+            if !wire.is_returnable() {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "borrowed values cannot be returned from #[wasmworker::export] \
+                     functions; return String or Vec<u8> instead",
+                ));
+            }
+            wire
+        }
+    };
+
+    let abi_ret = ret.abi_return();
+    let body = match result {
+        Some(_) => {
+            let ok = ret.lower(quote!(value));
+            let default = ret.abi_default();
+            let set_error = match error_codec {
+                Some(codec) => {
+                    let codec = codec.path();
+                    quote!(::wasmworker::__private::set_encoded_error::<#codec, _>(&error))
+                }
+                None => quote!(::wasmworker::__private::set_app_error(&error)),
+            };
+            quote! {
+                match #call {
+                    ::std::result::Result::Ok(value) => #ok,
+                    ::std::result::Result::Err(error) => {
+                        #set_error;
+                        #default
+                    }
+                }
+            }
+        }
+        None if error_codec.is_some() => {
+            return Err(syn::Error::new_spanned(
+                &func.sig,
+                "`error = \"...\"` only applies to direct exports returning `Result`",
+            ));
+        }
+        None => ret.lower(call),
+    };
+
+    Ok(quote! {
+        #[doc(hidden)]
+        #[export_name = #export_name]
+        #allow_ptr_deref
+        pub extern "C" fn #wrapper_ident(#(#abi_params),*) -> #abi_ret {
+            ::wasmworker::__private::install_panic_hook();
+            #body
+        }
+    })
+}
+
+/// Generate a wrapper that decodes the whole payload with a codec.
+///
+/// The export takes the encoded payload as `(ptr, len)` and hands back its
+/// encoded return value through the result slot.
+fn expand_codec(
+    func: &ItemFn,
+    codec: &Codec,
+    stream: bool,
+    export_name: &str,
+    wrapper_ident: &Ident,
+) -> syn::Result<TokenStream> {
+    let params = func
+        .sig
+        .inputs
+        .iter()
+        .map(|arg| {
+            let pat_type = typed_arg(arg)?;
+            if let Type::Reference(reference) = &*pat_type.ty {
+                return Err(syn::Error::new_spanned(
+                    reference,
+                    "codec exports take owned parameters; use String or Vec<T> instead",
+                ));
+            }
+            Ok((param_ident(pat_type)?, &*pat_type.ty))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let fn_ident = &func.sig.ident;
+    let codec = codec.path();
+    let decode = quote! {
+        match ::wasmworker::__private::decode_payload::<#codec, _>(payload) {
+            ::std::option::Option::Some(value) => value,
+            ::std::option::Option::None => return,
+        }
+    };
+
+    let idents: Vec<_> = params.iter().map(|(ident, _)| ident).collect();
+    let decode_args = match params.as_slice() {
+        [] => quote!(let _ = payload;),
+        // A single parameter receives the payload as a whole.
+        [(ident, ty)] => quote!(let #ident: #ty = #decode;),
+        // Several parameters are looked up by name in a payload object.
+        params => {
+            let fields = params.iter().map(|(ident, ty)| quote!(#ident: #ty));
+            quote! {
+                #[derive(::wasmworker::__private::serde::Deserialize)]
+                #[serde(crate = "::wasmworker::__private::serde")]
+                struct __WasmWorkerArgs {
+                    #(#fields),*
+                }
+                let __WasmWorkerArgs { #(#idents),* } = #decode;
+            }
+        }
+    };
+
+    let call = quote!(#fn_ident(#(#idents),*));
+    let store_result = match &func.sig.output {
+        _ if stream => stream_items(
+            func,
+            call,
+            |item| quote!(::wasmworker::__private::emit_encoded::<#codec, _>(&#item)),
+        ),
+        ReturnType::Type(_, ty) if result_types(ty).is_some() => quote! {
+            match #call {
+                ::std::result::Result::Ok(value) => {
+                    ::wasmworker::__private::set_encoded_result::<#codec, _>(&value);
+                }
+                ::std::result::Result::Err(error) => {
+                    ::wasmworker::__private::set_encoded_error::<#codec, _>(&error);
+                }
+            }
+        },
+        ReturnType::Type(_, ty) if !is_unit(ty) => quote! {
+            let result = #call;
+            ::wasmworker::__private::set_encoded_result::<#codec, _>(&result);
+        },
+        _ => quote!(#call;),
+    };
+
+    Ok(quote! {
+        #[doc(hidden)]
+        #[export_name = #export_name]
+        #[allow(clippy::not_unsafe_ptr_arg_deref)]
+        pub extern "C" fn #wrapper_ident(payload_ptr: *const u8, payload_len: usize) {
+            ::wasmworker::__private::install_panic_hook();
+            let payload = unsafe {
+                ::wasmworker::__private::slice_from_raw(payload_ptr, payload_len)
+            };
+            #decode_args
+            #store_result
+        }
+    })
+}
+
+/// Generate the body of a stream export, emitting every item of the
+/// returned iterator with `emit` until the call is cancelled.
+///
+/// Functions returning `()` are simply called; they emit items themselves.
+fn stream_items(
+    func: &ItemFn,
+    call: TokenStream,
+    emit: impl Fn(&Ident) -> TokenStream,
+) -> TokenStream {
+    match &func.sig.output {
+        ReturnType::Type(_, ty) if !is_unit(ty) => {
+            let item = format_ident!("item");
+            let emit = emit(&item);
+            quote! {
+                for #item in ::std::iter::IntoIterator::into_iter(#call) {
+                    if ::wasmworker::is_cancelled() {
+                        break;
+                    }
+                    #emit;
+                }
+            }
+        }
+        _ => quote!(#call;),
+    }
+}
+
+/// Reject signatures that cannot be called across the wasm boundary.
+fn validate_signature(func: &ItemFn) -> syn::Result<()> {
+    let sig = &func.sig;
+    if let Some(token) = &sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            token,
+            "#[wasmworker::export] functions cannot be async",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "#[wasmworker::export] functions cannot be generic",
+        ));
+    }
+    if let Some(variadic) = &sig.variadic {
+        return Err(syn::Error::new_spanned(
+            variadic,
+            "#[wasmworker::export] functions cannot be variadic",
+        ));
+    }
+    Ok(())
+}
+
+fn typed_arg(arg: &FnArg) -> syn::Result<&PatType> {
+    match arg {
+        FnArg::Receiver(receiver) => Err(syn::Error::new_spanned(
+            receiver,
+            "#[wasmworker::export] cannot be used on methods",
+        )),
+        FnArg::Typed(pat_type) => Ok(pat_type),
+    }
+}
+
+fn param_ident(pat_type: &PatType) -> syn::Result<Ident> {
+    match &*pat_type.pat {
+        Pat::Ident(pat) => Ok(pat.ident.clone()),
+        other => Err(syn::Error::new_spanned(
+            other,
+            "#[wasmworker::export] parameters must be plain identifiers",
+        )),
+    }
+}
+
+fn is_unit(ty: &Type) -> bool {
+    matches!(ty, Type::Tuple(tuple) if tuple.elems.is_empty())
+}
@@ -0,0 +1,24 @@
+//! Procedural macros for the `wasmworker` guest crate.
+//!
+//! This crate is an implementation detail; depend on `wasmworker` and use
+//! `#[wasmworker::export]` instead.
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, ItemFn};
+
+mod export;
+mod manifest;
+mod types;
+
+/// Export a Rust function so it can be called through `WasmWorker.call`.
+///
+/// See the `wasmworker` crate documentation for the supported signatures.
+#[proc_macro_attribute]
+pub fn export(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = proc_macro2::TokenStream::from(attr);
+    let func = parse_macro_input!(item as ItemFn);
+
+    export::expand(attr, func)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,137 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::{Expr, FnArg, ItemFn, Lit, Meta, Pat, ReturnType, Type};
+
+/// Name of the custom section holding the export manifest.
+const SECTION: &str = "wasmworker.manifest";
+
+/// Generate the manifest record describing an export.
+///
+/// The record is one line of JSON. The linker concatenates the custom
+/// sections of every export into one, which the runtime splits by line.
+/// Outside of wasm it is only kept as a hidden constant, for tests.
+pub fn expand(func: &ItemFn, export_name: &str, codec: Option<&str>, stream: bool) -> TokenStream {
+    let record = record(func, export_name, codec, stream);
+    let line = format!("{record}\n");
+
+    let const_ident = format_ident!("__wasmworker_manifest_{}", func.sig.ident);
+    let static_ident = format_ident!("__WASMWORKER_MANIFEST_{}", func.sig.ident);
+    let len = line.len();
+    let bytes = Literal::byte_string(line.as_bytes());
+
+    quote! {
+        #[doc(hidden)]
+        #[allow(non_upper_case_globals)]
+        pub const #const_ident: &str = #record;
+
+        #[cfg(target_arch = "wasm32")]
+        #[link_section = #SECTION]
+        #[used]
+        #[allow(non_upper_case_globals)]
+        static #static_ident: [u8; #len] = *#bytes;
+    }
+}
+
+fn record(func: &ItemFn, export_name: &str, codec: Option<&str>, stream: bool) -> String {
+    let params: Vec<_> = func
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(pat_type) => match &*pat_type.pat {
+                Pat::Ident(pat) => Some(format!(
+                    "{{\"name\":{},\"type\":{}}}",
+                    // The runtime maps object payloads to parameters by this name
+                    json_string(&pat.ident.unraw().to_string()),
+                    json_string(&type_name(&pat_type.ty)),
+                )),
+                _ => None,
+            },
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+
+    let returns = match &func.sig.output {
+        ReturnType::Type(_, ty) if !matches!(&**ty, Type::Tuple(tuple) if tuple.elems.is_empty()) => {
+            json_string(&type_name(ty))
+        }
+        _ => "null".to_owned(),
+    };
+
+    format!(
+        "{{\"name\":{},\"params\":[{}],\"returns\":{},\"codec\":{},\"stream\":{},\"docs\":{}}}",
+        json_string(export_name),
+        params.join(","),
+        returns,
+        codec.map_or_else(|| "null".to_owned(), json_string),
+        stream,
+        json_string(&docs(func)),
+    )
+}
+
+/// The doc comments of `func`, one line each, as rustdoc would show them.
+fn docs(func: &ItemFn) -> String {
+    let lines: Vec<String> = func
+        .attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+                Expr::Lit(expr) => match &expr.lit {
+                    Lit::Str(lit) => Some(lit.value()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .flat_map(|doc| {
+            // `split` rather than `lines`, so an empty `///` stays a blank line
+            doc.split('\n')
+                .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    lines.join("\n").trim().to_owned()
+}
+
+/// Render a type compactly, e.g. `Vec<Point>` rather than `Vec < Point >`.
+fn type_name(ty: &Type) -> String {
+    let spaced = ty.to_token_stream().to_string();
+    let chars: Vec<char> = spaced.chars().collect();
+    let tight = |c: char| "<>[]()&,:;".contains(c);
+
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' {
+            let prev = i.checked_sub(1).map(|i| chars[i]);
+            let next = chars.get(i + 1).copied();
+            if prev.is_some_and(tight) || next.is_some_and(tight) {
+                continue;
+            }
+        }
+        out.push(c);
+        if c == ',' {
+            out.push(' ');
+        }
+    }
+    out
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
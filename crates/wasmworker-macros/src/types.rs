@@ -0,0 +1,233 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{GenericArgument, Ident, PathArguments, Type, TypePath, TypeReference};
+
+/// How a Rust type crosses the wasm boundary.
+pub enum WireType {
+    /// A number that maps directly onto a wasm value type.
+    Scalar(Ident),
+    /// `bool`, passed as an `i32` that is zero or non-zero.
+    Bool,
+    /// `()`, only valid as a return type.
+    Unit,
+    /// `&[u8]`, passed as a `(ptr, len)` pair into guest memory.
+    ByteSlice,
+    /// `Vec<u8>`, passed like `&[u8]` and copied into an owned vector.
+    /// Returned through the result slot.
+    ByteVec,
+    /// `&str`, passed as a `(ptr, len)` pair of UTF-8 bytes.
+    Str,
+    /// `String`, passed like `&str` and returned through the result slot.
+    String,
+}
+
+impl WireType {
+    /// Classify a parameter or return type.
+    pub fn classify(ty: &Type) -> syn::Result<Self> {
+        match ty {
+            Type::Tuple(tuple) if tuple.elems.is_empty() => return Ok(WireType::Unit),
+            Type::Paren(inner) => return Self::classify(&inner.elem),
+            Type::Group(inner) => return Self::classify(&inner.elem),
+            Type::Reference(reference) if is_byte_slice(reference) => {
+                return Ok(WireType::ByteSlice)
+            }
+            Type::Reference(reference) if is_str(reference) => return Ok(WireType::Str),
+            Type::Path(path) => {
+                if let Some(ident) = simple_ident(path) {
+                    match ident.to_string().as_str() {
+                        "i32" | "u32" | "i64" | "u64" | "f32" | "f64" => {
+                            return Ok(WireType::Scalar(ident.clone()))
+                        }
+                        "bool" => return Ok(WireType::Bool),
+                        "String" => return Ok(WireType::String),
+                        _ => {}
+                    }
+                }
+                if is_byte_vec(path) {
+                    return Ok(WireType::ByteVec);
+                }
+            }
+            _ => {}
+        }
+
+        Err(syn::Error::new_spanned(
+            ty,
+            "unsupported type for #[wasmworker::export]; \
+             expected one of i32, u32, i64, u64, f32, f64, bool, &[u8], Vec<u8>, &str or String",
+        ))
+    }
+
+    /// Whether this type is passed as a `(ptr, len)` pair.
+    pub fn is_buffer(&self) -> bool {
+        matches!(
+            self,
+            WireType::ByteSlice | WireType::ByteVec | WireType::Str | WireType::String
+        )
+    }
+
+    /// Whether this type can be returned from an export.
+    pub fn is_returnable(&self) -> bool {
+        !matches!(self, WireType::ByteSlice | WireType::Str)
+    }
+
+    /// The parameters used for `ident` in the generated `extern "C"` signature.
+    pub fn abi_params(&self, ident: &Ident) -> TokenStream {
+        match self {
+            WireType::Scalar(ty) => quote!(#ident: #ty),
+            WireType::Bool => quote!(#ident: i32),
+            WireType::Unit => quote!(#ident: ()),
+            WireType::ByteSlice | WireType::ByteVec | WireType::Str | WireType::String => {
+                let (ptr, len) = buffer_idents(ident);
+                quote!(#ptr: *const u8, #len: usize)
+            }
+        }
+    }
+
+    /// The return type used in the generated `extern "C"` signature.
+    ///
+    /// Owned buffers go through the result slot, so the export returns nothing.
+    pub fn abi_return(&self) -> TokenStream {
+        match self {
+            WireType::Scalar(ty) => quote!(#ty),
+            WireType::Bool => quote!(i32),
+            _ => quote!(()),
+        }
+    }
+
+    /// A placeholder ABI value, returned when the export fails with `Err`.
+    ///
+    /// Empty for types returned through the result slot.
+    pub fn abi_default(&self) -> TokenStream {
+        match self {
+            WireType::Scalar(ty) => quote!(0 as #ty),
+            WireType::Bool => quote!(0),
+            _ => TokenStream::new(),
+        }
+    }
+
+    /// Convert the incoming ABI value(s) for `ident` into the Rust type.
+    pub fn lift(&self, ident: &Ident) -> TokenStream {
+        match self {
+            WireType::Scalar(_) | WireType::Unit => quote!(#ident),
+            WireType::Bool => quote!(#ident != 0),
+            WireType::ByteSlice => {
+                let (ptr, len) = buffer_idents(ident);
+                quote!(unsafe { ::wasmworker::__private::slice_from_raw(#ptr, #len) })
+            }
+            WireType::ByteVec => {
+                let (ptr, len) = buffer_idents(ident);
+                quote!(unsafe { ::wasmworker::__private::slice_from_raw(#ptr, #len) }.to_vec())
+            }
+            WireType::Str => {
+                let (ptr, len) = buffer_idents(ident);
+                quote!(unsafe { ::wasmworker::__private::str_from_raw(#ptr, #len) })
+            }
+            WireType::String => {
+                let (ptr, len) = buffer_idents(ident);
+                quote!(unsafe { ::wasmworker::__private::str_from_raw(#ptr, #len) }.to_owned())
+            }
+        }
+    }
+
+    /// Convert a Rust value held in `expr` into its ABI representation.
+    pub fn lower(&self, expr: TokenStream) -> TokenStream {
+        match self {
+            WireType::Bool => quote!(#expr as i32),
+            WireType::ByteVec => quote! {
+                ::wasmworker::__private::set_result(::wasmworker::KIND_BYTES, #expr)
+            },
+            WireType::String => quote! {
+                ::wasmworker::__private::set_result(
+                    ::wasmworker::KIND_STRING,
+                    ::std::string::String::into_bytes(#expr),
+                )
+            },
+            _ => expr,
+        }
+    }
+}
+
+/// The `T` and `E` of a `Result<T, E>` return type.
+pub fn result_types(ty: &Type) -> Option<(&Type, &Type)> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if path.qself.is_some() || segment.ident != "Result" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 2 => {
+            match (&args.args[0], &args.args[1]) {
+                (GenericArgument::Type(ok), GenericArgument::Type(err)) => Some((ok, err)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The `(ptr, len)` parameter names generated for a buffer parameter.
+fn buffer_idents(ident: &Ident) -> (Ident, Ident) {
+    (
+        format_ident!("{}_ptr", ident),
+        format_ident!("{}_len", ident),
+    )
+}
+
+/// Return the identifier of a single-segment path such as `u32`.
+fn simple_ident(path: &TypePath) -> Option<&Ident> {
+    if path.qself.is_some() || path.path.segments.len() != 1 {
+        return None;
+    }
+    let segment = &path.path.segments[0];
+    if !segment.arguments.is_none() {
+        return None;
+    }
+    Some(&segment.ident)
+}
+
+/// Whether `reference` is `&[u8]`.
+fn is_byte_slice(reference: &TypeReference) -> bool {
+    if reference.mutability.is_some() {
+        return false;
+    }
+    match &*reference.elem {
+        Type::Slice(slice) => is_u8(&slice.elem),
+        _ => false,
+    }
+}
+
+/// Whether `reference` is `&str`.
+fn is_str(reference: &TypeReference) -> bool {
+    if reference.mutability.is_some() {
+        return false;
+    }
+    match &*reference.elem {
+        Type::Path(path) => simple_ident(path).is_some_and(|ident| ident == "str"),
+        _ => false,
+    }
+}
+
+/// Whether `path` is `Vec<u8>`.
+fn is_byte_vec(path: &TypePath) -> bool {
+    let Some(segment) = path.path.segments.last() else {
+        return false;
+    };
+    if path.qself.is_some() || segment.ident != "Vec" {
+        return false;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+            matches!(&args.args[0], GenericArgument::Type(ty) if is_u8(ty))
+        }
+        _ => false,
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => simple_ident(path).is_some_and(|ident| ident == "u8"),
+        _ => false,
+    }
+}
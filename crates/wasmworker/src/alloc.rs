@@ -0,0 +1,85 @@
+//! Linear-memory allocator exports used by the runtime to pass buffers in.
+//!
+//! The runtime calls `ww_alloc(len)`, copies the payload bytes to the returned
+//! pointer, invokes the export with `(ptr, len)` and finally releases the
+//! buffer again with `ww_free(ptr, len)`.
+
+use std::alloc::{alloc, dealloc, Layout};
+use std::ptr::NonNull;
+
+/// Allocate `len` bytes of guest memory for the host to write into.
+///
+/// Zero-length requests return a dangling, well-aligned pointer that must
+/// not be dereferenced; `ww_free` accepts it back as a no-op.
+#[no_mangle]
+pub extern "C" fn ww_alloc(len: usize) -> *mut u8 {
+    if len == 0 {
+        return NonNull::dangling().as_ptr();
+    }
+    let layout = match Layout::from_size_align(len, 1) {
+        Ok(layout) => layout,
+        Err(_) => return std::ptr::null_mut(),
+    };
+    // SAFETY: `layout` has a non-zero size.
+    unsafe { alloc(layout) }
+}
+
+/// Release a buffer previously returned by [`ww_alloc`].
+///
+/// # Safety
+///
+/// `ptr` must come from `ww_alloc(len)` with the same `len` and must not be
+/// used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn ww_free(ptr: *mut u8, len: usize) {
+    if len == 0 || ptr.is_null() {
+        return;
+    }
+    // SAFETY: the caller guarantees `ptr` was allocated with this layout.
+    unsafe { dealloc(ptr, Layout::from_size_align_unchecked(len, 1)) }
+}
+
+/// Borrow a host-provided buffer as a byte slice.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` initialized bytes that stay valid and unaliased
+/// for the lifetime `'a`.
+#[doc(hidden)]
+pub unsafe fn slice_from_raw<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        return &[];
+    }
+    // SAFETY: upheld by the caller.
+    unsafe { std::slice::from_raw_parts(ptr, len) }
+}
+
+/// Borrow a host-provided buffer as UTF-8 text.
+///
+/// # Safety
+///
+/// Same requirements as [`slice_from_raw`].
+#[doc(hidden)]
+pub unsafe fn str_from_raw<'a>(ptr: *const u8, len: usize) -> &'a str {
+    // SAFETY: upheld by the caller.
+    let bytes = unsafe { slice_from_raw(ptr, len) };
+    std::str::from_utf8(bytes).expect("string argument is not valid UTF-8")
+}
+
+/// Borrow a shared region the host allocated with `worker.allocShared()`.
+///
+/// Exports working on a region in place take its `offset` and `byteLength`
+/// as plain integers, so the bytes the main thread wrote are never copied.
+///
+/// # Safety
+///
+/// `offset` must be the offset of a live region of at least `len` bytes, and
+/// nothing else may access the region for the lifetime `'a`: the main thread
+/// must not write to it while the export runs.
+pub unsafe fn shared_region<'a>(offset: usize, len: usize) -> &'a mut [u8] {
+    if len == 0 {
+        return &mut [];
+    }
+    // SAFETY: upheld by the caller.
+    unsafe { std::slice::from_raw_parts_mut(offset as *mut u8, len) }
+}
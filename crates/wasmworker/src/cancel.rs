@@ -0,0 +1,43 @@
+//! Cooperative cancellation.
+//!
+//! The runtime cannot interrupt a running export, so long computations poll
+//! [`is_cancelled`] and return early once the caller has aborted the call.
+//! The check goes through the imported host function `env.ww_is_cancelled`,
+//! which the runtime provides.
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "env")]
+extern "C" {
+    fn ww_is_cancelled() -> i32;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    static CANCELLED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Whether the caller has cancelled the call that is currently running.
+///
+/// Returns `true` once the `AbortSignal` passed to `worker.call` or
+/// `worker.stream` has fired, or the stream consumer stopped early. The
+/// value the export returns afterwards is discarded. Cheap enough to call
+/// in a loop, though not on every iteration of a tight one.
+pub fn is_cancelled() -> bool {
+    #[cfg(target_arch = "wasm32")]
+    // SAFETY: the import takes no arguments and only reads runtime state.
+    unsafe {
+        ww_is_cancelled() != 0
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    CANCELLED.with(|cancelled| cancelled.get())
+}
+
+/// Set what [`is_cancelled`] returns, as the runtime would.
+///
+/// Only available outside of wasm. Useful for testing cancellation
+/// natively.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_cancelled(cancelled: bool) {
+    CANCELLED.with(|flag| flag.set(cancelled));
+}
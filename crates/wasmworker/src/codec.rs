@@ -0,0 +1,107 @@
+//! Payload codecs for exports declared with `codec = "..."`.
+//!
+//! A codec export receives the whole call payload as one encoded buffer and
+//! hands its return value back encoded with the same codec through the
+//! result slot. Payloads that fail to decode are reported to the runtime as
+//! `INVALID_PAYLOAD` together with the decoder's error message.
+//!
+//! Exports returning `Result<T, E>` store an `Err` encoded like a return
+//! value but flagged with [`STATUS_APP_ERROR`],
+//! which the runtime reports as `APP_ERROR`.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::result::{set_result, KIND_STRING, STATUS_APP_ERROR, STATUS_INVALID_PAYLOAD};
+use crate::stream::emit_chunk;
+
+/// A serialization format understood by both the guest and the runtime.
+pub trait Codec {
+    /// Result slot kind for values encoded with this codec.
+    const KIND: u32;
+
+    /// Decode a payload written by the runtime.
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String>;
+
+    /// Encode a value for the runtime.
+    fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, String>;
+}
+
+/// JSON, via `serde_json`.
+#[cfg(feature = "json")]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl Codec for Json {
+    const KIND: u32 = crate::result::KIND_JSON;
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+        serde_json::from_slice(bytes).map_err(|err| err.to_string())
+    }
+
+    fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, String> {
+        serde_json::to_vec(value).map_err(|err| err.to_string())
+    }
+}
+
+/// MessagePack, via `rmp-serde`.
+///
+/// Structs are encoded as maps keyed by field name so they arrive in
+/// JavaScript as plain objects.
+#[cfg(feature = "msgpack")]
+pub struct MessagePack;
+
+#[cfg(feature = "msgpack")]
+impl Codec for MessagePack {
+    const KIND: u32 = crate::result::KIND_MSGPACK;
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+        rmp_serde::from_slice(bytes).map_err(|err| err.to_string())
+    }
+
+    fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, String> {
+        rmp_serde::to_vec_named(value).map_err(|err| err.to_string())
+    }
+}
+
+/// Decode the payload of a codec export.
+///
+/// On failure the error is stored in the result slot, the export must then
+/// return without calling the user function.
+#[doc(hidden)]
+pub fn decode_payload<C: Codec, T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    match C::decode(bytes) {
+        Ok(value) => Some(value),
+        Err(message) => {
+            set_result(STATUS_INVALID_PAYLOAD | KIND_STRING, message.into_bytes());
+            None
+        }
+    }
+}
+
+/// Store the return value of a codec export in the result slot.
+#[doc(hidden)]
+pub fn set_encoded_result<C: Codec, T: Serialize + ?Sized>(value: &T) {
+    match C::encode(value) {
+        Ok(bytes) => set_result(C::KIND, bytes),
+        Err(message) => panic!("failed to encode result: {message}"),
+    }
+}
+
+/// Store the error of a codec export returning `Result` in the result slot.
+#[doc(hidden)]
+pub fn set_encoded_error<C: Codec, E: Serialize + ?Sized>(error: &E) {
+    match C::encode(error) {
+        Ok(bytes) => set_result(STATUS_APP_ERROR | C::KIND, bytes),
+        Err(message) => panic!("failed to encode error: {message}"),
+    }
+}
+
+/// Emit one item of a codec stream export.
+#[doc(hidden)]
+pub fn emit_encoded<C: Codec, T: Serialize + ?Sized>(value: &T) {
+    match C::encode(value) {
+        Ok(bytes) => emit_chunk(C::KIND, &bytes),
+        Err(message) => panic!("failed to encode stream item: {message}"),
+    }
+}
@@ -0,0 +1,138 @@
+//! Host functions running on the main thread.
+//!
+//! Functions passed to the runtime in `LoadOptions.init` stay on the main
+//! thread. Calling one posts a message to it and blocks the worker until the
+//! function has returned, which needs a cross-origin isolated page.
+//!
+//! Host functions taking and returning numbers can be imported directly from
+//! `env` with an `extern "C"` block. [`call_host`] passes any JSON values,
+//! through the imported `env.ww_host_call` and `env.ww_host_result`:
+//! `ww_host_call(name_ptr, name_len, args_ptr, args_len)` runs the function
+//! and returns the length of its reply, or `-len - 1` if it failed and the
+//! reply is the error message. `ww_host_result(ptr)` then copies the reply
+//! into a buffer of that length.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::cell::RefCell;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "env")]
+extern "C" {
+    fn ww_host_call(
+        name_ptr: *const u8,
+        name_len: usize,
+        args_ptr: *const u8,
+        args_len: usize,
+    ) -> i32;
+    fn ww_host_result(ptr: *mut u8);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type NativeHostFunction =
+    std::rc::Rc<dyn Fn(&[serde_json::Value]) -> Result<serde_json::Value, String>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    // Outside of wasm there is no main thread to call into, so host
+    // functions are registered with `set_host_function`.
+    static HOST_FUNCTIONS: RefCell<HashMap<String, NativeHostFunction>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Error returned by [`call_host`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostError {
+    /// The host function threw or does not exist, with the error message.
+    Failed(String),
+    /// The arguments or the return value did not convert to or from JSON.
+    Json(String),
+}
+
+impl fmt::Display for HostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostError::Failed(message) => write!(f, "host function failed: {message}"),
+            HostError::Json(message) => write!(f, "invalid host function JSON: {message}"),
+        }
+    }
+}
+
+impl Error for HostError {}
+
+/// Call the host function `name` on the main thread and wait for its result.
+///
+/// `args` is serialized to JSON. A tuple or array is spread over the
+/// function's parameters, `()` passes none and any other value is passed as
+/// the only argument. What the function returns, or its promise resolves
+/// to, is deserialized into `R`.
+pub fn call_host<A, R>(name: &str, args: &A) -> Result<R, HostError>
+where
+    A: Serialize + ?Sized,
+    R: DeserializeOwned,
+{
+    let args = serde_json::to_vec(args).map_err(|err| HostError::Json(err.to_string()))?;
+    let reply = invoke(name, &args)?;
+    serde_json::from_slice(&reply).map_err(|err| HostError::Json(err.to_string()))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn invoke(name: &str, args: &[u8]) -> Result<Vec<u8>, HostError> {
+    // SAFETY: the runtime only reads both buffers during the call.
+    let len = unsafe { ww_host_call(name.as_ptr(), name.len(), args.as_ptr(), args.len()) };
+    let failed = len < 0;
+    let len = if failed { -(len + 1) } else { len };
+
+    let mut reply = vec![0; len as usize];
+    // SAFETY: the runtime writes exactly the `len` bytes it announced.
+    unsafe { ww_host_result(reply.as_mut_ptr()) };
+
+    if failed {
+        return Err(HostError::Failed(
+            String::from_utf8_lossy(&reply).into_owned(),
+        ));
+    }
+    Ok(reply)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn invoke(name: &str, args: &[u8]) -> Result<Vec<u8>, HostError> {
+    use serde_json::Value;
+
+    let function = HOST_FUNCTIONS
+        .with(|functions| functions.borrow().get(name).cloned())
+        .ok_or_else(|| HostError::Failed(format!("No host function named \"{name}\"")))?;
+
+    // Spread like the runtime does
+    let args: Value =
+        serde_json::from_slice(args).map_err(|err| HostError::Json(err.to_string()))?;
+    let args = match args {
+        Value::Array(values) => values,
+        Value::Null => Vec::new(),
+        value => vec![value],
+    };
+    let value = function(&args).map_err(HostError::Failed)?;
+    serde_json::to_vec(&value).map_err(|err| HostError::Json(err.to_string()))
+}
+
+/// Register a host function for [`call_host`] on this thread, as the page
+/// would pass it in `LoadOptions.init`.
+///
+/// Only available outside of wasm. Useful for testing host calls natively.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_host_function<F>(name: &str, function: F)
+where
+    F: Fn(&[serde_json::Value]) -> Result<serde_json::Value, String> + 'static,
+{
+    HOST_FUNCTIONS.with(|functions| {
+        functions
+            .borrow_mut()
+            .insert(name.to_owned(), std::rc::Rc::new(function));
+    });
+}
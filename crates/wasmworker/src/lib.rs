@@ -0,0 +1,260 @@
+//! Guest-side helpers for Rust modules running inside [WasmWorker].
+//!
+//! Annotate ordinary Rust functions with [`export`] and the macro generates
+//! the raw `extern "C"` export that the WasmWorker runtime calls:
+//!
+//! ```
+//! /// Add two 32-bit integers
+//! #[wasmworker::export]
+//! pub fn add(a: i32, b: i32) -> i32 {
+//!     a + b
+//! }
+//!
+//! // The original function is still callable from Rust.
+//! assert_eq!(add(2, 3), 5);
+//! ```
+//!
+//! From JavaScript the function is then available as
+//! `worker.call('add', { a: 2, b: 3 })`, where the keys of the
+//! payload are matched to the parameters by name.
+//!
+//! # Supported signatures
+//!
+//! | Rust type                              | JavaScript value |
+//! |----------------------------------------|------------------|
+//! | `i32`, `u32`, `f32`, `f64`             | `number`         |
+//! | `i64`, `u64`                           | `bigint`         |
+//! | `bool`                                 | `boolean` / `0`/`1` |
+//! | `&[u8]`, `Vec<u8>`                     | `Uint8Array` / `ArrayBuffer` |
+//! | `&str`, `String`                       | `string`         |
+//!
+//! `&[u8]` and `&str` are only valid as parameters. Functions may also
+//! return `()`, or a `Result` of any of these (see [Errors](#errors)). The
+//! export name defaults to the function name and can be overridden with
+//! `#[wasmworker::export(name = "...")]`.
+//!
+//! Byte buffers are copied into linear memory by the runtime through the
+//! [`ww_alloc`] / [`ww_free`] exports this crate provides, and the export
+//! receives them as a `(ptr, len)` pair. Returned `String`s and `Vec<u8>`s
+//! are handed back through a result slot (see [`ww_result`]) that the runtime
+//! decodes and frees after each call.
+//!
+//! # Structured payloads
+//!
+//! With `codec = "json"` the whole payload is serialized by the runtime and
+//! deserialized with serde, so parameters and the return value can be any
+//! type implementing `Deserialize` / `Serialize`:
+//!
+//! ```
+//! # #[cfg(feature = "json")] {
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize)]
+//! pub struct Point {
+//!     x: f64,
+//!     y: f64,
+//! }
+//!
+//! #[derive(Serialize)]
+//! pub struct Length {
+//!     value: f64,
+//! }
+//!
+//! #[wasmworker::export(codec = "json")]
+//! pub fn length(point: Point) -> Length {
+//!     Length { value: (point.x * point.x + point.y * point.y).sqrt() }
+//! }
+//! # }
+//! ```
+//!
+//! Called as `worker.call('length', { x: 3, y: 4 }, { codec: 'json' })`. A
+//! single parameter receives the payload as a whole, several parameters are
+//! looked up by name in a payload object. Payloads that do not match are
+//! rejected with `INVALID_PAYLOAD` carrying the serde error message. Requires
+//! the default `json` feature.
+//!
+//! For large structured payloads, `codec = "msgpack"` uses MessagePack
+//! instead, avoiding text encoding overhead. It is enabled with the `msgpack`
+//! feature and selected per call with `{ codec: 'msgpack' }`.
+//!
+//! # Errors
+//!
+//! Exports may return `Result<T, E>`. `Ok` is returned as `T` would be,
+//! while `Err` rejects the call with `APP_ERROR`, keeping domain errors apart
+//! from traps. Direct exports send the error as its `Display` text in
+//! `details`, so `E: Display`:
+//!
+//! ```
+//! #[wasmworker::export]
+//! pub fn parse_port(text: &str) -> Result<u32, std::num::ParseIntError> {
+//!     text.parse()
+//! }
+//! ```
+//!
+//! With `error = "json"` or `error = "msgpack"` the error is serialized
+//! with that codec instead, so `E: Serialize`, and arrives as `details`:
+//!
+//! ```
+//! # #[cfg(feature = "json")] {
+//! #[derive(serde::Serialize)]
+//! pub enum MathError {
+//!     DivisionByZero,
+//! }
+//!
+//! #[wasmworker::export(error = "json")]
+//! pub fn checked_div(a: i32, b: i32) -> Result<i32, MathError> {
+//!     a.checked_div(b).ok_or(MathError::DivisionByZero)
+//! }
+//! # }
+//! ```
+//!
+//! Codec exports always encode the error with their codec.
+//!
+//! # Streaming
+//!
+//! `#[wasmworker::export(stream)]` turns a function returning an iterator
+//! into a stream export. Each item is sent to the runtime as soon as it is
+//! produced and arrives as one chunk of `worker.stream(...)`:
+//!
+//! ```
+//! #[wasmworker::export(stream)]
+//! pub fn countdown(from: u32) -> impl Iterator<Item = u32> {
+//!     (0..=from).rev()
+//! }
+//! ```
+//!
+//! Items may be any of the types above that can be returned (see
+//! [`StreamItem`]), or any `Serialize` type when combined with a codec, as in
+//! `#[wasmworker::export(stream, codec = "json")]`. A stream export returning
+//! `()` can also push items itself with [`emit`]. Chunks are delivered through
+//! the `env.ww_emit` import, which the runtime provides.
+//!
+//! # Cancellation
+//!
+//! Calls and streams can be aborted from JavaScript with an `AbortSignal`.
+//! The runtime cannot interrupt wasm, so long-running exports poll
+//! [`is_cancelled`] and return early:
+//!
+//! ```
+//! #[wasmworker::export]
+//! pub fn count_primes(limit: u32) -> u32 {
+//!     let mut count = 0;
+//!     for n in 2..limit {
+//!         if n % 1024 == 0 && wasmworker::is_cancelled() {
+//!             return 0; // discarded, the caller sees `CANCELLED`
+//!         }
+//!         if (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0) {
+//!             count += 1;
+//!         }
+//!     }
+//!     count
+//! }
+//! ```
+//!
+//! Stream exports stop iterating by themselves once cancelled. Polling an
+//! export that is already running needs a cross-origin isolated page, as
+//! the cancellation flags live in a `SharedArrayBuffer`.
+//!
+//! # Logging
+//!
+//! With the `log` feature, `init_logger` installs a logger for the `log`
+//! crate. Its records are forwarded to the main thread through the imported
+//! `env.ww_log` and delivered to `worker.on('log', ...)` listeners.
+//!
+//! # Host Functions
+//!
+//! Functions passed in `LoadOptions.init` run on the main thread. With the
+//! `json` feature, `call_host` calls one with JSON arguments and blocks until
+//! it has returned:
+//!
+//! ```
+//! # #[cfg(feature = "json")]
+//! #[wasmworker::export]
+//! pub fn scaled(x: f64) -> f64 {
+//!     let factor: f64 = wasmworker::call_host("fetch_config", &("scale",)).unwrap_or(1.0);
+//!     x * factor
+//! }
+//! ```
+//!
+//! Host functions taking and returning numbers can also be imported from
+//! `env` with an `extern "C"` block. Either way the page must be
+//! cross-origin isolated.
+//!
+//! # Shared Regions
+//!
+//! `worker.allocShared(byteLength)` allocates a region of a module built with
+//! `+atomics`, whose memory the main thread can view. Exports receive the
+//! region's offset and length and borrow it with [`shared_region`]:
+//!
+//! ```
+//! #[wasmworker::export]
+//! pub fn invert(offset: u32, len: u32) {
+//!     // SAFETY: the main thread doesn't touch the region during the call
+//!     let pixels = unsafe { wasmworker::shared_region(offset as usize, len as usize) };
+//!     for pixel in pixels {
+//!         *pixel = 255 - *pixel;
+//!     }
+//! }
+//! ```
+//!
+//! # Panics
+//!
+//! A panic aborts the module with a trap. Exports install a panic hook on
+//! their first call, which records the panic message and location for the
+//! runtime to add to the `WASM_TRAP` error as `details.panicMessage` and
+//! `details.location`. [`take_panic`] reads the record natively.
+//!
+//! # Manifest
+//!
+//! Each export is described in the `wasmworker.manifest` custom section of
+//! the compiled module, one line of JSON per export with its name, parameter
+//! names and types, return type, codec, streaming flag and doc comment. The
+//! runtime exposes it as `worker.describe()`.
+//!
+//! [WasmWorker]: https://github.com/barisguler/wasmworker
+
+mod alloc;
+mod cancel;
+#[cfg(any(feature = "json", feature = "msgpack"))]
+pub mod codec;
+#[cfg(feature = "json")]
+mod host;
+#[cfg(feature = "log")]
+mod logger;
+mod panic;
+mod result;
+mod stream;
+
+pub use alloc::{shared_region, ww_alloc, ww_free};
+pub use cancel::is_cancelled;
+#[cfg(not(target_arch = "wasm32"))]
+pub use cancel::set_cancelled;
+#[cfg(all(feature = "json", not(target_arch = "wasm32")))]
+pub use host::set_host_function;
+#[cfg(feature = "json")]
+pub use host::{call_host, HostError};
+#[cfg(all(feature = "log", not(target_arch = "wasm32")))]
+pub use logger::take_logs;
+#[cfg(feature = "log")]
+pub use logger::{init_logger, Logger};
+pub use panic::{take_panic, ww_panic, PanicSlot};
+pub use result::{
+    take_result, ww_result, ResultSlot, KIND_BOOL, KIND_BYTES, KIND_F32, KIND_F64, KIND_I32,
+    KIND_I64, KIND_JSON, KIND_MSGPACK, KIND_NONE, KIND_STRING, KIND_U32, KIND_U64,
+    STATUS_APP_ERROR, STATUS_INVALID_PAYLOAD,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use stream::take_chunks;
+pub use stream::{emit, StreamItem};
+pub use wasmworker_macros::export;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::alloc::{slice_from_raw, str_from_raw};
+    #[cfg(any(feature = "json", feature = "msgpack"))]
+    pub use crate::codec::{decode_payload, emit_encoded, set_encoded_error, set_encoded_result};
+    pub use crate::panic::install_panic_hook;
+    pub use crate::result::{set_app_error, set_result};
+    #[cfg(any(feature = "json", feature = "msgpack"))]
+    pub use serde;
+}
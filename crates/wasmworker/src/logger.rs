@@ -0,0 +1,70 @@
+//! Logging through the `log` crate.
+//!
+//! [`Logger`] formats each record and hands it to the imported host function
+//! `env.ww_log(level, ptr, len)`, which the runtime provides. The runtime
+//! copies the message out and forwards it to the main thread, where it is
+//! delivered to `worker.on('log', ...)` listeners. `level` is the numeric
+//! value of [`log::Level`], from 1 for `Error` to 5 for `Trace`.
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "env")]
+extern "C" {
+    fn ww_log(level: u32, ptr: *const u8, len: usize);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    // Outside of wasm there is no runtime to forward records to, so they are
+    // kept for `take_logs`.
+    static LOGS: std::cell::RefCell<Vec<(Level, String)>> =
+        const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// A [`Log`] implementation forwarding records to the runtime.
+pub struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+
+        #[cfg(target_arch = "wasm32")]
+        // SAFETY: the runtime only reads `len` bytes from `ptr` during the call.
+        unsafe {
+            ww_log(record.level() as u32, message.as_ptr(), message.len())
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        LOGS.with(|logs| logs.borrow_mut().push((record.level(), message)));
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install [`Logger`] as the global logger, recording up to `level`.
+///
+/// Call it once, e.g. from the first export that runs. Fails if another
+/// logger has been installed already.
+pub fn init_logger(level: LevelFilter) -> Result<(), SetLoggerError> {
+    static LOGGER: Logger = Logger;
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+/// Take the records logged so far on this thread, as the runtime would
+/// receive them.
+///
+/// Only available outside of wasm. Useful for testing logging natively.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn take_logs() -> Vec<(Level, String)> {
+    LOGS.with(|logs| logs.take())
+}
@@ -0,0 +1,132 @@
+//! Panic reporting.
+//!
+//! A panic in a wasm module aborts with an `unreachable` trap, which on its
+//! own tells the caller nothing. Exports install a panic hook on their first
+//! call that records the panic message and location in a thread-local slot
+//! before the trap. The runtime reads the slot (its address comes from
+//! `ww_panic()`) once the call has trapped and adds both to the `WASM_TRAP`
+//! error.
+//!
+//! The slot is laid out as five little-endian `u32` values on wasm32:
+//! `panicked`, `message_ptr`, `message_len`, `location_ptr`, `location_len`.
+//! The runtime resets `panicked` to zero after reading it. The strings stay
+//! owned by the guest and are released by the next panic, as freeing memory
+//! after a trap is not safe.
+
+use std::cell::Cell;
+use std::panic::{self, PanicHookInfo};
+use std::sync::Once;
+
+/// Memory layout shared with the runtime.
+#[repr(C)]
+pub struct PanicSlot {
+    panicked: Cell<u32>,
+    message_ptr: Cell<usize>,
+    message_len: Cell<usize>,
+    location_ptr: Cell<usize>,
+    location_len: Cell<usize>,
+}
+
+thread_local! {
+    static PANIC: PanicSlot = const {
+        PanicSlot {
+            panicked: Cell::new(0),
+            message_ptr: Cell::new(0),
+            message_len: Cell::new(0),
+            location_ptr: Cell::new(0),
+            location_len: Cell::new(0),
+        }
+    };
+}
+
+/// Address of the panic slot, read once by the runtime after instantiation.
+#[no_mangle]
+pub extern "C" fn ww_panic() -> *const PanicSlot {
+    PANIC.with(|slot| slot as *const PanicSlot)
+}
+
+/// Install the panic hook, unless it already is.
+///
+/// Called by the generated wrappers on entry. The previous hook still runs
+/// after the panic has been recorded.
+#[doc(hidden)]
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            record(info);
+            previous(info);
+        }));
+    });
+}
+
+fn record(info: &PanicHookInfo<'_>) {
+    let message = match info.payload_as_str() {
+        Some(message) => message.to_owned(),
+        None => "Box<dyn Any>".to_owned(),
+    };
+    let location = info
+        .location()
+        .map(|location| location.to_string())
+        .unwrap_or_default();
+
+    // The slot is gone if the thread is shutting down
+    let _ = PANIC.try_with(|slot| {
+        // SAFETY: both strings were leaked by an earlier panic in this
+        // function and have not been released since.
+        unsafe {
+            release(slot.message_ptr.get(), slot.message_len.get());
+            release(slot.location_ptr.get(), slot.location_len.get());
+        }
+
+        let (ptr, len) = leak(message);
+        slot.message_ptr.set(ptr);
+        slot.message_len.set(len);
+        let (ptr, len) = leak(location);
+        slot.location_ptr.set(ptr);
+        slot.location_len.set(len);
+        slot.panicked.set(1);
+    });
+}
+
+fn leak(text: String) -> (usize, usize) {
+    let bytes = text.into_bytes().into_boxed_slice();
+    let len = bytes.len();
+    (Box::into_raw(bytes) as *mut u8 as usize, len)
+}
+
+/// Release a string leaked by [`leak`].
+///
+/// # Safety
+///
+/// `ptr` must be zero or come from [`leak`] with the same `len`, and not
+/// have been released yet.
+unsafe fn release(ptr: usize, len: usize) {
+    if ptr != 0 {
+        // SAFETY: the caller guarantees `ptr` and `len` describe a leaked box.
+        drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr as *mut u8, len)) });
+    }
+}
+
+/// Take the message and location of the last panic, as the runtime would.
+///
+/// Returns `None` if no export has panicked since the last call. Useful for
+/// testing panics natively, together with [`std::panic::catch_unwind`].
+pub fn take_panic() -> Option<(String, String)> {
+    PANIC.with(|slot| {
+        if slot.panicked.replace(0) == 0 {
+            return None;
+        }
+        let read = |ptr: usize, len: usize| {
+            // SAFETY: the hook leaked `len` bytes of UTF-8 at `ptr`, which
+            // stay alive until the next panic.
+            let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, len) };
+            String::from_utf8_lossy(bytes).into_owned()
+        };
+        Some((
+            read(slot.message_ptr.get(), slot.message_len.get()),
+            read(slot.location_ptr.get(), slot.location_len.get()),
+        ))
+    })
+}
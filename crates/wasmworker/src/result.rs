@@ -0,0 +1,122 @@
+//! Result slot used to hand buffers back to the runtime.
+//!
+//! Exports that return a `String` or `Vec<u8>` leak the bytes into guest
+//! memory and record them in a thread-local slot. After every call the
+//! runtime reads the slot (its address comes from `ww_result()`), decodes the
+//! bytes according to `kind`, resets `kind` to zero and releases the buffer
+//! with `ww_free(ptr, len)`.
+//!
+//! The slot is laid out as three little-endian `u32` values on wasm32:
+//! `kind`, `ptr`, `len`. The low byte of `kind` says how the bytes are
+//! encoded, the next byte carries a status such as [`STATUS_INVALID_PAYLOAD`].
+
+use std::cell::Cell;
+
+/// The slot is empty, the export's return value is the result.
+pub const KIND_NONE: u32 = 0;
+/// Raw bytes, surfaced as a `Uint8Array`.
+pub const KIND_BYTES: u32 = 1;
+/// UTF-8 text, surfaced as a `string`.
+pub const KIND_STRING: u32 = 2;
+/// A JSON document, parsed by the runtime.
+pub const KIND_JSON: u32 = 3;
+/// A MessagePack document, decoded by the runtime.
+pub const KIND_MSGPACK: u32 = 4;
+
+// Stream chunks may also carry a single scalar, stored little-endian.
+
+/// An `i32`, surfaced as a `number`.
+pub const KIND_I32: u32 = 5;
+/// A `u32`, surfaced as a `number`.
+pub const KIND_U32: u32 = 6;
+/// An `i64`, surfaced as a `bigint`.
+pub const KIND_I64: u32 = 7;
+/// A `u64`, surfaced as a `bigint`.
+pub const KIND_U64: u32 = 8;
+/// An `f32`, surfaced as a `number`.
+pub const KIND_F32: u32 = 9;
+/// An `f64`, surfaced as a `number`.
+pub const KIND_F64: u32 = 10;
+/// A `bool` stored as one byte, surfaced as a `boolean`.
+pub const KIND_BOOL: u32 = 11;
+
+/// The call failed because its payload could not be decoded. The bytes are
+/// the error message.
+pub const STATUS_INVALID_PAYLOAD: u32 = 0x100;
+
+/// The export returned `Err`. The bytes are the error, encoded like a
+/// return value.
+pub const STATUS_APP_ERROR: u32 = 0x200;
+
+/// Memory layout shared with the runtime.
+#[repr(C)]
+pub struct ResultSlot {
+    kind: Cell<u32>,
+    ptr: Cell<usize>,
+    len: Cell<usize>,
+}
+
+thread_local! {
+    // Calls are driven by a single runtime thread, which owns this slot.
+    static RESULT: ResultSlot = const {
+        ResultSlot {
+            kind: Cell::new(KIND_NONE),
+            ptr: Cell::new(0),
+            len: Cell::new(0),
+        }
+    };
+}
+
+/// Address of the result slot, read once by the runtime after instantiation.
+#[no_mangle]
+pub extern "C" fn ww_result() -> *const ResultSlot {
+    RESULT.with(|slot| slot as *const ResultSlot)
+}
+
+/// Store `bytes` as the result of the current call.
+///
+/// Ownership of the buffer passes to the runtime, which frees it with
+/// `ww_free` once it has been copied out.
+#[doc(hidden)]
+pub fn set_result(kind: u32, bytes: Vec<u8>) {
+    let bytes = bytes.into_boxed_slice();
+    let len = bytes.len();
+    let ptr = Box::into_raw(bytes) as *mut u8;
+
+    RESULT.with(|slot| {
+        slot.ptr.set(ptr as usize);
+        slot.len.set(len);
+        slot.kind.set(kind);
+    });
+}
+
+/// Store the error of a direct export returning `Result` in the result slot.
+///
+/// The error is sent as its `Display` text, unless the export names a codec
+/// with `error = "..."`.
+#[doc(hidden)]
+pub fn set_app_error<E: std::fmt::Display + ?Sized>(error: &E) {
+    set_result(
+        STATUS_APP_ERROR | KIND_STRING,
+        error.to_string().into_bytes(),
+    );
+}
+
+/// Take the pending result out of the slot, as the runtime would.
+///
+/// Returns the kind and the owned bytes, leaving the slot empty. Useful for
+/// testing exports natively.
+pub fn take_result() -> Option<(u32, Vec<u8>)> {
+    RESULT.with(|slot| {
+        let kind = slot.kind.replace(KIND_NONE);
+        if kind == KIND_NONE {
+            return None;
+        }
+        let ptr = slot.ptr.get() as *mut u8;
+        let len = slot.len.get();
+        // SAFETY: `ptr`/`len` were produced by `set_result` from a boxed
+        // slice that has not been freed, since the slot was still pending.
+        let bytes = unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)) };
+        Some((kind, bytes.into_vec()))
+    })
+}
@@ -0,0 +1,112 @@
+//! Streaming exports.
+//!
+//! A stream export sends its values to the runtime one chunk at a time
+//! through the imported host function `env.ww_emit(kind, ptr, len)`. The
+//! runtime copies the bytes out, decodes them according to `kind` (see the
+//! `KIND_*` constants) and forwards each chunk as a `stream_chunk` message.
+//! Once the export returns the stream is closed.
+
+use crate::result::{
+    KIND_BOOL, KIND_BYTES, KIND_F32, KIND_F64, KIND_I32, KIND_I64, KIND_STRING, KIND_U32, KIND_U64,
+};
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "env")]
+extern "C" {
+    fn ww_emit(kind: u32, ptr: *const u8, len: usize);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    // Outside of wasm there is no runtime to forward chunks to, so they are
+    // kept for `take_chunks`.
+    static CHUNKS: std::cell::RefCell<Vec<(u32, Vec<u8>)>> =
+        const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Hand one encoded chunk to the runtime, which copies it before returning.
+#[doc(hidden)]
+pub fn emit_chunk(kind: u32, bytes: &[u8]) {
+    #[cfg(target_arch = "wasm32")]
+    // SAFETY: the runtime only reads `len` bytes from `ptr` during the call.
+    unsafe {
+        ww_emit(kind, bytes.as_ptr(), bytes.len())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    CHUNKS.with(|chunks| chunks.borrow_mut().push((kind, bytes.to_vec())));
+}
+
+/// Take the chunks emitted so far, as the runtime would receive them.
+///
+/// Only available outside of wasm. Useful for testing stream exports
+/// natively.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn take_chunks() -> Vec<(u32, Vec<u8>)> {
+    CHUNKS.with(|chunks| chunks.take())
+}
+
+/// A value that can be sent as a chunk of a stream export.
+pub trait StreamItem {
+    /// Send this value to the runtime as the next chunk.
+    fn emit(self);
+}
+
+/// Send `item` as the next chunk of the current stream export.
+///
+/// Stream exports usually return an iterator and let the generated wrapper
+/// emit its items, but a stream export returning `()` may call this directly.
+pub fn emit<T: StreamItem>(item: T) {
+    item.emit();
+}
+
+macro_rules! scalar_items {
+    ($($ty:ty => $kind:expr),* $(,)?) => {
+        $(
+            impl StreamItem for $ty {
+                fn emit(self) {
+                    emit_chunk($kind, &self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+scalar_items! {
+    i32 => KIND_I32,
+    u32 => KIND_U32,
+    i64 => KIND_I64,
+    u64 => KIND_U64,
+    f32 => KIND_F32,
+    f64 => KIND_F64,
+}
+
+impl StreamItem for bool {
+    fn emit(self) {
+        emit_chunk(KIND_BOOL, &[self as u8]);
+    }
+}
+
+impl StreamItem for &str {
+    fn emit(self) {
+        emit_chunk(KIND_STRING, self.as_bytes());
+    }
+}
+
+impl StreamItem for String {
+    fn emit(self) {
+        self.as_str().emit();
+    }
+}
+
+impl StreamItem for &[u8] {
+    fn emit(self) {
+        emit_chunk(KIND_BYTES, self);
+    }
+}
+
+impl StreamItem for Vec<u8> {
+    fn emit(self) {
+        self.as_slice().emit();
+    }
+}
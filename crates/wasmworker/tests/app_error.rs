@@ -0,0 +1,87 @@
+#![cfg(feature = "json")]
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use wasmworker::{take_result, KIND_JSON, KIND_STRING, STATUS_APP_ERROR};
+
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+enum MathError {
+    DivisionByZero,
+    Overflow { limit: i32 },
+}
+
+#[wasmworker::export(error = "json")]
+fn checked_div(a: i32, b: i32) -> Result<i32, MathError> {
+    if b == 0 {
+        return Err(MathError::DivisionByZero);
+    }
+    a.checked_div(b).ok_or(MathError::Overflow { limit: i32::MAX })
+}
+
+#[wasmworker::export]
+fn shout(text: &str) -> Result<String, String> {
+    if text.is_empty() {
+        return Err("nothing to shout".into());
+    }
+    Ok(text.to_uppercase())
+}
+
+#[derive(Deserialize)]
+struct Transfer {
+    amount: u32,
+    balance: u32,
+}
+
+#[wasmworker::export(codec = "json")]
+fn withdraw(transfer: Transfer) -> Result<u32, Value> {
+    transfer
+        .balance
+        .checked_sub(transfer.amount)
+        .ok_or_else(|| json!({ "missing": transfer.amount - transfer.balance }))
+}
+
+fn app_error() -> Value {
+    let (kind, bytes) = take_result().expect("an error in the result slot");
+    assert_eq!(kind, STATUS_APP_ERROR | KIND_JSON);
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[test]
+fn ok_values_are_returned_as_usual() {
+    assert_eq!(__wasmworker_export_checked_div(6, 3), 2);
+    assert!(take_result().is_none());
+
+    let text = "hi";
+    __wasmworker_export_shout(text.as_ptr(), text.len());
+    assert_eq!(take_result(), Some((KIND_STRING, b"HI".to_vec())));
+}
+
+#[test]
+fn errors_are_serialized_with_the_error_codec() {
+    assert_eq!(__wasmworker_export_checked_div(1, 0), 0);
+    assert_eq!(app_error(), json!({ "kind": "DivisionByZero" }));
+
+    __wasmworker_export_checked_div(i32::MIN, -1);
+    assert_eq!(app_error(), json!({ "kind": "Overflow", "limit": i32::MAX }));
+}
+
+#[test]
+fn errors_without_a_codec_are_sent_as_text() {
+    __wasmworker_export_shout("".as_ptr(), 0);
+    assert_eq!(
+        take_result(),
+        Some((STATUS_APP_ERROR | KIND_STRING, b"nothing to shout".to_vec()))
+    );
+}
+
+#[test]
+fn codec_exports_encode_errors_with_their_codec() {
+    let payload = br#"{"amount":5,"balance":8}"#;
+    __wasmworker_export_withdraw(payload.as_ptr(), payload.len());
+    assert_eq!(take_result(), Some((KIND_JSON, b"3".to_vec())));
+
+    let payload = br#"{"amount":9,"balance":8}"#;
+    __wasmworker_export_withdraw(payload.as_ptr(), payload.len());
+    assert_eq!(app_error(), json!({ "missing": 1 }));
+}
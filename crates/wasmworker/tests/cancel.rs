@@ -0,0 +1,37 @@
+use wasmworker::{is_cancelled, set_cancelled, take_chunks};
+
+#[wasmworker::export]
+fn spin(limit: u32) -> u32 {
+    let mut done = 0;
+    while done < limit {
+        if is_cancelled() {
+            break;
+        }
+        done += 1;
+    }
+    done
+}
+
+#[wasmworker::export(stream)]
+fn naturals() -> impl Iterator<Item = u32> {
+    0..
+}
+
+#[test]
+fn exports_observe_the_cancellation_flag() {
+    assert_eq!(__wasmworker_export_spin(10), 10);
+
+    set_cancelled(true);
+    assert_eq!(__wasmworker_export_spin(10), 0);
+    set_cancelled(false);
+}
+
+#[test]
+fn cancelled_streams_stop_iterating() {
+    set_cancelled(true);
+    // Would never return without the cancellation check
+    __wasmworker_export_naturals();
+    set_cancelled(false);
+
+    assert!(take_chunks().is_empty());
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "json")]
+
+use serde::{Deserialize, Serialize};
+use wasmworker::{take_result, KIND_JSON, KIND_STRING, STATUS_INVALID_PAYLOAD};
+
+#[derive(Debug, Deserialize)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct Bounds {
+    min: [f64; 2],
+    max: [f64; 2],
+}
+
+#[wasmworker::export(codec = "json")]
+fn bounds(points: Vec<Point>) -> Bounds {
+    let mut bounds = Bounds {
+        min: [f64::INFINITY; 2],
+        max: [f64::NEG_INFINITY; 2],
+    };
+    for point in points {
+        bounds.min = [bounds.min[0].min(point.x), bounds.min[1].min(point.y)];
+        bounds.max = [bounds.max[0].max(point.x), bounds.max[1].max(point.y)];
+    }
+    bounds
+}
+
+#[wasmworker::export(codec = "json")]
+fn label(name: String, count: u32) -> String {
+    format!("{name} x{count}")
+}
+
+#[wasmworker::export(codec = "json")]
+fn ping() -> bool {
+    true
+}
+
+fn call(export: extern "C" fn(*const u8, usize), payload: &str) -> (u32, String) {
+    export(payload.as_ptr(), payload.len());
+    let (kind, bytes) = take_result().expect("result slot should be filled");
+    (kind, String::from_utf8(bytes).unwrap())
+}
+
+#[test]
+fn single_parameter_receives_the_whole_payload() {
+    let (kind, json) = call(
+        __wasmworker_export_bounds,
+        r#"[{"x": 1, "y": 5}, {"x": -2, "y": 3}]"#,
+    );
+
+    assert_eq!(kind, KIND_JSON);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({ "min": [-2.0, 3.0], "max": [1.0, 5.0] })
+    );
+}
+
+#[test]
+fn multiple_parameters_are_read_by_name() {
+    let (kind, json) = call(
+        __wasmworker_export_label,
+        r#"{"count": 3, "name": "apples"}"#,
+    );
+
+    assert_eq!(kind, KIND_JSON);
+    assert_eq!(json, r#""apples x3""#);
+}
+
+#[test]
+fn parameterless_exports_ignore_the_payload() {
+    let (kind, json) = call(__wasmworker_export_ping, "null");
+
+    assert_eq!(kind, KIND_JSON);
+    assert_eq!(json, "true");
+}
+
+#[test]
+fn mismatched_payloads_are_reported_as_invalid() {
+    let (kind, message) = call(__wasmworker_export_bounds, r#"{"x": 1}"#);
+
+    assert_eq!(kind, STATUS_INVALID_PAYLOAD | KIND_STRING);
+    assert!(message.contains("invalid type"), "{message}");
+
+    let (kind, message) = call(__wasmworker_export_label, r#"{"name": "apples"}"#);
+
+    assert_eq!(kind, STATUS_INVALID_PAYLOAD | KIND_STRING);
+    assert!(message.contains("missing field `count`"), "{message}");
+}
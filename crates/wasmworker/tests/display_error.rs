@@ -0,0 +1,27 @@
+use std::fmt;
+
+use wasmworker::{take_result, KIND_STRING, STATUS_APP_ERROR};
+
+struct DivisionByZero;
+
+impl fmt::Display for DivisionByZero {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("division by zero")
+    }
+}
+
+#[wasmworker::export]
+fn checked_div(a: i32, b: i32) -> Result<i32, DivisionByZero> {
+    a.checked_div(b).ok_or(DivisionByZero)
+}
+
+#[test]
+fn errors_are_sent_as_their_display_text() {
+    assert_eq!(__wasmworker_export_checked_div(6, 3), 2);
+    assert!(take_result().is_none());
+
+    assert_eq!(__wasmworker_export_checked_div(1, 0), 0);
+    let (kind, bytes) = take_result().unwrap();
+    assert_eq!(kind, STATUS_APP_ERROR | KIND_STRING);
+    assert_eq!(bytes, b"division by zero");
+}
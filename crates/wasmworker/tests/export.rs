@@ -0,0 +1,142 @@
+//! The generated wrappers are plain `extern "C"` functions, so they can be
+//! exercised on the host as well as on wasm32.
+
+#[wasmworker::export]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[wasmworker::export]
+fn fib(n: u32) -> u64 {
+    if n <= 1 {
+        return n as u64;
+    }
+    fib(n - 1) + fib(n - 2)
+}
+
+#[wasmworker::export]
+fn is_even(x: i64) -> bool {
+    x % 2 == 0
+}
+
+#[wasmworker::export]
+fn negate(flag: bool) -> bool {
+    !flag
+}
+
+#[wasmworker::export(name = "scale")]
+fn scale_by_half(x: f64) -> f64 {
+    x * 0.5
+}
+
+#[wasmworker::export]
+fn noop() {}
+
+#[test]
+fn original_functions_are_untouched() {
+    assert_eq!(add(2, 3), 5);
+    assert_eq!(fib(10), 55);
+}
+
+#[test]
+fn scalar_wrappers_pass_values_through() {
+    assert_eq!(__wasmworker_export_add(2, 3), 5);
+    assert_eq!(__wasmworker_export_fib(20), 6765);
+    assert_eq!(__wasmworker_export_scale_by_half(3.0), 1.5);
+    __wasmworker_export_noop();
+}
+
+#[test]
+fn bools_cross_the_boundary_as_i32() {
+    assert_eq!(__wasmworker_export_is_even(4), 1);
+    assert_eq!(__wasmworker_export_is_even(5), 0);
+    assert_eq!(__wasmworker_export_negate(0), 1);
+    assert_eq!(__wasmworker_export_negate(7), 0);
+}
+
+#[wasmworker::export]
+fn sum_bytes(data: &[u8]) -> u32 {
+    data.iter().map(|&b| b as u32).sum()
+}
+
+#[wasmworker::export]
+fn count_owned(data: Vec<u8>, extra: u32) -> u32 {
+    data.len() as u32 + extra
+}
+
+#[test]
+fn buffers_are_passed_as_ptr_and_len() {
+    let bytes = [1u8, 2, 3, 4];
+    assert_eq!(
+        __wasmworker_export_sum_bytes(bytes.as_ptr(), bytes.len()),
+        10
+    );
+    assert_eq!(
+        __wasmworker_export_count_owned(bytes.as_ptr(), bytes.len(), 1),
+        5
+    );
+    assert_eq!(__wasmworker_export_sum_bytes(std::ptr::null(), 0), 0);
+}
+
+#[test]
+fn allocator_round_trip() {
+    let ptr = wasmworker::ww_alloc(16);
+    assert!(!ptr.is_null());
+    unsafe {
+        ptr.write_bytes(7, 16);
+        assert_eq!(__wasmworker_export_sum_bytes(ptr, 16), 112);
+        wasmworker::ww_free(ptr, 16);
+    }
+
+    let empty = wasmworker::ww_alloc(0);
+    unsafe { wasmworker::ww_free(empty, 0) };
+}
+
+#[wasmworker::export]
+fn greet(name: &str) -> String {
+    format!("Hello, {name}!")
+}
+
+#[wasmworker::export]
+fn reversed(data: Vec<u8>) -> Vec<u8> {
+    data.into_iter().rev().collect()
+}
+
+#[test]
+fn strings_are_returned_through_the_result_slot() {
+    let name = "Ferris";
+    __wasmworker_export_greet(name.as_ptr(), name.len());
+
+    let (kind, bytes) = wasmworker::take_result().expect("result slot should be filled");
+    assert_eq!(kind, wasmworker::KIND_STRING);
+    assert_eq!(bytes, b"Hello, Ferris!");
+    assert!(wasmworker::take_result().is_none());
+}
+
+#[test]
+fn byte_vectors_are_returned_through_the_result_slot() {
+    let bytes = [1u8, 2, 3];
+    __wasmworker_export_reversed(bytes.as_ptr(), bytes.len());
+
+    let (kind, bytes) = wasmworker::take_result().expect("result slot should be filled");
+    assert_eq!(kind, wasmworker::KIND_BYTES);
+    assert_eq!(bytes, [3, 2, 1]);
+}
+
+#[test]
+fn result_slot_is_freed_with_ww_free() {
+    wasmworker::__private::set_result(wasmworker::KIND_BYTES, vec![0; 32]);
+
+    // Mirror what the runtime does: read the slot, then free the buffer.
+    let slot = wasmworker::ww_result() as *const usize;
+    unsafe {
+        let kind = *(slot as *const u32);
+        assert_eq!(kind, wasmworker::KIND_BYTES);
+        let ptr = *slot.add(1) as *mut u8;
+        let len = *slot.add(2);
+        assert_eq!(len, 32);
+        *(slot as *mut u32) = wasmworker::KIND_NONE;
+        wasmworker::ww_free(ptr, len);
+    }
+    assert!(wasmworker::take_result().is_none());
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "json")]
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use wasmworker::{call_host, set_host_function, HostError};
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Config {
+    scale: f64,
+}
+
+#[test]
+fn results_are_deserialized() {
+    set_host_function("fetch_config", |args| {
+        assert_eq!(args, [json!("default")]);
+        Ok(json!({ "scale": 2.5 }))
+    });
+
+    let config: Config = call_host("fetch_config", &("default",)).unwrap();
+    assert_eq!(config, Config { scale: 2.5 });
+}
+
+#[test]
+fn arguments_are_spread_like_the_runtime_does() {
+    set_host_function("echo", |args| Ok(Value::Array(args.to_vec())));
+
+    let none: Vec<Value> = call_host("echo", &()).unwrap();
+    assert_eq!(none, Vec::<Value>::new());
+
+    let spread: Vec<Value> = call_host("echo", &(1, "two")).unwrap();
+    assert_eq!(spread, [json!(1), json!("two")]);
+
+    let single: Vec<Value> = call_host("echo", &json!({ "id": 3 })).unwrap();
+    assert_eq!(single, [json!({ "id": 3 })]);
+}
+
+#[test]
+fn failures_are_reported() {
+    set_host_function("offline", |_| Err("network unreachable".into()));
+    set_host_function("number", |_| Ok(json!(4)));
+
+    let failed = call_host::<_, Value>("offline", &());
+    assert_eq!(failed, Err(HostError::Failed("network unreachable".into())));
+
+    let missing = call_host::<_, Value>("missing", &());
+    assert_eq!(
+        missing,
+        Err(HostError::Failed(
+            "No host function named \"missing\"".into()
+        ))
+    );
+
+    let mismatched = call_host::<_, String>("number", &());
+    assert!(matches!(mismatched, Err(HostError::Json(_))));
+}
@@ -0,0 +1,28 @@
+#![cfg(feature = "log")]
+
+use log::{Level, LevelFilter};
+use wasmworker::{init_logger, take_logs};
+
+#[wasmworker::export]
+fn checked_sqrt(x: f64) -> f64 {
+    if x < 0.0 {
+        log::warn!("negative input {x}, returning NaN");
+    }
+    log::debug!("sqrt({x})");
+    x.sqrt()
+}
+
+#[test]
+fn records_up_to_the_level_are_forwarded() {
+    init_logger(LevelFilter::Warn).unwrap();
+    // The logger is global, so installing it again fails
+    assert!(init_logger(LevelFilter::Trace).is_err());
+
+    assert_eq!(__wasmworker_export_checked_sqrt(4.0), 2.0);
+    assert!(__wasmworker_export_checked_sqrt(-1.0).is_nan());
+
+    assert_eq!(
+        take_logs(),
+        [(Level::Warn, "negative input -1, returning NaN".to_owned())]
+    );
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "json")]
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Add two 32-bit integers
+#[wasmworker::export]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+/// Say "hello"
+///
+/// Second paragraph with a \ backslash.
+#[wasmworker::export(name = "hello")]
+fn greet(name: &str, bytes: &[u8]) -> String {
+    format!("{name} {}", bytes.len())
+}
+
+#[derive(Deserialize)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[wasmworker::export(codec = "json")]
+fn lengths(points: Vec<Point>) -> Vec<f64> {
+    points.iter().map(|p| p.x.hypot(p.y)).collect()
+}
+
+#[wasmworker::export(stream)]
+fn countdown(from: u32) -> impl Iterator<Item = u32> {
+    (0..=from).rev()
+}
+
+#[wasmworker::export]
+fn reset() {}
+
+#[wasmworker::export]
+fn clamp(r#type: i32, max: i32) -> i32 {
+    r#type.min(max)
+}
+
+fn manifest(record: &str) -> Value {
+    serde_json::from_str(record).unwrap()
+}
+
+#[test]
+fn records_describe_direct_exports() {
+    assert_eq!(
+        manifest(__wasmworker_manifest_add),
+        json!({
+            "name": "add",
+            "params": [{ "name": "a", "type": "i32" }, { "name": "b", "type": "i32" }],
+            "returns": "i32",
+            "codec": null,
+            "stream": false,
+            "docs": "Add two 32-bit integers",
+        })
+    );
+    assert_eq!(
+        manifest(__wasmworker_manifest_reset),
+        json!({
+            "name": "reset",
+            "params": [],
+            "returns": null,
+            "codec": null,
+            "stream": false,
+            "docs": "",
+        })
+    );
+}
+
+#[test]
+fn records_use_the_export_name_and_keep_docs() {
+    let record = manifest(__wasmworker_manifest_greet);
+
+    assert_eq!(record["name"], "hello");
+    assert_eq!(
+        record["params"],
+        json!([{ "name": "name", "type": "&str" }, { "name": "bytes", "type": "&[u8]" }])
+    );
+    assert_eq!(
+        record["docs"],
+        "Say \"hello\"\n\nSecond paragraph with a \\ backslash."
+    );
+}
+
+#[test]
+fn records_name_raw_parameters_without_prefix() {
+    let record = manifest(__wasmworker_manifest_clamp);
+    assert_eq!(
+        record["params"],
+        json!([{ "name": "type", "type": "i32" }, { "name": "max", "type": "i32" }])
+    );
+}
+
+#[test]
+fn records_describe_codecs_and_streams() {
+    let record = manifest(__wasmworker_manifest_lengths);
+    assert_eq!(record["codec"], "json");
+    assert_eq!(record["params"][0]["type"], "Vec<Point>");
+    assert_eq!(record["returns"], "Vec<f64>");
+
+    let record = manifest(__wasmworker_manifest_countdown);
+    assert_eq!(record["stream"], true);
+    assert_eq!(record["returns"], "impl Iterator<Item = u32>");
+}
+
+#[test]
+fn records_are_single_lines() {
+    for record in [
+        __wasmworker_manifest_add,
+        __wasmworker_manifest_greet,
+        __wasmworker_manifest_lengths,
+        __wasmworker_manifest_countdown,
+    ] {
+        assert!(!record.contains('\n'));
+    }
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "msgpack")]
+
+use serde::{Deserialize, Serialize};
+use wasmworker::{take_result, KIND_MSGPACK, KIND_STRING, STATUS_INVALID_PAYLOAD};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[wasmworker::export(codec = "msgpack")]
+fn translate(points: Vec<Point>, dx: f64) -> Vec<Point> {
+    points
+        .into_iter()
+        .map(|point| Point {
+            x: point.x + dx,
+            y: point.y,
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct Args {
+    points: Vec<Point>,
+    dx: f64,
+}
+
+fn call(export: extern "C" fn(*const u8, usize), payload: &[u8]) -> (u32, Vec<u8>) {
+    export(payload.as_ptr(), payload.len());
+    take_result().expect("result slot should be filled")
+}
+
+#[test]
+fn structs_are_encoded_as_named_maps() {
+    let payload = rmp_serde::to_vec_named(&Args {
+        points: vec![Point { x: 1.0, y: 2.0 }],
+        dx: 0.5,
+    })
+    .unwrap();
+
+    let (kind, bytes) = call(__wasmworker_export_translate, &payload);
+
+    assert_eq!(kind, KIND_MSGPACK);
+    let value: serde_json::Value = rmp_serde::from_slice(&bytes).unwrap();
+    assert_eq!(value, serde_json::json!([{ "x": 1.5, "y": 2.0 }]));
+}
+
+#[test]
+fn integers_are_accepted_for_float_fields() {
+    // `{ points: [{ x: 1, y: 2.5 }], dx: -3 }` as encoded by the runtime,
+    // which writes integral numbers as MessagePack integers
+    let payload = [
+        0x82, 0xa6, b'p', b'o', b'i', b'n', b't', b's', 0x91, 0x82, 0xa1, b'x', 0x01, 0xa1, b'y',
+        0xcb, 0x40, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xa2, b'd', b'x', 0xfd,
+    ];
+
+    let (kind, bytes) = call(__wasmworker_export_translate, &payload);
+
+    assert_eq!(kind, KIND_MSGPACK);
+    let value: serde_json::Value = rmp_serde::from_slice(&bytes).unwrap();
+    assert_eq!(value, serde_json::json!([{ "x": -2.0, "y": 2.5 }]));
+}
+
+#[test]
+fn malformed_payloads_are_reported_as_invalid() {
+    // 0xc1 is never used in MessagePack
+    let (kind, message) = call(__wasmworker_export_translate, &[0xc1]);
+
+    assert_eq!(kind, STATUS_INVALID_PAYLOAD | KIND_STRING);
+    assert!(!message.is_empty());
+}
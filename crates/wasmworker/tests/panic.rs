@@ -0,0 +1,38 @@
+use std::panic;
+
+use wasmworker::take_panic;
+
+#[wasmworker::export]
+fn divide(a: i32, b: i32) -> i32 {
+    a / b
+}
+
+#[test]
+fn panics_are_recorded_once_an_export_has_run() {
+    // Unwinding out of the `extern "C"` wrapper would abort, so the wrapper
+    // only installs the hook and the function itself panics
+    assert_eq!(__wasmworker_export_divide(6, 3), 2);
+    assert!(take_panic().is_none());
+
+    let line = line!() + 1;
+    let result = panic::catch_unwind(|| panic!("bad input: {}", 7));
+    assert!(result.is_err());
+
+    let (message, location) = take_panic().unwrap();
+    assert_eq!(message, "bad input: 7");
+    assert!(location.starts_with(&format!("{}:{line}:", file!())));
+
+    // Taking the panic clears it
+    assert!(take_panic().is_none());
+}
+
+#[test]
+fn later_panics_replace_earlier_ones() {
+    __wasmworker_export_divide(1, 1);
+
+    let _ = panic::catch_unwind(|| divide(1, 0));
+    let _ = panic::catch_unwind(|| "x".parse::<i32>().unwrap());
+
+    let (message, _) = take_panic().unwrap();
+    assert!(message.starts_with("called `Result::unwrap()` on an `Err` value"));
+}
@@ -0,0 +1,28 @@
+use wasmworker::{shared_region, ww_alloc, ww_free};
+
+// What an export taking a region's offset and length does with it
+fn invert(offset: usize, len: usize) {
+    let pixels = unsafe { shared_region(offset, len) };
+    for pixel in pixels {
+        *pixel = 255 - *pixel;
+    }
+}
+
+#[test]
+fn regions_are_borrowed_in_place() {
+    let ptr = ww_alloc(4);
+    unsafe { ptr.copy_from_nonoverlapping([0, 10, 200, 255].as_ptr(), 4) };
+
+    invert(ptr as usize, 4);
+    assert_eq!(
+        unsafe { shared_region(ptr as usize, 4) },
+        &[255, 245, 55, 0]
+    );
+
+    unsafe { ww_free(ptr, 4) };
+}
+
+#[test]
+fn empty_regions_borrow_nothing() {
+    assert!(unsafe { shared_region(0, 0) }.is_empty());
+}
@@ -0,0 +1,105 @@
+use wasmworker::{take_chunks, take_result, KIND_BOOL, KIND_BYTES, KIND_STRING, KIND_U32};
+#[cfg(feature = "json")]
+use wasmworker::{KIND_JSON, STATUS_INVALID_PAYLOAD};
+
+#[wasmworker::export(stream)]
+fn countdown(from: u32) -> impl Iterator<Item = u32> {
+    (0..=from).rev()
+}
+
+#[wasmworker::export(stream)]
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace().map(str::to_uppercase).collect()
+}
+
+#[wasmworker::export(stream)]
+fn pushed(count: u64) {
+    for i in 0..count {
+        wasmworker::emit(i % 2 == 0);
+        wasmworker::emit(vec![i as u8]);
+    }
+}
+
+#[cfg(feature = "json")]
+#[wasmworker::export(stream, codec = "json")]
+fn pairs(limit: u64) -> impl Iterator<Item = (u64, u64)> {
+    (1..=limit).map(|i| (i, i * i))
+}
+
+#[test]
+fn iterator_items_are_emitted_in_order() {
+    __wasmworker_export_countdown(2);
+
+    let chunks = take_chunks();
+    assert_eq!(
+        chunks,
+        vec![
+            (KIND_U32, 2u32.to_le_bytes().to_vec()),
+            (KIND_U32, 1u32.to_le_bytes().to_vec()),
+            (KIND_U32, 0u32.to_le_bytes().to_vec()),
+        ]
+    );
+    assert!(take_result().is_none());
+}
+
+#[test]
+fn string_items_are_emitted_as_utf8() {
+    let text = "hello streaming world";
+    __wasmworker_export_words(text.as_ptr(), text.len());
+
+    let chunks = take_chunks();
+    let words: Vec<_> = chunks
+        .iter()
+        .map(|(kind, bytes)| {
+            assert_eq!(*kind, KIND_STRING);
+            std::str::from_utf8(bytes).unwrap()
+        })
+        .collect();
+    assert_eq!(words, ["HELLO", "STREAMING", "WORLD"]);
+}
+
+#[test]
+fn unit_stream_exports_emit_items_themselves() {
+    __wasmworker_export_pushed(2);
+
+    assert_eq!(
+        take_chunks(),
+        vec![
+            (KIND_BOOL, vec![1]),
+            (KIND_BYTES, vec![0]),
+            (KIND_BOOL, vec![0]),
+            (KIND_BYTES, vec![1]),
+        ]
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn codec_stream_items_are_encoded() {
+    let payload = b"3";
+    __wasmworker_export_pairs(payload.as_ptr(), payload.len());
+
+    let chunks = take_chunks();
+    assert!(chunks.iter().all(|(kind, _)| *kind == KIND_JSON));
+    let items: Vec<_> = chunks
+        .iter()
+        .map(|(_, bytes)| std::str::from_utf8(bytes).unwrap())
+        .collect();
+    assert_eq!(items, ["[1,1]", "[2,4]", "[3,9]"]);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn invalid_codec_payloads_emit_nothing() {
+    let payload = b"\"three\"";
+    __wasmworker_export_pairs(payload.as_ptr(), payload.len());
+
+    assert!(take_chunks().is_empty());
+    let (kind, _) = take_result().expect("result slot should be filled");
+    assert_eq!(kind, STATUS_INVALID_PAYLOAD | KIND_STRING);
+}
+
+#[test]
+fn original_stream_functions_are_untouched() {
+    assert_eq!(countdown(1).collect::<Vec<_>>(), [1, 0]);
+}
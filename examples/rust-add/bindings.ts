@@ -0,0 +1,75 @@
+// Generated by wasmworker-bindgen, do not edit.
+
+import type { Bindings, CallOptions } from '@wasmworker/sdk';
+
+/** A 2D point, `{ x, y }` in JavaScript */
+export interface Point {
+  x: number;
+  y: number;
+}
+
+/** Axis-aligned bounding box of a set of points */
+export interface Bounds {
+  min: Point;
+  max: Point;
+  count: number;
+}
+
+export interface Api {
+  /** Forward `log` records to the main thread, debug ones too if `verbose` */
+  enable_logging(verbose: boolean, options?: CallOptions): Promise<void>;
+  /** Add two 32-bit integers */
+  add(a: number, b: number, options?: CallOptions): Promise<number>;
+  /** Calculate fibonacci number (recursive, for benchmarking) */
+  fib(n: number, options?: CallOptions): Promise<bigint>;
+  /** Stream the first `n` fibonacci numbers, one chunk each */
+  fib_sequence(n: number, options?: CallOptions): AsyncIterable<bigint>;
+  /** Multiply a number by 2 */
+  double(x: number, options?: CallOptions): Promise<number>;
+  /** Subtract two numbers */
+  subtract(a: number, b: number, options?: CallOptions): Promise<number>;
+  /** Multiply two numbers */
+  multiply(a: number, b: number, options?: CallOptions): Promise<number>;
+  /** Adler-32 checksum of a byte buffer passed in from JavaScript */
+  checksum(data: Uint8Array | ArrayBuffer, options?: CallOptions): Promise<number>;
+  /** Build a greeting for `name`, returned to JavaScript as a string */
+  greet(name: string, options?: CallOptions): Promise<string>;
+  /** Compute the bounding box of a list of points, passed as JSON */
+  bounds(points: Point[], options?: CallOptions): Promise<Bounds>;
+  /** Total length of a polyline, passed as MessagePack */
+  path_length(points: Point[], options?: CallOptions): Promise<number>;
+  /**
+   * Scale `x` by the `scale` setting, read from the page through the
+   * `fetch_config` host function
+   *
+   * @throws WasmWorkerError with code `APP_ERROR` and `details` of type `string`
+   */
+  scaled(x: number, options?: CallOptions): Promise<number>;
+}
+
+export const bindings: Bindings<Api> = (target) => ({
+  enable_logging: (verbose, options) =>
+    target.call<unknown, void>('enable_logging', [verbose], options),
+  add: (a, b, options) =>
+    target.call<unknown, number>('add', [a, b], options),
+  fib: (n, options) =>
+    target.call<unknown, bigint>('fib', [n], options),
+  fib_sequence: (n, options) =>
+    target.stream<unknown, bigint>('fib_sequence', [n], options),
+  double: (x, options) =>
+    target.call<unknown, number>('double', [x], options),
+  subtract: (a, b, options) =>
+    target.call<unknown, number>('subtract', [a, b], options),
+  multiply: (a, b, options) =>
+    target.call<unknown, number>('multiply', [a, b], options),
+  checksum: (data, options) =>
+    target.call<unknown, number>('checksum', [data], options),
+  greet: (name, options) =>
+    target.call<unknown, string>('greet', [name], options),
+  bounds: (points, options) =>
+    target.call<unknown, Bounds>('bounds', points, { ...options, codec: 'json' }),
+  path_length: (points, options) =>
+    target.call<unknown, number>('path_length', points, { ...options, codec: 'msgpack' }),
+  scaled: (x, options) =>
+    target.call<unknown, number>('scaled', [x], options),
+});
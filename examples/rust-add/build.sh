@@ -9,9 +9,15 @@ mkdir -p dist
 # Build with cargo
 cargo build --target wasm32-unknown-unknown --release
 
-# Copy the wasm file to dist
-cp target/wasm32-unknown-unknown/release/rust_add.wasm dist/module.wasm
+# Copy the wasm file to dist (the example is part of the root Cargo workspace)
+cp ../../target/wasm32-unknown-unknown/release/rust_add.wasm dist/module.wasm
+
+# Regenerate the TypeScript bindings used by the demo
+cargo run --quiet -p wasmworker-bindgen -- . -o bindings.ts
 
 # Get the file size
 SIZE=$(wc -c < dist/module.wasm | tr -d ' ')
 echo "Built module.wasm (${SIZE} bytes)"
+
+# Digest to pass as `LoadOptions.integrity`
+echo "Integrity: $(cargo run --quiet -p wasmworker-integrity -- dist/module.wasm)"
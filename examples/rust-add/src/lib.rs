@@ -1,32 +1,138 @@
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+
+/// Forward `log` records to the main thread, debug ones too if `verbose`
+#[wasmworker::export]
+pub fn enable_logging(verbose: bool) {
+    let level = if verbose {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Info
+    };
+    // Installed by an earlier call, only the level changes
+    if wasmworker::init_logger(level).is_err() {
+        log::set_max_level(level);
+    }
+}
+
 /// Add two 32-bit integers
-#[no_mangle]
-pub extern "C" fn add(a: i32, b: i32) -> i32 {
+#[wasmworker::export]
+pub fn add(a: i32, b: i32) -> i32 {
     a + b
 }
 
 /// Calculate fibonacci number (recursive, for benchmarking)
-#[no_mangle]
-pub extern "C" fn fib(n: u32) -> u64 {
+#[wasmworker::export]
+pub fn fib(n: u32) -> u64 {
     if n <= 1 {
         return n as u64;
     }
+    // Only the top of the call tree polls, so cancellation stays cheap
+    if n > 30 && wasmworker::is_cancelled() {
+        return 0;
+    }
     fib(n - 1) + fib(n - 2)
 }
 
+/// Stream the first `n` fibonacci numbers, one chunk each
+#[wasmworker::export(stream)]
+pub fn fib_sequence(n: u32) -> impl Iterator<Item = u64> {
+    std::iter::successors(Some((0u64, 1u64)), |&(a, b)| Some((b, a.checked_add(b)?)))
+        .map(|(a, _)| a)
+        .take(n as usize)
+}
+
 /// Multiply a number by 2
-#[no_mangle]
-pub extern "C" fn double(x: i32) -> i32 {
+#[wasmworker::export]
+pub fn double(x: i32) -> i32 {
     x * 2
 }
 
 /// Subtract two numbers
-#[no_mangle]
-pub extern "C" fn subtract(a: i32, b: i32) -> i32 {
+#[wasmworker::export]
+pub fn subtract(a: i32, b: i32) -> i32 {
     a - b
 }
 
 /// Multiply two numbers
-#[no_mangle]
-pub extern "C" fn multiply(a: i32, b: i32) -> i32 {
+#[wasmworker::export]
+pub fn multiply(a: i32, b: i32) -> i32 {
     a * b
 }
+
+/// Adler-32 checksum of a byte buffer passed in from JavaScript
+#[wasmworker::export]
+pub fn checksum(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    log::debug!("checksum of {} bytes", data.len());
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+    (b << 16) | a
+}
+
+/// Build a greeting for `name`, returned to JavaScript as a string
+#[wasmworker::export]
+pub fn greet(name: &str) -> String {
+    log::info!("greeting {name}");
+    format!("Hello, {name}! Greetings from Rust.")
+}
+
+/// A 2D point, `{ x, y }` in JavaScript
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Axis-aligned bounding box of a set of points
+#[derive(Serialize)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+    pub count: usize,
+}
+
+/// Compute the bounding box of a list of points, passed as JSON
+#[wasmworker::export(codec = "json")]
+pub fn bounds(points: Vec<Point>) -> Bounds {
+    let mut min = Point {
+        x: f64::INFINITY,
+        y: f64::INFINITY,
+    };
+    let mut max = Point {
+        x: f64::NEG_INFINITY,
+        y: f64::NEG_INFINITY,
+    };
+    for point in &points {
+        min.x = min.x.min(point.x);
+        min.y = min.y.min(point.y);
+        max.x = max.x.max(point.x);
+        max.y = max.y.max(point.y);
+    }
+    Bounds {
+        min,
+        max,
+        count: points.len(),
+    }
+}
+
+/// Total length of a polyline, passed as MessagePack
+#[wasmworker::export(codec = "msgpack")]
+pub fn path_length(points: Vec<Point>) -> f64 {
+    points
+        .windows(2)
+        .map(|pair| (pair[1].x - pair[0].x).hypot(pair[1].y - pair[0].y))
+        .sum()
+}
+
+/// Scale `x` by the `scale` setting, read from the page through the
+/// `fetch_config` host function
+#[wasmworker::export]
+pub fn scaled(x: f64) -> Result<f64, String> {
+    let factor: f64 =
+        wasmworker::call_host("fetch_config", &("scale",)).map_err(|error| error.to_string())?;
+    Ok(x * factor)
+}
@@ -1,11 +1,30 @@
 import type {
   LoadOptions,
   CallOptions,
+  CallMsg,
+  ExportDescription,
+  FsReadMsg,
+  FsWriteMsg,
+  HostCallMsg,
+  HostFunction,
+  LogRecord,
   PendingRequest,
+  SharedAllocMsg,
+  SharedFreeMsg,
+  SharedMemoryType,
+  SharedRegion,
   StreamingRequest,
+  ThreadInitMsg,
+  ThreadsMsg,
   WorkerResponse,
   WasmWorkerError,
+  WasmWorkerEvents,
+  WasmWorkerFs,
 } from './types.js';
+import { HostStatus, writeHostReply } from './host.js';
+import { absoluteUrl, checkSource, compileResponse, postableBytes, postableFiles } from './source.js';
+import { IntegrityError, checkIntegrity } from './integrity.js';
+import { importsMemory, sharedMemoryImport } from './worker/threads.js';
 
 /**
  * Generate a unique ID for messages
@@ -19,30 +38,185 @@ function generateId(): string {
   return `${Date.now()}-${Math.random().toString(36).slice(2, 11)}`;
 }
 
+/**
+ * Number of cancellation flags shared with the worker
+ *
+ * A call with sequence number `seq` is cancelled when slot `seq % CANCEL_SLOTS`
+ * holds `seq`, so flags are only lost with this many cancellations in flight.
+ */
+const CANCEL_SLOTS = 64;
+
+/**
+ * Shared memory each threaded module imports, so workers given the compiled
+ * module can create it without its bytes
+ */
+const memoryTypes = new WeakMap<WebAssembly.Module, SharedMemoryType>();
+
+/**
+ * Split `LoadOptions.init` into the values posted to the worker and the
+ * names of the host functions, which can't be posted
+ */
+function splitInit(init: Record<string, unknown> = {}): {
+  values: Record<string, unknown>;
+  hostFunctions: string[];
+} {
+  const entries = Object.entries(init);
+  return {
+    values: Object.fromEntries(entries.filter(([, value]) => typeof value !== 'function')),
+    hostFunctions: entries.filter(([, value]) => typeof value === 'function').map(([name]) => name),
+  };
+}
+
 /**
  * Main WasmWorker class that manages WASM execution in a WebWorker
  */
-export class WasmWorker {
+export class WasmWorker<TApi = unknown> {
+  /**
+   * Typed methods for the module's exports, built by `LoadOptions.bindings`
+   */
+  api!: TApi;
+
   private worker: Worker | null = null;
+  // Run the threads of a threaded module, on the worker's memory
+  private helpers: Worker[] = [];
   private pendingRequests = new Map<string, PendingRequest>();
   private streamingRequests = new Map<string, StreamingRequest>();
   private initialized = false;
+  private nextSeq = 1;
+  // Shared with the worker so running exports can observe cancellation,
+  // only available on cross-origin isolated pages
+  private cancelFlags: Int32Array | null = null;
+  private loadOptions: LoadOptions<TApi> | null = null;
+  // Compiled by the first worker, used to respawn quickly after a timeout
+  private module: WebAssembly.Module | null = null;
+  // Shared memory of the module, for the views of its shared regions
+  private memory: WebAssembly.Memory | null = null;
+  private regions = new Map<string, SharedRegion>();
+  private manifest: ExportDescription[] = [];
+  // Messages held back while a respawned worker initializes
+  private backlog: Array<{ message: unknown; transfer: Transferable[] }> | null = null;
+  private listeners: { [E in keyof WasmWorkerEvents]: Set<(value: WasmWorkerEvents[E]) => void> } = {
+    log: new Set(),
+  };
 
   private constructor() {}
 
   /**
    * Load a WASM module in a new WebWorker
    */
-  static async load(options: LoadOptions): Promise<WasmWorker> {
-    const instance = new WasmWorker();
+  static async load<TApi = unknown>(options: LoadOptions<TApi>): Promise<WasmWorker<TApi>> {
+    const instance = new WasmWorker<TApi>();
     await instance.init(options);
     return instance;
   }
 
+  /**
+   * Instantiate an already compiled module in a new WebWorker
+   *
+   * @internal Used by `WasmWorkerPool` to compile the module only once
+   */
+  static async fromModule<TApi = unknown>(
+    options: LoadOptions<TApi>,
+    module: WebAssembly.Module
+  ): Promise<WasmWorker<TApi>> {
+    const instance = new WasmWorker<TApi>();
+    await instance.init(options, module);
+    return instance;
+  }
+
+  /**
+   * The module compiled by the worker, once loaded
+   *
+   * @internal
+   */
+  get compiledModule(): WebAssembly.Module | null {
+    return this.module;
+  }
+
   /**
    * Initialize the worker with a WASM module
    */
-  private async init(options: LoadOptions): Promise<void> {
+  private async init(options: LoadOptions<TApi>, module?: WebAssembly.Module): Promise<void> {
+    checkSource(options);
+    this.loadOptions = options;
+
+    if (typeof SharedArrayBuffer === 'function' && globalThis.crossOriginIsolated) {
+      this.cancelFlags = new Int32Array(
+        new SharedArrayBuffer(CANCEL_SLOTS * Int32Array.BYTES_PER_ELEMENT)
+      );
+    }
+
+    // A response can't be posted, so it is compiled here
+    if (!module && options.moduleResponse) {
+      module = await this.compileModuleResponse(options.moduleResponse, options.integrity);
+    }
+
+    await this.spawn(options, module ?? options.module, options.files);
+    this.initialized = true;
+    this.api = options.bindings?.(this) as TApi;
+  }
+
+  /**
+   * Compile a module passed as a `Response`, failing like a fetch in the
+   * worker would
+   */
+  private async compileModuleResponse(
+    response: Response,
+    integrity?: string
+  ): Promise<WebAssembly.Module> {
+    if (!response.ok) {
+      throw this.createError('MODULE_FETCH_FAILED', `Failed to fetch module: ${response.statusText}`, {
+        status: response.status,
+        url: response.url,
+      });
+    }
+    try {
+      let module: WebAssembly.Module;
+      let bytes: ArrayBuffer | undefined;
+      // Unread copy, for the limits of a shared memory the module imports
+      const copy = integrity === undefined ? response.clone() : undefined;
+      if (copy) {
+        module = await compileResponse(response);
+      } else {
+        // The whole module is needed to check it before compiling
+        bytes = await response.arrayBuffer();
+        await checkIntegrity(bytes, integrity!);
+        module = await WebAssembly.compile(bytes);
+      }
+
+      if (importsMemory(module)) {
+        bytes ??= await copy!.arrayBuffer();
+        const memoryType = sharedMemoryImport(bytes);
+        if (memoryType) {
+          memoryTypes.set(module, memoryType);
+        }
+      } else {
+        void copy?.body?.cancel();
+      }
+      return module;
+    } catch (error) {
+      if (error instanceof IntegrityError) {
+        throw this.createError('INTEGRITY_MISMATCH', error.message, { ...error.details, url: response.url });
+      }
+      const errorMsg = error instanceof Error ? error.message : String(error);
+      throw this.createError('WASM_INIT_FAILED', `Failed to initialize WASM module: ${errorMsg}`, {
+        error: errorMsg,
+      });
+    }
+  }
+
+  /**
+   * Start a worker and instantiate the module in it
+   *
+   * A compiled `module` skips fetching and compiling the module's source.
+   * `files` are only given at load time, a respawned worker starts without
+   * them.
+   */
+  private spawn(
+    options: LoadOptions,
+    module?: WebAssembly.Module,
+    files?: LoadOptions['files']
+  ): Promise<void> {
     return new Promise((resolve, reject) => {
       try {
         // Create worker from the runtime script
@@ -61,19 +235,63 @@ export class WasmWorker {
         // Send init message
         const id = generateId();
         this.pendingRequests.set(id, {
-          resolve: () => {
-            this.initialized = true;
+          resolve: (value) => {
+            const result = value as {
+              module?: WebAssembly.Module;
+              manifest?: ExportDescription[];
+              memory?: WebAssembly.Memory | null;
+              memoryType?: SharedMemoryType | null;
+            };
+            this.module = result?.module ?? null;
+            this.manifest = result?.manifest ?? [];
+            this.memory = result?.memory ?? null;
+
+            if (this.module && result.memory && result.memoryType) {
+              memoryTypes.set(this.module, result.memoryType);
+              // Calls may start threads, so helpers are ready first
+              if ((options.threads ?? 0) > 0) {
+                this.startThreads(options, this.module, result.memory, result.memoryType).then(
+                  resolve,
+                  reject
+                );
+                return;
+              }
+            }
             resolve();
           },
           reject,
         });
 
-        this.worker.postMessage({
-          id,
-          type: 'init',
-          moduleUrl: options.moduleUrl,
-          init: options.init,
-        });
+        // Functions can't be posted, the worker calls back into them instead
+        const { values, hostFunctions } = splitInit(options.init);
+        const { bytes, transfer } =
+          !module && options.moduleBytes
+            ? postableBytes(options.moduleBytes)
+            : { bytes: undefined, transfer: [] };
+        const initialFiles = files
+          ? postableFiles(files, options.transferFiles)
+          : { files: undefined, transfer: [] };
+        this.worker.postMessage(
+          {
+            id,
+            type: 'init',
+            moduleUrl: options.moduleUrl,
+            moduleBytes: bytes,
+            cache: options.cache,
+            integrity: options.integrity,
+            wasi: options.wasi,
+            files: initialFiles.files,
+            // The worker would resolve a relative URL against its own script
+            bindgen: options.bindgen && { glueUrl: absoluteUrl(options.bindgen.glueUrl) },
+            threads: options.threads,
+            init: values,
+            hostFunctions,
+            module,
+            memoryType: module && memoryTypes.get(module),
+            cancelBuffer: this.cancelFlags?.buffer,
+          },
+          [...transfer, ...initialFiles.transfer]
+        );
       } catch (error) {
         const errorMsg = error instanceof Error ? error.message : String(error);
         reject(new Error(`Failed to create worker: ${errorMsg}`));
@@ -81,6 +299,103 @@ export class WasmWorker {
     });
   }
 
+  /**
+   * Post a message to the worker, or hold it back while the worker restarts
+   */
+  private post(message: unknown, transfer: Transferable[] = []): void {
+    if (this.backlog) {
+      this.backlog.push({ message, transfer });
+      return;
+    }
+    this.worker?.postMessage(message, transfer);
+  }
+
+  /**
+   * Whether the request with sequence number `seq` is the one the worker is
+   * running, rather than one queued behind it
+   *
+   * The worker handles requests in order, so this is the oldest one that is
+   * still outstanding.
+   */
+  private isRunning(seq: number): boolean {
+    let oldest = Infinity;
+    for (const pending of this.pendingRequests.values()) {
+      if (pending.seq !== undefined) {
+        oldest = Math.min(oldest, pending.seq);
+      }
+    }
+    for (const stream of this.streamingRequests.values()) {
+      if (!stream.done) {
+        oldest = Math.min(oldest, stream.seq);
+      }
+    }
+    return seq === oldest;
+  }
+
+  /**
+   * Replace a worker stuck in `fn` with a fresh instance of the module
+   *
+   * Pending calls are sent again to the new worker, except those whose
+   * payload was transferred away. Open streams cannot be resumed and fail.
+   */
+  private respawn(fn: string): void {
+    this.worker?.terminate();
+    this.worker = null;
+    this.stopThreads();
+    // The new worker has a new memory
+    this.memory = null;
+    this.regions.clear();
+
+    const restartError = () =>
+      this.createError('TIMEOUT', `Worker restarted after "${fn}" timed out`, {
+        function: fn,
+        restarted: true,
+      });
+
+    for (const stream of this.streamingRequests.values()) {
+      if (!stream.done) {
+        stream.error = restartError();
+        stream.done = true;
+        stream.notify?.();
+      }
+    }
+
+    const replay: CallMsg[] = [];
+    for (const [id, pending] of this.pendingRequests) {
+      if (pending.replay) {
+        replay.push(pending.replay);
+      } else {
+        this.pendingRequests.delete(id);
+        pending.reject(restartError());
+      }
+    }
+
+    this.backlog = replay.map((message) => ({ message, transfer: [] }));
+    this.spawn(this.loadOptions!, this.module ?? undefined).then(
+      () => {
+        const backlog = this.backlog ?? [];
+        this.backlog = null;
+        for (const { message, transfer } of backlog) {
+          this.worker?.postMessage(message, transfer);
+        }
+      },
+      (error: Error) => {
+        this.backlog = null;
+        for (const pending of this.pendingRequests.values()) {
+          pending.reject(error);
+        }
+        this.pendingRequests.clear();
+        for (const stream of this.streamingRequests.values()) {
+          if (!stream.done) {
+            stream.error = error;
+            stream.done = true;
+            stream.notify?.();
+          }
+        }
+      }
+    );
+  }
+
   /**
    * Handle messages from the worker
    */
@@ -92,6 +407,16 @@ export class WasmWorker {
       return;
     }
 
+    if (msg.type === 'log') {
+      this.log({ level: msg.level, message: msg.message });
+      return;
+    }
+
+    if (msg.type === 'host_call') {
+      void this.runHostFunction(msg);
+      return;
+    }
+
     const pending = this.pendingRequests.get(msg.id);
     const streaming = this.streamingRequests.get(msg.id);
 
@@ -107,27 +432,53 @@ export class WasmWorker {
         this.pendingRequests.delete(msg.id);
       }
       if (streaming) {
-        streaming.reject(error);
-        this.streamingRequests.delete(msg.id);
+        streaming.error = error;
+        streaming.done = true;
+        streaming.notify?.();
       }
     } else if (msg.type === 'stream_chunk') {
       if (streaming) {
         streaming.queue.push(msg.value);
-        if (streaming.resolve) {
-          streaming.resolve({ value: streaming.queue.shift(), done: false });
-        }
+        streaming.notify?.();
       }
     } else if (msg.type === 'stream_close') {
       if (streaming) {
         streaming.done = true;
-        if (streaming.resolve) {
-          streaming.resolve({ value: undefined, done: true });
-        }
-        this.streamingRequests.delete(msg.id);
+        streaming.notify?.();
       }
     }
   }
 
+  /**
+   * Deliver a guest log record to listeners, and the console if enabled
+   */
+  private log(record: LogRecord): void {
+    if (this.loadOptions?.logToConsole) {
+      console[record.level === 'trace' ? 'debug' : record.level](record.message);
+    }
+    for (const listener of this.listeners.log) {
+      listener(record);
+    }
+  }
+
+  /**
+   * Run a host function for the worker, which is blocked until the reply
+   * is written to the message's channel
+   */
+  private async runHostFunction(msg: HostCallMsg): Promise<void> {
+    const fn = this.loadOptions?.init?.[msg.name];
+    try {
+      if (typeof fn !== 'function') {
+        throw new Error(`No host function named "${msg.name}"`);
+      }
+      const value = await (fn as HostFunction)(...msg.args);
+      writeHostReply(msg.channel, HostStatus.Ok, JSON.stringify(value ?? null));
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      writeHostReply(msg.channel, HostStatus.Error, message);
+    }
+  }
+
   /**
    * Create a WasmWorkerError from error details
    */
@@ -139,6 +490,148 @@ export class WasmWorker {
     return error;
   }
 
+  /**
+   * Create the error a cancelled call or stream fails with
+   */
+  private cancelledError(fn: string, signal?: AbortSignal): Error {
+    return this.createError('CANCELLED', `Call to "${fn}" was cancelled`, {
+      function: fn,
+      reason: signal?.reason,
+    });
+  }
+
+  /**
+   * Create the error a call or stream that ran out of time fails with
+   */
+  private timeoutError(fn: string, timeoutMs: number): Error {
+    return this.createError('TIMEOUT', `Call to "${fn}" timed out after ${timeoutMs}ms`, {
+      function: fn,
+      timeoutMs,
+    });
+  }
+
+  /**
+   * Resolve the timeout of a call, 0 or less meaning none
+   */
+  private timeoutFor(options?: CallOptions): number | null {
+    const timeoutMs = options?.timeoutMs ?? this.loadOptions?.timeoutMs;
+    return timeoutMs !== undefined && timeoutMs > 0 ? timeoutMs : null;
+  }
+
+  /**
+   * Fail a call that ran out of time
+   *
+   * If the worker is stuck running it, the worker is replaced; a call still
+   * queued is only cancelled.
+   */
+  private expire(id: string, fn: string, timeoutMs: number): void {
+    const pending = this.pendingRequests.get(id);
+    if (!pending || pending.seq === undefined) {
+      return;
+    }
+
+    const running = this.isRunning(pending.seq);
+    this.pendingRequests.delete(id);
+    pending.reject(this.timeoutError(fn, timeoutMs));
+
+    if (running) {
+      this.respawn(fn);
+    } else {
+      this.cancel(id, pending.seq);
+    }
+  }
+
+  /**
+   * Tell the worker to stop a call or stream
+   *
+   * The flag lets a running export notice through `is_cancelled()`, the
+   * message covers workers without shared memory.
+   */
+  private cancel(id: string, seq: number): void {
+    if (this.cancelFlags) {
+      Atomics.store(this.cancelFlags, seq % this.cancelFlags.length, seq);
+    }
+    this.post({ id, type: 'cancel', seq });
+  }
+
+  /**
+   * Send a request answered with a result or an error, like a call without
+   * a timeout or cancellation
+   */
+  private request<T extends FsWriteMsg | FsReadMsg | ThreadInitMsg | ThreadsMsg | SharedAllocMsg | SharedFreeMsg>(
+    message: Omit<T, 'id'>,
+    transfer: Transferable[] = [],
+    target?: Worker
+  ): Promise<unknown> {
+    if (!this.worker) {
+      return Promise.reject(new Error('Worker not initialized'));
+    }
+
+    return new Promise((resolve, reject) => {
+      const id = generateId();
+      this.pendingRequests.set(id, { resolve, reject });
+      if (target) {
+        target.postMessage({ ...message, id }, transfer);
+      } else {
+        this.post({ ...message, id }, transfer);
+      }
+    });
+  }
+
+  /**
+   * Start the helper workers a threaded module's threads run in, each with
+   * its own instance on the worker's memory, and hand them to the worker
+   */
+  private async startThreads(
+    options: LoadOptions,
+    module: WebAssembly.Module,
+    memory: WebAssembly.Memory,
+    memoryType: SharedMemoryType
+  ): Promise<void> {
+    const count = options.threads ?? 0;
+    const busy = new SharedArrayBuffer(count * Int32Array.BYTES_PER_ELEMENT);
+    const { values, hostFunctions } = splitInit(options.init);
+
+    const ports = await Promise.all(
+      Array.from({ length: count }, async (_, index) => {
+        const helper = new Worker(new URL('./worker/runtime.ts', import.meta.url), { type: 'module' });
+        // Helpers log and call host functions like the worker
+        helper.addEventListener('message', this.handleMessage.bind(this));
+        this.helpers.push(helper);
+
+        const channel = new MessageChannel();
+        const message: Omit<ThreadInitMsg, 'id'> = {
+          type: 'thread_init',
+          module,
+          memory,
+          memoryType,
+          init: values,
+          hostFunctions,
+          wasi: options.wasi,
+          threads: count,
+          busy,
+          index,
+          port: channel.port1,
+        };
+        await this.request<ThreadInitMsg>(message, [channel.port1], helper);
+        return channel.port2;
+      })
+    );
+
+    // Sent ahead of calls held back while the worker restarts
+    await this.request<ThreadsMsg>({ type: 'threads', ports, busy }, ports, this.worker!);
+  }
+
+  /**
+   * Terminate the thread helpers, whose memory goes with the worker
+   */
+  private stopThreads(): void {
+    for (const helper of this.helpers) {
+      helper.terminate();
+    }
+    this.helpers = [];
+  }
+
   /**
    * Call a WASM function
    */
@@ -155,31 +648,70 @@ export class WasmWorker {
       throw new Error('Worker not ready');
     }
 
+    const signal = options?.signal;
+    if (signal?.aborted) {
+      throw this.cancelledError(fn, signal);
+    }
+
     return new Promise((resolve, reject) => {
       const id = generateId();
+      const seq = this.nextSeq++;
+      const message: CallMsg = {
+        id,
+        type: 'call',
+        fn,
+        payload,
+        codec: options?.codec,
+        seq,
+      };
+      const transfer = options?.transfer || [];
+
+      const onAbort = () => {
+        this.pendingRequests.delete(id);
+        this.cancel(id, seq);
+        settle();
+        reject(this.cancelledError(fn, signal));
+      };
+      signal?.addEventListener('abort', onAbort, { once: true });
+
+      const timeoutMs = this.timeoutFor(options);
+      const timer =
+        timeoutMs !== null ? setTimeout(() => this.expire(id, fn, timeoutMs), timeoutMs) : undefined;
+
+      const settle = () => {
+        signal?.removeEventListener('abort', onAbort);
+        clearTimeout(timer);
+      };
+
       this.pendingRequests.set(id, {
-        resolve: resolve as (value: unknown) => void,
-        reject,
+        resolve: (value) => {
+          settle();
+          resolve(value as TOut);
+        },
+        reject: (error) => {
+          settle();
+          reject(error);
+        },
+        seq,
+        // A transferred payload is gone once posted and cannot be sent again
+        replay: transfer.length === 0 ? message : undefined,
       });
 
-      this.worker!.postMessage(
-        {
-          id,
-          type: 'call',
-          fn,
-          payload,
-        },
-        options?.transfer || []
-      );
+      this.post(message, transfer);
     });
   }
 
   /**
-   * Stream data from a WASM function (stub for future implementation)
+   * Stream data from a WASM function
+   *
+   * Yields every chunk the export emits, in order, and finishes when the
+   * export returns. Errors are thrown after the chunks received before them.
+   * Aborting `options.signal` or leaving the loop early cancels the export.
    */
   async *stream<TIn = unknown, TChunk = unknown>(
     fn: string,
-    payload?: TIn
+    payload?: TIn,
+    options?: CallOptions
   ): AsyncIterable<TChunk> {
     if (!this.worker) {
       throw new Error('Worker not initialized');
@@ -189,49 +721,201 @@ export class WasmWorker {
       throw new Error('Worker not ready');
     }
 
+    const signal = options?.signal;
+    if (signal?.aborted) {
+      throw this.cancelledError(fn, signal);
+    }
+
     const id = generateId();
+    const seq = this.nextSeq++;
     const request: StreamingRequest = {
       queue: [],
-      resolve: () => {},
-      reject: () => {},
       done: false,
+      error: null,
+      notify: null,
+      seq,
     };
     this.streamingRequests.set(id, request);
 
-    this.worker.postMessage({
-      id,
-      type: 'stream_open',
-      fn,
-      payload,
-    });
+    const onAbort = () => {
+      request.queue.length = 0;
+      request.error = this.cancelledError(fn, signal);
+      request.notify?.();
+    };
+    signal?.addEventListener('abort', onAbort, { once: true });
+
+    const timeoutMs = this.timeoutFor(options);
+    const timer =
+      timeoutMs !== null
+        ? setTimeout(() => {
+            if (request.done) {
+              return;
+            }
+            const running = this.isRunning(seq);
+            request.error = this.timeoutError(fn, timeoutMs);
+            request.done = true;
+            request.notify?.();
+
+            if (running) {
+              this.respawn(fn);
+            } else {
+              this.cancel(id, seq);
+            }
+          }, timeoutMs)
+        : undefined;
+
+    this.post(
+      {
+        id,
+        type: 'stream_open',
+        fn,
+        payload,
+        codec: options?.codec,
+        seq,
+      },
+      options?.transfer || []
+    );
 
     try {
-      while (!request.done || request.queue.length > 0) {
+      while (true) {
         if (request.queue.length > 0) {
           yield request.queue.shift() as TChunk;
+        } else if (request.error) {
+          throw request.error;
+        } else if (request.done) {
+          return;
         } else {
-          // Wait for next chunk
-          await new Promise<IteratorResult<unknown>>((resolve, reject) => {
-            request.resolve = resolve;
-            request.reject = reject;
+          // Wait for the next chunk, the end of the stream or an error
+          await new Promise<void>((resolve) => {
+            request.notify = resolve;
           });
+          request.notify = null;
         }
       }
     } finally {
+      signal?.removeEventListener('abort', onAbort);
+      clearTimeout(timer);
       this.streamingRequests.delete(id);
+      // Aborted, or the consumer stopped before the export returned
+      if (!request.done) {
+        this.cancel(id, seq);
+      }
     }
   }
 
+  /**
+   * Listen to worker events
+   *
+   * `log` receives the records the guest logs through `env.ww_log`, e.g. with
+   * the `log` crate. Returns a function that removes the listener.
+   */
+  on<E extends keyof WasmWorkerEvents>(
+    event: E,
+    listener: (value: WasmWorkerEvents[E]) => void
+  ): () => void {
+    const listeners = this.listeners[event] as Set<(value: WasmWorkerEvents[E]) => void>;
+    listeners.add(listener);
+    return () => {
+      listeners.delete(listener);
+    };
+  }
+
+  /**
+   * The in-memory filesystem of the worker, where WASI modules read and
+   * write files
+   *
+   * Files are only kept by this worker: a worker restarted after a timeout
+   * starts without them.
+   */
+  get fs(): WasmWorkerFs {
+    return {
+      write: (path, bytes, transfer) => {
+        const { bytes: posted, transfer: moved } = postableBytes(bytes, transfer);
+        const message = { type: 'fs_write' as const, path, bytes: posted };
+        return this.request<FsWriteMsg>(message, moved).then(() => undefined);
+      },
+      read: (path) => this.request<FsReadMsg>({ type: 'fs_read', path }) as Promise<Uint8Array>,
+    };
+  }
+
+  /**
+   * Allocate a region of `byteLength` bytes in the module's shared memory
+   *
+   * The main thread writes into and reads from `region.bytes` directly, and
+   * passes `region.offset` to exports that work on the region in place, so
+   * per-frame data is never copied. Needs a module importing a shared memory,
+   * built with `+atomics`. Calls must not run while the main thread writes to
+   * a region they use.
+   *
+   * Regions are only kept by this worker: a worker restarted after a timeout
+   * has a new memory and starts without them.
+   */
+  async allocShared(byteLength: number, name: string = generateId()): Promise<SharedRegion> {
+    if (!Number.isInteger(byteLength) || byteLength < 0) {
+      throw new TypeError(`Invalid byteLength ${byteLength}, expected a non-negative integer`);
+    }
+    if (!this.memory) {
+      throw new Error('allocShared needs a module with shared memory, built with +atomics');
+    }
+    if (this.regions.has(name)) {
+      throw new Error(`Shared region "${name}" already exists`);
+    }
+
+    const { offset } = (await this.request<SharedAllocMsg>({ type: 'shared_alloc', byteLength })) as {
+      offset: number;
+    };
+    // Views of a shared memory stay valid when it grows
+    const bytes = new Uint8Array(this.memory.buffer, offset, byteLength);
+    const region = { name, offset, byteLength, bytes };
+    this.regions.set(name, region);
+    return region;
+  }
+
+  /**
+   * The shared region allocated as `name`, if any
+   */
+  sharedRegion(name: string): SharedRegion | undefined {
+    return this.regions.get(name);
+  }
+
+  /**
+   * Free a shared region, its view must not be used afterwards
+   */
+  async freeShared(region: string | SharedRegion): Promise<void> {
+    const name = typeof region === 'string' ? region : region.name;
+    const existing = this.regions.get(name);
+    if (!existing) {
+      return;
+    }
+    this.regions.delete(name);
+    await this.request<SharedFreeMsg>({ type: 'shared_free', offset: existing.offset });
+  }
+
+  /**
+   * Describe the module's exports: parameter and return types, codec,
+   * streaming and doc comments
+   *
+   * Read from the manifest the `wasmworker` crate embeds in the module, so
+   * modules built without it describe no exports.
+   */
+  describe(): ExportDescription[] {
+    return this.manifest;
+  }
+
   /**
    * Terminate the worker
    */
   terminate(): void {
+    this.stopThreads();
     if (this.worker) {
       this.worker.terminate();
       this.worker = null;
       this.initialized = false;
+      this.backlog = null;
       this.pendingRequests.clear();
       this.streamingRequests.clear();
     }
+    this.memory = null;
+    this.regions.clear();
   }
 }
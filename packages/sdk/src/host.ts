@@ -0,0 +1,80 @@
+/**
+ * Channel a worker blocks on while the main thread runs a host function
+ *
+ * Layout: an Int32 status, an Int32 byte length, then the reply bytes. The
+ * worker resets the status to `Pending` and waits on it, the main thread
+ * writes the reply and notifies.
+ */
+
+/**
+ * Status of the reply in a host channel
+ */
+export const HostStatus = {
+  Pending: 0,
+  // Reply is the JSON encoded return value
+  Ok: 1,
+  // Reply is the error message
+  Error: 2,
+} as const;
+
+export type HostStatus = (typeof HostStatus)[keyof typeof HostStatus];
+
+// Status and length
+const HEADER_BYTES = 8;
+
+/**
+ * Size of the buffer host function replies are written to
+ */
+export const HOST_CHANNEL_BYTES = 1 << 20;
+
+const textEncoder = new TextEncoder();
+const textDecoder = new TextDecoder();
+
+/**
+ * Create a channel, only possible on cross-origin isolated pages
+ */
+export function createHostChannel(bytes = HOST_CHANNEL_BYTES): SharedArrayBuffer {
+  return new SharedArrayBuffer(HEADER_BYTES + bytes);
+}
+
+/**
+ * Write a reply and wake the worker waiting on `channel`
+ *
+ * Replies that do not fit are turned into an error.
+ */
+export function writeHostReply(channel: SharedArrayBuffer, status: HostStatus, reply: string): void {
+  let bytes = textEncoder.encode(reply);
+  const capacity = channel.byteLength - HEADER_BYTES;
+  if (bytes.length > capacity) {
+    status = HostStatus.Error;
+    bytes = textEncoder
+      .encode(`Host function reply of ${bytes.length} bytes does not fit in ${capacity} bytes`)
+      .subarray(0, capacity);
+  }
+
+  const header = new Int32Array(channel, 0, 2);
+  new Uint8Array(channel, HEADER_BYTES, bytes.length).set(bytes);
+  header[1] = bytes.length;
+  Atomics.store(header, 0, status);
+  Atomics.notify(header, 0);
+}
+
+/**
+ * Block until the main thread has replied on `channel`
+ *
+ * `send` asks the main thread to run the host function, after the status
+ * has been reset so a quick reply cannot be missed.
+ */
+export function waitHostReply(
+  channel: SharedArrayBuffer,
+  send: () => void
+): { status: HostStatus; reply: string } {
+  const header = new Int32Array(channel, 0, 2);
+  Atomics.store(header, 0, HostStatus.Pending);
+  send();
+  Atomics.wait(header, 0, HostStatus.Pending);
+
+  // Shared memory cannot be decoded directly
+  const bytes = new Uint8Array(channel, HEADER_BYTES, header[1]).slice();
+  return { status: Atomics.load(header, 0) as HostStatus, reply: textDecoder.decode(bytes) };
+}
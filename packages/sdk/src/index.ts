@@ -1,7 +1,23 @@
 export { WasmWorker } from './bridge.js';
+export { WasmWorkerPool } from './pool.js';
 export type {
   LoadOptions,
+  PoolOptions,
   CallOptions,
+  ApiTarget,
+  Bindings,
+  ExportDescription,
+  ExportParam,
+  LogLevel,
+  LogRecord,
+  HostFunction,
+  CacheMode,
+  WasiOptions,
+  WasmWorkerFs,
+  SharedRegion,
+  BindgenOptions,
+  WasmWorkerEvents,
+  Codec,
   ErrorCode,
   WasmWorkerError,
 } from './types.js';
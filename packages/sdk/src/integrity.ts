@@ -0,0 +1,65 @@
+/**
+ * Subresource Integrity checks of module bytes, before they are compiled
+ */
+
+// Weakest first, only digests of the strongest algorithm given are compared
+const ALGORITHMS = {
+  sha256: 'SHA-256',
+  sha384: 'SHA-384',
+  sha512: 'SHA-512',
+} as const;
+
+type Algorithm = keyof typeof ALGORITHMS;
+
+/**
+ * Error raised when module bytes don't match `LoadOptions.integrity`,
+ * reported as INTEGRITY_MISMATCH
+ */
+export class IntegrityError extends Error {
+  constructor(message: string, public details: { expected: string[]; actual: string }) {
+    super(message);
+    this.name = 'IntegrityError';
+  }
+}
+
+/**
+ * The digests of an SRI string to compare against, e.g. `sha384-<base64>`
+ *
+ * Several space separated digests are accepted, as in the `integrity`
+ * attribute. Throws when none uses a supported algorithm.
+ */
+export function parseIntegrity(integrity: string): { algorithm: Algorithm; digests: string[] } {
+  const names = Object.keys(ALGORITHMS) as Algorithm[];
+  const digests = integrity
+    .trim()
+    .split(/\s+/)
+    // Options after `?` are reserved and ignored
+    .map((token) => token.split('?')[0])
+    .filter((token) => names.some((name) => token.startsWith(`${name}-`) && token.length > name.length + 1));
+
+  if (digests.length === 0) {
+    throw new TypeError(`Invalid integrity "${integrity}", expected sha256-, sha384- or sha512-<base64>`);
+  }
+
+  const algorithm = names
+    .filter((name) => digests.some((digest) => digest.startsWith(`${name}-`)))
+    .pop()!;
+  return { algorithm, digests: digests.filter((digest) => digest.startsWith(`${algorithm}-`)) };
+}
+
+/**
+ * Check `bytes` against an SRI string, throwing an `IntegrityError` when
+ * they don't match
+ */
+export async function checkIntegrity(bytes: ArrayBuffer, integrity: string): Promise<void> {
+  const { algorithm, digests } = parseIntegrity(integrity);
+  const hash = new Uint8Array(await crypto.subtle.digest(ALGORITHMS[algorithm], bytes));
+  const actual = `${algorithm}-${btoa(String.fromCharCode(...hash))}`;
+
+  if (!digests.includes(actual)) {
+    throw new IntegrityError(`Module integrity check failed, got ${actual}`, {
+      expected: digests,
+      actual,
+    });
+  }
+}
@@ -0,0 +1,170 @@
+import { WasmWorker } from './bridge.js';
+import type { CallOptions, ExportDescription, PoolOptions, WasmWorkerEvents } from './types.js';
+
+/**
+ * A worker of the pool, with the number of calls and streams it is running
+ */
+interface PoolEntry {
+  worker: WasmWorker;
+  busy: number;
+}
+
+/**
+ * Pick a default pool size from the number of logical cores
+ */
+function defaultSize(): number {
+  const cores = typeof navigator !== 'undefined' ? navigator.hardwareConcurrency : undefined;
+  return Math.max(1, cores || 4);
+}
+
+/**
+ * Runs a WASM module in several WebWorkers to execute calls in parallel
+ *
+ * The module is compiled once and instantiated in every worker, so each
+ * worker has its own memory. Calls go to the worker with the fewest calls
+ * in flight.
+ */
+export class WasmWorkerPool<TApi = unknown> {
+  /**
+   * Typed methods for the module's exports, built by `PoolOptions.bindings`
+   */
+  api!: TApi;
+  private entries: PoolEntry[] = [];
+
+  private constructor() {}
+
+  /**
+   * Load a WASM module in a pool of `options.size` WebWorkers
+   */
+  static async load<TApi = unknown>(options: PoolOptions<TApi>): Promise<WasmWorkerPool<TApi>> {
+    const size = options.size ?? defaultSize();
+    if (!Number.isInteger(size) || size < 1) {
+      throw new RangeError(`Pool size must be a positive integer, got ${size}`);
+    }
+
+    const pool = new WasmWorkerPool<TApi>();
+    // Calls go through the pool, so the workers need no API of their own.
+    // Every worker gets the files, so none can take them away from the others
+    const workerOptions = { ...options, bindings: undefined, transferFiles: undefined };
+
+    // The first worker fetches and compiles the module, the others reuse it
+    const first = await WasmWorker.load(workerOptions);
+    pool.entries.push({ worker: first, busy: 0 });
+
+    const module = first.compiledModule;
+    // Every worker has its own filesystem, with a copy of the files given
+    const rest = await Promise.allSettled(
+      Array.from({ length: size - 1 }, async () =>
+        module ? WasmWorker.fromModule(workerOptions, module) : WasmWorker.load(workerOptions)
+      )
+    );
+    for (const result of rest) {
+      if (result.status === 'fulfilled') {
+        pool.entries.push({ worker: result.value, busy: 0 });
+      }
+    }
+
+    const failed = rest.find(
+      (result): result is PromiseRejectedResult => result.status === 'rejected'
+    );
+    if (failed) {
+      // Workers that did load are part of the pool by now and go with it
+      pool.terminate();
+      throw failed.reason;
+    }
+
+    pool.api = options.bindings?.(pool) as TApi;
+    return pool;
+  }
+
+  /**
+   * Number of workers in the pool
+   */
+  get size(): number {
+    return this.entries.length;
+  }
+
+  /**
+   * Take the least busy worker, the earliest one on ties
+   */
+  private acquire(): PoolEntry {
+    if (this.entries.length === 0) {
+      throw new Error('Worker pool terminated');
+    }
+
+    let best = this.entries[0];
+    for (const entry of this.entries) {
+      if (entry.busy < best.busy) {
+        best = entry;
+      }
+    }
+    best.busy++;
+    return best;
+  }
+
+  /**
+   * Call a WASM function on the least busy worker
+   */
+  async call<TIn = unknown, TOut = unknown>(
+    fn: string,
+    payload?: TIn,
+    options?: CallOptions
+  ): Promise<TOut> {
+    const entry = this.acquire();
+    try {
+      return await entry.worker.call<TIn, TOut>(fn, payload, options);
+    } finally {
+      entry.busy--;
+    }
+  }
+
+  /**
+   * Stream data from a WASM function on the least busy worker
+   *
+   * The worker counts as busy until the stream finishes.
+   */
+  async *stream<TIn = unknown, TChunk = unknown>(
+    fn: string,
+    payload?: TIn,
+    options?: CallOptions
+  ): AsyncIterable<TChunk> {
+    const entry = this.acquire();
+    try {
+      yield* entry.worker.stream<TIn, TChunk>(fn, payload, options);
+    } finally {
+      entry.busy--;
+    }
+  }
+
+  /**
+   * Listen to the events of every worker, see `WasmWorker.on`
+   */
+  on<E extends keyof WasmWorkerEvents>(
+    event: E,
+    listener: (value: WasmWorkerEvents[E]) => void
+  ): () => void {
+    const removers = this.entries.map(({ worker }) => worker.on(event, listener));
+    return () => {
+      for (const remove of removers) {
+        remove();
+      }
+    };
+  }
+
+  /**
+   * Describe the module's exports, see `WasmWorker.describe`
+   */
+  describe(): ExportDescription[] {
+    return this.entries[0]?.worker.describe() ?? [];
+  }
+
+  /**
+   * Terminate every worker of the pool
+   */
+  terminate(): void {
+    for (const { worker } of this.entries) {
+      worker.terminate();
+    }
+    this.entries = [];
+  }
+}
@@ -0,0 +1,115 @@
+import type { LoadOptions } from './types.js';
+import { parseIntegrity } from './integrity.js';
+
+/**
+ * Ways of passing the module in `LoadOptions`, exactly one must be set
+ */
+const SOURCES = ['moduleUrl', 'moduleBytes', 'module', 'moduleResponse'] as const;
+
+/**
+ * Check that exactly one module source is set, can be verified when
+ * `integrity` is, and suits the loader mode
+ */
+export function checkSource(options: LoadOptions): void {
+  const given = SOURCES.filter((source) => options[source] !== undefined);
+  if (given.length !== 1) {
+    throw new TypeError(
+      `LoadOptions needs exactly one of ${SOURCES.join(', ')}, got ${given.length ? given.join(', ') : 'none'}`
+    );
+  }
+
+  if (options.integrity !== undefined) {
+    parseIntegrity(options.integrity);
+    if (options.module) {
+      throw new TypeError('integrity cannot be checked for an already compiled module');
+    }
+  }
+
+  if (options.bindgen && options.init) {
+    throw new TypeError('init cannot be used with bindgen, the glue provides the imports');
+  }
+
+  if (options.threads !== undefined) {
+    if (!Number.isInteger(options.threads) || options.threads < 0) {
+      throw new RangeError(`threads must be a non-negative integer, got ${options.threads}`);
+    }
+    // Threads would not see them, each helper has a filesystem of its own
+    if (options.threads > 0 && options.files) {
+      throw new TypeError('files cannot be used with threads, whose helpers have filesystems of their own');
+    }
+  }
+}
+
+/**
+ * Resolve `url` against the page, as fetching it there would
+ */
+export function absoluteUrl(url: string): string {
+  return typeof location === 'undefined' ? url : new URL(url, location.href).href;
+}
+
+/**
+ * Compile a fetched module, streaming when it is served as `application/wasm`
+ *
+ * `compileStreaming` rejects other content types, so those are read whole.
+ */
+export async function compileResponse(response: Response): Promise<WebAssembly.Module> {
+  const contentType = response.headers.get('Content-Type') ?? '';
+  if (typeof WebAssembly.compileStreaming === 'function' && contentType.startsWith('application/wasm')) {
+    return WebAssembly.compileStreaming(response);
+  }
+  return WebAssembly.compile(await response.arrayBuffer());
+}
+
+/**
+ * Prepare module bytes or a file for the worker
+ *
+ * The bytes are copied and the copy transferred, so the caller's buffer stays
+ * usable and can be given to several workers. A `SharedArrayBuffer` is shared.
+ * An `ArrayBuffer` listed in `transfer`, or a view spanning all of one, is
+ * moved without a copy instead, which detaches it. A view of part of a buffer
+ * is still copied.
+ */
+export function postableBytes(
+  bytes: BufferSource | SharedArrayBuffer,
+  transfer: Transferable[] = []
+): {
+  bytes: ArrayBuffer | SharedArrayBuffer;
+  transfer: Transferable[];
+} {
+  if (typeof SharedArrayBuffer === 'function' && bytes instanceof SharedArrayBuffer) {
+    return { bytes, transfer: [] };
+  }
+  const view = ArrayBuffer.isView(bytes)
+    ? new Uint8Array(bytes.buffer, bytes.byteOffset, bytes.byteLength)
+    : new Uint8Array(bytes as ArrayBuffer);
+  if (
+    view.buffer instanceof ArrayBuffer &&
+    view.byteLength === view.buffer.byteLength &&
+    transfer.includes(view.buffer)
+  ) {
+    return { bytes: view.buffer, transfer: [view.buffer] };
+  }
+  const copy = view.slice().buffer;
+  return { bytes: copy, transfer: [copy] };
+}
+
+/**
+ * Prepare `LoadOptions.files` for the worker, like module bytes
+ */
+export function postableFiles(
+  files: Record<string, BufferSource | SharedArrayBuffer>,
+  transfer: Transferable[] = []
+): {
+  files: Record<string, ArrayBuffer | SharedArrayBuffer>;
+  transfer: Transferable[];
+} {
+  const posted: Record<string, ArrayBuffer | SharedArrayBuffer> = {};
+  const moved = new Set<Transferable>();
+  for (const [path, contents] of Object.entries(files)) {
+    const { bytes, transfer: buffers } = postableBytes(contents, transfer);
+    posted[path] = bytes;
+    // A buffer moved for several paths is listed once
+    buffers.forEach((buffer) => moved.add(buffer));
+  }
+  return { files: posted, transfer: [...moved] };
+}
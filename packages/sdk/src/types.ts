@@ -3,7 +3,21 @@
  */
 export interface MsgBase {
   id: string;
-  type: 'init' | 'call' | 'stream_open' | 'stream_chunk' | 'stream_close' | 'result' | 'error';
+  type:
+    | 'init'
+    | 'call'
+    | 'stream_open'
+    | 'stream_chunk'
+    | 'stream_close'
+    | 'cancel'
+    | 'fs_write'
+    | 'fs_read'
+    | 'thread_init'
+    | 'threads'
+    | 'shared_alloc'
+    | 'shared_free'
+    | 'result'
+    | 'error';
 }
 
 /**
@@ -11,8 +25,27 @@ export interface MsgBase {
  */
 export interface InitMsg extends MsgBase {
   type: 'init';
-  moduleUrl: string;
+  moduleUrl?: string;
+  // Module bytes, compiled instead of fetching `moduleUrl`
+  moduleBytes?: ArrayBuffer | SharedArrayBuffer;
   init?: Record<string, unknown>;
+  cache?: CacheMode;
+  integrity?: string;
+  wasi?: WasiOptions;
+  // Initial files of the worker's filesystem, by path
+  files?: Record<string, ArrayBuffer | SharedArrayBuffer>;
+  // Instantiate the module through its wasm-bindgen glue, at an absolute URL
+  bindgen?: BindgenOptions;
+  // Number of helper workers for the threads of a threaded module
+  threads?: number;
+  // Shared memory `module` imports, read from its bytes by an earlier worker
+  memoryType?: SharedMemoryType;
+  // Names of the functions in `LoadOptions.init`, which run on the main thread
+  hostFunctions?: string[];
+  // Compiled module, instantiated as is
+  module?: WebAssembly.Module;
+  // Cancellation flags written by the bridge, when shared memory is available
+  cancelBuffer?: SharedArrayBuffer;
 }
 
 /**
@@ -22,6 +55,9 @@ export interface CallMsg extends MsgBase {
   type: 'call';
   fn: string;
   payload?: unknown;
+  codec?: Codec;
+  // Sequence number identifying the call in cancellation flags
+  seq?: number;
 }
 
 /**
@@ -51,6 +87,8 @@ export interface StreamOpenMsg extends MsgBase {
   type: 'stream_open';
   fn: string;
   payload?: unknown;
+  codec?: Codec;
+  seq?: number;
 }
 
 /**
@@ -68,6 +106,94 @@ export interface StreamCloseMsg extends MsgBase {
   type: 'stream_close';
 }
 
+/**
+ * Cancel a call or stream
+ */
+export interface CancelMsg extends MsgBase {
+  type: 'cancel';
+  seq: number;
+}
+
+/**
+ * Replace a file of the worker's filesystem
+ */
+export interface FsWriteMsg extends MsgBase {
+  type: 'fs_write';
+  path: string;
+  bytes: ArrayBuffer | SharedArrayBuffer;
+}
+
+/**
+ * Read a file of the worker's filesystem, answered with its bytes
+ */
+export interface FsReadMsg extends MsgBase {
+  type: 'fs_read';
+  path: string;
+}
+
+/**
+ * Instantiate a threaded module in a helper worker, whose threads are
+ * started through `port`
+ */
+export interface ThreadInitMsg extends MsgBase {
+  type: 'thread_init';
+  module: WebAssembly.Module;
+  memory: WebAssembly.Memory;
+  memoryType: SharedMemoryType;
+  init?: Record<string, unknown>;
+  hostFunctions?: string[];
+  wasi?: WasiOptions;
+  threads?: number;
+  // One flag per helper, set while it runs a thread
+  busy: SharedArrayBuffer;
+  index: number;
+  port: MessagePort;
+}
+
+/**
+ * Hand the worker the ports of its thread helpers
+ */
+export interface ThreadsMsg extends MsgBase {
+  type: 'threads';
+  ports: MessagePort[];
+  busy: SharedArrayBuffer;
+}
+
+/**
+ * Allocate a region of the module's shared memory, answered with its offset
+ */
+export interface SharedAllocMsg extends MsgBase {
+  type: 'shared_alloc';
+  byteLength: number;
+}
+
+/**
+ * Free the shared region at `offset`
+ */
+export interface SharedFreeMsg extends MsgBase {
+  type: 'shared_free';
+  offset: number;
+}
+
+/**
+ * Record logged by the guest through `env.ww_log`
+ */
+export interface LogMsg {
+  type: 'log';
+  level: LogLevel;
+  message: string;
+}
+
+/**
+ * Ask the main thread to run a host function and reply on `channel`
+ */
+export interface HostCallMsg {
+  type: 'host_call';
+  name: string;
+  args: unknown[];
+  channel: SharedArrayBuffer;
+}
+
 /**
  * Worker ready signal
  */
@@ -78,12 +204,29 @@ export interface ReadyMsg {
 /**
  * Union of all message types sent TO the worker
  */
-export type WorkerRequest = InitMsg | CallMsg | StreamOpenMsg;
+export type WorkerRequest =
+  | InitMsg
+  | CallMsg
+  | StreamOpenMsg
+  | CancelMsg
+  | FsWriteMsg
+  | FsReadMsg
+  | ThreadInitMsg
+  | ThreadsMsg
+  | SharedAllocMsg
+  | SharedFreeMsg;
 
 /**
  * Union of all message types received FROM the worker
  */
-export type WorkerResponse = ResultMsg | ErrorMsg | StreamChunkMsg | StreamCloseMsg | ReadyMsg;
+export type WorkerResponse =
+  | ResultMsg
+  | ErrorMsg
+  | StreamChunkMsg
+  | StreamCloseMsg
+  | LogMsg
+  | HostCallMsg
+  | ReadyMsg;
 
 /**
  * Error codes for standardized error handling
@@ -95,14 +238,179 @@ export type ErrorCode =
   | 'INVALID_PAYLOAD'
   | 'WASM_TRAP'
   | 'NOT_INITIALIZED'
+  | 'CANCELLED'
+  | 'TIMEOUT'
+  | 'APP_ERROR'
+  | 'INTEGRITY_MISMATCH'
+  | 'FS_ERROR'
   | 'UNKNOWN_ERROR';
 
+/**
+ * Level of a guest log record, as in Rust's `log::Level`
+ */
+export type LogLevel = 'error' | 'warn' | 'info' | 'debug' | 'trace';
+
+/**
+ * A record logged by the guest, delivered to `worker.on('log', ...)`
+ */
+export interface LogRecord {
+  level: LogLevel;
+  message: string;
+}
+
+/**
+ * Events emitted by a worker, with the value passed to their listeners
+ */
+export interface WasmWorkerEvents {
+  log: LogRecord;
+}
+
+/**
+ * A function the guest calls on the main thread, passed in `LoadOptions.init`
+ *
+ * Its return value, or what its promise resolves to, must be JSON serializable.
+ */
+export type HostFunction = (...args: any[]) => unknown;
+
+/**
+ * What `LoadOptions.cache` keeps of a module between page loads
+ *
+ * `'bytes'` stores the downloaded module, `'module'` also the compiled one
+ * where the browser supports it. Current browsers can't store compiled
+ * modules in IndexedDB, so there `'module'` works like `'bytes'`.
+ */
+export type CacheMode = 'none' | 'bytes' | 'module';
+
+/**
+ * Process arguments and environment variables seen by a WASI module
+ */
+export interface WasiOptions {
+  // Including the program name, as `std::env::args()` returns them
+  args?: string[];
+  env?: Record<string, string>;
+}
+
+/**
+ * Where the JS glue of a wasm-bindgen module is, built with `--target web`
+ *
+ * Calls then go to the glue's exported functions, which convert strings,
+ * structs and `JsValue`s themselves.
+ */
+export interface BindgenOptions {
+  glueUrl: string;
+}
+
+/**
+ * A shared memory imported by a threaded module, its limits in pages
+ */
+export interface SharedMemoryType {
+  module: string;
+  name: string;
+  initial: number;
+  maximum: number;
+}
+
+/**
+ * The in-memory filesystem WASI modules read and write, as `worker.fs`
+ */
+export interface WasmWorkerFs {
+  // Replace the file at `path` with a copy of `bytes`. Listing their
+  // `ArrayBuffer` in `transfer` moves it instead, detaching it, as long as
+  // `bytes` spans the whole buffer
+  write(
+    path: string,
+    bytes: BufferSource | SharedArrayBuffer,
+    transfer?: Transferable[]
+  ): Promise<void>;
+  // A copy of the file at `path`
+  read(path: string): Promise<Uint8Array>;
+}
+
+/**
+ * A region of the module's shared memory, from `worker.allocShared()`
+ *
+ * `bytes` views the region directly: what the main thread writes there is
+ * what exports given `offset` read, without a copy.
+ */
+export interface SharedRegion {
+  name: string;
+  // Byte offset of the region in the module's memory, 16 byte aligned
+  offset: number;
+  byteLength: number;
+  bytes: Uint8Array;
+}
+
+/**
+ * Codec used to serialize a call payload into guest memory
+ *
+ * Must match the `codec` the export was declared with in the
+ * `wasmworker` guest crate.
+ */
+export type Codec = 'json' | 'msgpack';
+
+/**
+ * A parameter of an export, as written in Rust
+ */
+export interface ExportParam {
+  name: string;
+  type: string;
+}
+
+/**
+ * An export described by the module's `wasmworker.manifest` section
+ */
+export interface ExportDescription {
+  name: string;
+  params: ExportParam[];
+  // Rust return type, null for `()`
+  returns: string | null;
+  codec: Codec | null;
+  stream: boolean;
+  // Doc comment of the Rust function
+  docs: string;
+}
+
 /**
  * Options for loading a WASM module
  */
-export interface LoadOptions {
-  moduleUrl: string;
+export interface LoadOptions<TApi = unknown> {
+  // Where the module comes from, exactly one of these must be set
+  moduleUrl?: string;
+  // Copied to the worker, shared when a `SharedArrayBuffer`
+  moduleBytes?: BufferSource | SharedArrayBuffer;
+  module?: WebAssembly.Module;
+  // Compiled on the main thread, its body can only be read once
+  moduleResponse?: Response;
+  // Extra `env` imports, functions run on the main thread as host functions
   init?: Record<string, unknown>;
+  // Default timeout for every call and stream, in milliseconds
+  timeoutMs?: number;
+  // Generated by `wasmworker-bindgen`, exposed as `worker.api`
+  bindings?: Bindings<TApi>;
+  // Also write guest log records to the console
+  logToConsole?: boolean;
+  // Keep fetched modules in IndexedDB, 'none' by default
+  cache?: CacheMode;
+  // SRI digest the module's bytes must match, e.g. 'sha384-<base64>'
+  integrity?: string;
+  // Arguments and environment of modules built for wasm32-wasip1
+  wasi?: WasiOptions;
+  // Files WASI modules find at these paths, copied to the worker.
+  // Threads don't see them, so they can't be used with `threads`
+  files?: Record<string, BufferSource | SharedArrayBuffer>;
+  // Buffers of `files` to move to the worker rather than copy, which
+  // detaches them. Only files spanning a whole buffer can be moved, and
+  // pools copy them regardless, as every worker needs its own
+  transferFiles?: Transferable[];
+  // Run a module built with wasm-bindgen through its JS glue
+  bindgen?: BindgenOptions;
+  // Threads of a wasm32-wasip1-threads module, or of a wasm-bindgen-rayon pool
+  threads?: number;
+}
+
+export interface PoolOptions<TApi = unknown> extends LoadOptions<TApi> {
+  // Number of workers, defaults to the number of logical cores
+  size?: number;
 }
 
 /**
@@ -110,14 +418,40 @@ export interface LoadOptions {
  */
 export interface CallOptions {
   transfer?: Transferable[];
+  codec?: Codec;
+  signal?: AbortSignal;
+  // Overrides `LoadOptions.timeoutMs`, 0 disables the timeout
+  timeoutMs?: number;
 }
 
+/**
+ * The calls generated bindings are built on, provided by `WasmWorker` and
+ * `WasmWorkerPool`
+ */
+export interface ApiTarget {
+  call<TIn = unknown, TOut = unknown>(fn: string, payload?: TIn, options?: CallOptions): Promise<TOut>;
+  stream<TIn = unknown, TChunk = unknown>(
+    fn: string,
+    payload?: TIn,
+    options?: CallOptions
+  ): AsyncIterable<TChunk>;
+}
+
+/**
+ * Build a typed API with one method per export
+ */
+export type Bindings<TApi> = (target: ApiTarget) => TApi;
+
 /**
  * Pending request state
  */
 export interface PendingRequest {
   resolve: (value: unknown) => void;
   reject: (error: Error) => void;
+  // Sequence number of a call, absent for init requests
+  seq?: number;
+  // Sent again if the worker is respawned, absent when the payload was transferred
+  replay?: CallMsg;
 }
 
 /**
@@ -125,9 +459,11 @@ export interface PendingRequest {
  */
 export interface StreamingRequest {
   queue: unknown[];
-  resolve: (value: IteratorResult<unknown>) => void;
-  reject: (error: Error) => void;
   done: boolean;
+  error: Error | null;
+  // Wakes the consumer while it waits for the next chunk
+  notify: (() => void) | null;
+  seq: number;
 }
 
 /**
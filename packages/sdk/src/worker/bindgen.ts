@@ -0,0 +1,89 @@
+/**
+ * Modules built with wasm-bindgen, run through the JS glue it generates
+ *
+ * The glue must be built with `--target web`: its default export
+ * instantiates the module, with the imports only it knows about, and its
+ * named exports wrap the module's functions.
+ */
+
+/**
+ * Namespace of the imported glue
+ */
+export type BindgenGlue = Record<string, unknown>;
+
+// Exports of the glue that set the module up rather than wrap its functions,
+// including those wasm-bindgen-rayon adds
+const GLUE_SETUP = new Set([
+  'default',
+  'initSync',
+  'initThreadPool',
+  'wbg_rayon_start_worker',
+  'wbg_rayon_PoolBuilder',
+]);
+
+/**
+ * Instantiate the already compiled module through the glue's default export
+ *
+ * Glue from wasm-bindgen 0.2.93 on takes `{ module_or_path }`, earlier glue
+ * the module itself. Only the newer one names the option, which survives
+ * minification as a property key.
+ */
+export async function initGlue(glue: BindgenGlue, module: WebAssembly.Module): Promise<BindgenGlue> {
+  const init = glue.default;
+  if (typeof init !== 'function') {
+    throw new Error('wasm-bindgen glue has no default export, build it with `--target web`');
+  }
+  await init(String(init).includes('module_or_path') ? { module_or_path: module } : module);
+  return glue;
+}
+
+/**
+ * Import the glue at `url` and instantiate the module with it
+ */
+export async function loadGlue(url: string, module: WebAssembly.Module): Promise<BindgenGlue> {
+  const glue = (await import(/* @vite-ignore */ url)) as BindgenGlue;
+  return initGlue(glue, module);
+}
+
+/**
+ * Start the thread pool of a module built with `+atomics` and
+ * wasm-bindgen-rayon, whose glue starts a worker per thread itself
+ */
+export async function initGlueThreads(glue: BindgenGlue, threads: number): Promise<void> {
+  const initThreadPool = glue.initThreadPool;
+  if (typeof initThreadPool !== 'function') {
+    throw new Error('wasm-bindgen glue has no initThreadPool export, threads need wasm-bindgen-rayon');
+  }
+  await initThreadPool(threads);
+}
+
+/**
+ * Names of the functions the glue exports
+ */
+export function glueFunctions(glue: BindgenGlue): string[] {
+  return Object.keys(glue).filter((name) => typeof glue[name] === 'function' && !GLUE_SETUP.has(name));
+}
+
+/**
+ * Make a value returned by the glue postable to the main thread
+ *
+ * Exported structs are wrapper classes holding a pointer into the module,
+ * they are sent as the plain object their `toJSON` returns and freed. Structs
+ * get one with `#[wasm_bindgen(inspectable)]`.
+ */
+export function toPostable(value: unknown): unknown {
+  if (Array.isArray(value)) {
+    return value.map(toPostable);
+  }
+  if (value === null || typeof value !== 'object') {
+    return value;
+  }
+
+  const wrapper = value as { toJSON?: () => unknown; free?: () => void };
+  if (typeof wrapper.toJSON !== 'function' || typeof wrapper.free !== 'function') {
+    return value;
+  }
+  const plain = wrapper.toJSON();
+  wrapper.free();
+  return plain;
+}
@@ -0,0 +1,168 @@
+import type { CacheMode } from '../types.js';
+import { checkIntegrity } from '../integrity.js';
+
+/**
+ * A module kept in the cache
+ */
+export interface CachedModule {
+  // Sent back in `If-None-Match` to skip downloading unchanged modules
+  etag: string | null;
+  // Hex SHA-256 of `bytes`
+  hash: string;
+  bytes: ArrayBuffer;
+  // Only where the browser can store compiled modules
+  module?: WebAssembly.Module;
+}
+
+/**
+ * Where cached modules are kept, keyed by URL, integrity or content hash
+ */
+export interface ModuleStore {
+  get(key: string): Promise<CachedModule | undefined>;
+  put(key: string, record: CachedModule): Promise<void>;
+}
+
+const DB_NAME = 'wasmworker';
+const STORE_NAME = 'modules';
+
+/**
+ * Wait for an IndexedDB request
+ */
+function settle<T>(request: IDBRequest<T>): Promise<T> {
+  return new Promise((resolve, reject) => {
+    request.onsuccess = () => resolve(request.result);
+    request.onerror = () => reject(request.error);
+  });
+}
+
+/**
+ * Open the IndexedDB store, or null where IndexedDB is unavailable
+ */
+export async function openModuleStore(): Promise<ModuleStore | null> {
+  if (typeof indexedDB === 'undefined') {
+    return null;
+  }
+
+  try {
+    const open = indexedDB.open(DB_NAME, 1);
+    open.onupgradeneeded = () => open.result.createObjectStore(STORE_NAME);
+    const db = await settle(open);
+
+    const store = (mode: IDBTransactionMode) =>
+      db.transaction(STORE_NAME, mode).objectStore(STORE_NAME);
+    return {
+      get: (key) => settle(store('readonly').get(key)),
+      put: async (key, record) => {
+        await settle(store('readwrite').put(record, key));
+      },
+    };
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * Hex encoded SHA-256 of `bytes`
+ */
+export async function sha256(bytes: ArrayBuffer): Promise<string> {
+  const digest = new Uint8Array(await crypto.subtle.digest('SHA-256', bytes));
+  return Array.from(digest, (byte) => byte.toString(16).padStart(2, '0')).join('');
+}
+
+/**
+ * Instantiable module of a cache record
+ */
+export function recordModule(record: CachedModule): Promise<WebAssembly.Module> {
+  return record.module ? Promise.resolve(record.module) : WebAssembly.compile(record.bytes);
+}
+
+/**
+ * Compile `bytes`, reusing the cached module when the content is unchanged
+ *
+ * Records are kept under `key`, or the content hash when there is none.
+ * Failing to store a record never fails the load.
+ */
+export async function compileCached(
+  store: ModuleStore,
+  mode: CacheMode,
+  bytes: ArrayBuffer,
+  key?: string,
+  etag: string | null = null
+): Promise<WebAssembly.Module> {
+  const hash = await sha256(bytes);
+  key ??= `sha256:${hash}`;
+
+  const cached = await store.get(key).catch(() => undefined);
+  if (cached && cached.hash === hash) {
+    return recordModule(cached);
+  }
+
+  const module = await WebAssembly.compile(bytes);
+  const record: CachedModule = { etag, hash, bytes };
+  try {
+    await store.put(key, mode === 'module' ? { ...record, module } : record);
+  } catch {
+    // Compiled modules can't be stored by every browser, keep the bytes
+    if (mode === 'module') {
+      await store.put(key, record).catch(() => {});
+    }
+  }
+  return module;
+}
+
+/**
+ * A module loaded through the cache, with its bytes
+ */
+export interface CachedLoad {
+  module: WebAssembly.Module;
+  bytes: ArrayBuffer;
+}
+
+/**
+ * Load the module at `url` through the cache, or return the response when
+ * the server answered with an error
+ *
+ * With `integrity` the content is known in advance, so a record stored under
+ * it is used without a request. Otherwise the record stored under the URL is
+ * revalidated with its ETag, and without one the module is downloaded again.
+ * Bytes are checked against `integrity` before they are compiled.
+ */
+export async function fetchCached(
+  store: ModuleStore,
+  mode: CacheMode,
+  url: string,
+  integrity?: string
+): Promise<CachedLoad | Response> {
+  if (integrity !== undefined) {
+    const key = `sri:${integrity.trim()}`;
+    const cached = await store.get(key).catch(() => undefined);
+    // A record that no longer matches is downloaded again
+    if (cached && (await checkIntegrity(cached.bytes, integrity).then(() => true, () => false))) {
+      return { module: await recordModule(cached), bytes: cached.bytes };
+    }
+
+    const response = await fetch(url);
+    if (!response.ok) {
+      return response;
+    }
+    const bytes = await response.arrayBuffer();
+    await checkIntegrity(bytes, integrity);
+    return { module: await compileCached(store, mode, bytes, key), bytes };
+  }
+
+  const cached = await store.get(url).catch(() => undefined);
+  // Unchanged modules are answered with an empty 304
+  const response = await fetch(
+    url,
+    cached?.etag ? { headers: { 'If-None-Match': cached.etag } } : undefined
+  );
+  if (cached && response.status === 304) {
+    return { module: await recordModule(cached), bytes: cached.bytes };
+  }
+  if (!response.ok) {
+    return response;
+  }
+  const bytes = await response.arrayBuffer();
+  const etag = response.headers.get('ETag');
+  return { module: await compileCached(store, mode, bytes, url, etag), bytes };
+}
@@ -0,0 +1,55 @@
+import type { Codec } from '../types.js';
+import { ResultKind } from './memory.js';
+import * as msgpack from './msgpack.js';
+
+const textEncoder = new TextEncoder();
+const textDecoder = new TextDecoder();
+
+/**
+ * Encode a call payload with the codec selected for the call
+ */
+export function encodePayload(codec: Codec, payload: unknown): Uint8Array {
+  switch (codec) {
+    case 'json':
+      // `undefined` has no JSON form, send null so the guest sees a value
+      return textEncoder.encode(JSON.stringify(payload === undefined ? null : payload));
+    case 'msgpack':
+      return msgpack.encode(payload);
+    default:
+      throw new Error(`Unknown codec "${String(codec)}"`);
+  }
+}
+
+/**
+ * Decode bytes from the guest result slot according to their encoding
+ */
+export function decodeValue(encoding: number, bytes: Uint8Array): unknown {
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+
+  switch (encoding) {
+    case ResultKind.Bytes:
+      return bytes;
+    case ResultKind.String:
+      return textDecoder.decode(bytes);
+    case ResultKind.Json:
+      return JSON.parse(textDecoder.decode(bytes));
+    case ResultKind.MsgPack:
+      return msgpack.decode(bytes);
+    case ResultKind.I32:
+      return view.getInt32(0, true);
+    case ResultKind.U32:
+      return view.getUint32(0, true);
+    case ResultKind.I64:
+      return view.getBigInt64(0, true);
+    case ResultKind.U64:
+      return view.getBigUint64(0, true);
+    case ResultKind.F32:
+      return view.getFloat32(0, true);
+    case ResultKind.F64:
+      return view.getFloat64(0, true);
+    case ResultKind.Bool:
+      return view.getUint8(0) !== 0;
+    default:
+      throw new Error(`Unknown result encoding ${encoding}`);
+  }
+}
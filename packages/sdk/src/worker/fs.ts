@@ -0,0 +1,225 @@
+/**
+ * In-memory filesystem the WASI shim serves files from
+ *
+ * Paths are absolute and `/` separated. Directories exist explicitly,
+ * writing a file creates its missing parent directories.
+ */
+
+/**
+ * A regular file, whose bytes grow in place
+ */
+export interface FileNode {
+  // Backing store, only the first `size` bytes belong to the file
+  data: Uint8Array;
+  size: number;
+  // Nanoseconds since the epoch
+  mtime: bigint;
+}
+
+/**
+ * Error raised by filesystem operations, with the WASI errno to report
+ */
+export class FsError extends Error {
+  constructor(public errno: number, message: string) {
+    super(message);
+    this.name = 'FsError';
+  }
+}
+
+// WASI errno values used by the filesystem
+export const Errno = {
+  SUCCESS: 0,
+  BADF: 8,
+  EXIST: 20,
+  INVAL: 28,
+  ISDIR: 31,
+  NOENT: 44,
+  NOSYS: 52,
+  NOTDIR: 54,
+  NOTEMPTY: 55,
+  NOTSUP: 58,
+  SPIPE: 70,
+} as const;
+
+function now(): bigint {
+  return BigInt(Date.now()) * 1_000_000n;
+}
+
+/**
+ * Resolve `path` against the directory `base`, collapsing `.` and `..`
+ */
+export function resolvePath(base: string, path: string): string {
+  const parts: string[] = [];
+  for (const part of `${path.startsWith('/') ? '' : base}/${path}`.split('/')) {
+    if (part === '..') {
+      parts.pop();
+    } else if (part !== '' && part !== '.') {
+      parts.push(part);
+    }
+  }
+  return `/${parts.join('/')}`;
+}
+
+function parentOf(path: string): string {
+  return path.slice(0, path.lastIndexOf('/')) || '/';
+}
+
+export class MemoryFs {
+  private files = new Map<string, FileNode>();
+  private dirs = new Set<string>(['/']);
+
+  /**
+   * Whether `path` is a file, a directory, or missing
+   */
+  kind(path: string): 'file' | 'dir' | null {
+    if (this.files.has(path)) {
+      return 'file';
+    }
+    return this.dirs.has(path) ? 'dir' : null;
+  }
+
+  /**
+   * The file at `path`, created empty with `create`
+   */
+  open(path: string, create = false): FileNode {
+    const file = this.files.get(path);
+    if (file) {
+      return file;
+    }
+    if (this.dirs.has(path)) {
+      throw new FsError(Errno.ISDIR, `${path} is a directory`);
+    }
+    if (!create) {
+      throw new FsError(Errno.NOENT, `${path} does not exist`);
+    }
+    this.requireDir(parentOf(path));
+    const created: FileNode = { data: new Uint8Array(0), size: 0, mtime: now() };
+    this.files.set(path, created);
+    return created;
+  }
+
+  /**
+   * The contents of the file at `path`
+   */
+  readFile(path: string): Uint8Array {
+    const file = this.open(path);
+    return file.data.subarray(0, file.size);
+  }
+
+  /**
+   * Replace the file at `path`, creating its parent directories
+   */
+  writeFile(path: string, bytes: Uint8Array): void {
+    this.mkdirAll(parentOf(path));
+    if (this.dirs.has(path)) {
+      throw new FsError(Errno.ISDIR, `${path} is a directory`);
+    }
+    this.files.set(path, { data: bytes, size: bytes.length, mtime: now() });
+  }
+
+  /**
+   * Write `bytes` into `file` at `offset`, growing it as needed
+   */
+  write(file: FileNode, offset: number, bytes: Uint8Array): void {
+    this.resize(file, Math.max(file.size, offset + bytes.length));
+    file.data.set(bytes, offset);
+    file.mtime = now();
+  }
+
+  /**
+   * Truncate or zero-extend `file` to `size` bytes
+   */
+  resize(file: FileNode, size: number): void {
+    if (size > file.data.length) {
+      // Double the capacity so appends stay cheap
+      const data = new Uint8Array(Math.max(size, file.data.length * 2));
+      data.set(file.data.subarray(0, file.size));
+      file.data = data;
+    } else if (size < file.size) {
+      file.data.fill(0, size, file.size);
+    }
+    file.size = size;
+  }
+
+  mkdir(path: string): void {
+    if (this.kind(path)) {
+      throw new FsError(Errno.EXIST, `${path} already exists`);
+    }
+    this.requireDir(parentOf(path));
+    this.dirs.add(path);
+  }
+
+  mkdirAll(path: string): void {
+    if (this.dirs.has(path)) {
+      return;
+    }
+    this.mkdirAll(parentOf(path));
+    this.mkdir(path);
+  }
+
+  unlink(path: string): void {
+    if (this.dirs.has(path)) {
+      throw new FsError(Errno.ISDIR, `${path} is a directory`);
+    }
+    if (!this.files.delete(path)) {
+      throw new FsError(Errno.NOENT, `${path} does not exist`);
+    }
+  }
+
+  rmdir(path: string): void {
+    this.requireDir(path);
+    if (this.readdir(path).length > 0) {
+      throw new FsError(Errno.NOTEMPTY, `${path} is not empty`);
+    }
+    this.dirs.delete(path);
+  }
+
+  rename(from: string, to: string): void {
+    const kind = this.kind(from);
+    if (!kind) {
+      throw new FsError(Errno.NOENT, `${from} does not exist`);
+    }
+    this.requireDir(parentOf(to));
+
+    if (kind === 'file') {
+      if (this.dirs.has(to)) {
+        throw new FsError(Errno.ISDIR, `${to} is a directory`);
+      }
+      this.files.set(to, this.files.get(from)!);
+      this.files.delete(from);
+      return;
+    }
+
+    if (this.files.has(to)) {
+      throw new FsError(Errno.NOTDIR, `${to} is not a directory`);
+    }
+    if (to.startsWith(`${from}/`)) {
+      throw new FsError(Errno.INVAL, `Cannot move ${from} into itself`);
+    }
+    // Move the directory along with everything below it
+    const moved = (path: string) => (path === from || path.startsWith(`${from}/`) ? to + path.slice(from.length) : path);
+    this.dirs = new Set([...this.dirs].map(moved));
+    this.files = new Map([...this.files].map(([path, file]) => [moved(path), file]));
+  }
+
+  /**
+   * Names of the entries of the directory at `path`, sorted
+   */
+  readdir(path: string): string[] {
+    this.requireDir(path);
+    const prefix = path === '/' ? '/' : `${path}/`;
+    const names = [...this.dirs, ...this.files.keys()]
+      .filter((entry) => entry !== path && entry.startsWith(prefix) && !entry.slice(prefix.length).includes('/'))
+      .map((entry) => entry.slice(prefix.length));
+    return names.sort();
+  }
+
+  private requireDir(path: string): void {
+    if (this.files.has(path)) {
+      throw new FsError(Errno.NOTDIR, `${path} is not a directory`);
+    }
+    if (!this.dirs.has(path)) {
+      throw new FsError(Errno.NOENT, `${path} does not exist`);
+    }
+  }
+}
@@ -0,0 +1,29 @@
+import type { ExportDescription } from '../types.js';
+
+/**
+ * Name of the custom section the `wasmworker` crate describes exports in
+ */
+export const MANIFEST_SECTION = 'wasmworker.manifest';
+
+const textDecoder = new TextDecoder();
+
+/**
+ * Read the export manifest embedded in a compiled module
+ *
+ * Every export contributes one line of JSON, and the linker concatenates
+ * them, possibly across several sections. Modules built without the
+ * `wasmworker` crate have no manifest and describe no exports.
+ */
+export function readManifest(module: WebAssembly.Module): ExportDescription[] {
+  const exports: ExportDescription[] = [];
+
+  for (const section of WebAssembly.Module.customSections(module, MANIFEST_SECTION)) {
+    for (const line of textDecoder.decode(section).split('\n')) {
+      if (line.trim() !== '') {
+        exports.push(JSON.parse(line) as ExportDescription);
+      }
+    }
+  }
+
+  return exports;
+}
@@ -0,0 +1,212 @@
+/**
+ * Allocator exports provided by the `wasmworker` guest crate
+ */
+export interface GuestAllocator {
+  alloc(len: number): number;
+  free(ptr: number, len: number): void;
+}
+
+/**
+ * A buffer living in guest linear memory
+ */
+export interface GuestBuffer {
+  ptr: number;
+  len: number;
+}
+
+/**
+ * Look up the `ww_alloc` / `ww_free` exports of an instance
+ */
+export function getAllocator(exports: WebAssembly.Exports): GuestAllocator | null {
+  const alloc = exports.ww_alloc;
+  const free = exports.ww_free;
+
+  if (typeof alloc !== 'function' || typeof free !== 'function') {
+    return null;
+  }
+
+  return {
+    // Pointers come back as signed i32, normalize to an unsigned offset
+    alloc: (len: number) => (alloc(len) as number) >>> 0,
+    free: (ptr: number, len: number) => {
+      free(ptr, len);
+    },
+  };
+}
+
+/**
+ * Check whether a value is binary data that should be passed as (ptr, len)
+ */
+export function isBinary(value: unknown): value is ArrayBuffer | ArrayBufferView {
+  return value instanceof ArrayBuffer || ArrayBuffer.isView(value);
+}
+
+/**
+ * View binary data as bytes without copying
+ */
+export function toBytes(value: ArrayBuffer | ArrayBufferView): Uint8Array {
+  if (value instanceof ArrayBuffer) {
+    return new Uint8Array(value);
+  }
+  return new Uint8Array(value.buffer, value.byteOffset, value.byteLength);
+}
+
+/**
+ * Copy bytes into guest memory using the guest allocator
+ */
+export function copyIn(
+  memory: WebAssembly.Memory,
+  allocator: GuestAllocator,
+  bytes: Uint8Array
+): GuestBuffer {
+  const len = bytes.byteLength;
+  const ptr = allocator.alloc(len);
+
+  if (len > 0 && ptr === 0) {
+    throw new Error(`ww_alloc failed to allocate ${len} bytes`);
+  }
+
+  // Create the view after allocating, as allocation may grow memory
+  new Uint8Array(memory.buffer, ptr, len).set(bytes);
+  return { ptr, len };
+}
+
+/**
+ * Alignment of shared regions, enough for any typed array view
+ */
+export const REGION_ALIGN = 16;
+
+/**
+ * Allocate a shared region of `byteLength` bytes with the guest allocator
+ *
+ * `ww_alloc` only aligns to a byte, so the allocation is padded and the
+ * region starts at the next aligned offset within it. The returned buffer is
+ * what has to be freed.
+ */
+export function allocRegion(
+  allocator: GuestAllocator,
+  byteLength: number
+): { buffer: GuestBuffer; offset: number } {
+  const len = byteLength + REGION_ALIGN - 1;
+  const ptr = allocator.alloc(len);
+
+  if (ptr === 0) {
+    throw new Error(`ww_alloc failed to allocate ${len} bytes`);
+  }
+
+  return { buffer: { ptr, len }, offset: Math.ceil(ptr / REGION_ALIGN) * REGION_ALIGN };
+}
+
+/**
+ * Encodings stored in the low byte of the result slot kind, mirroring
+ * `wasmworker::KIND_*`
+ *
+ * The scalar kinds only occur in stream chunks and are stored little-endian.
+ */
+export const ResultKind = {
+  None: 0,
+  Bytes: 1,
+  String: 2,
+  Json: 3,
+  MsgPack: 4,
+  I32: 5,
+  U32: 6,
+  I64: 7,
+  U64: 8,
+  F32: 9,
+  F64: 10,
+  Bool: 11,
+} as const;
+
+/**
+ * Statuses stored in the second byte of the result slot kind, mirroring
+ * `wasmworker::STATUS_*`
+ */
+export const ResultStatus = {
+  Ok: 0,
+  InvalidPayload: 0x100,
+  AppError: 0x200,
+} as const;
+
+/**
+ * Split a result slot kind into its encoding and status
+ */
+export function splitKind(kind: number): { encoding: number; status: number } {
+  return { encoding: kind & 0xff, status: kind & 0xff00 };
+}
+
+/**
+ * Bytes handed back through the guest result slot
+ */
+export interface SlotResult {
+  kind: number;
+  bytes: Uint8Array;
+}
+
+/**
+ * Read and clear the guest result slot
+ *
+ * The slot at `slotPtr` holds three little-endian u32 values: kind, ptr and
+ * len. The bytes are copied out and the guest buffer is released with the
+ * allocator. Returns null when the export left the slot empty.
+ */
+export function takeResult(
+  memory: WebAssembly.Memory,
+  allocator: GuestAllocator,
+  slotPtr: number
+): SlotResult | null {
+  const slot = new DataView(memory.buffer, slotPtr, 12);
+  const kind = slot.getUint32(0, true);
+
+  if (kind === ResultKind.None) {
+    return null;
+  }
+
+  const ptr = slot.getUint32(4, true);
+  const len = slot.getUint32(8, true);
+  slot.setUint32(0, ResultKind.None, true);
+
+  const bytes = new Uint8Array(memory.buffer, ptr, len).slice();
+  allocator.free(ptr, len);
+  return { kind, bytes };
+}
+
+/**
+ * A Rust panic recorded by the guest's panic hook
+ */
+export interface PanicRecord {
+  message: string;
+  // `file:line:column` of the panic
+  location: string;
+}
+
+const textDecoder = new TextDecoder();
+
+/**
+ * Decode the UTF-8 string at `ptr` in guest memory
+ */
+export function readString(memory: WebAssembly.Memory, ptr: number, len: number): string {
+  // Shared memory cannot be decoded directly
+  return textDecoder.decode(new Uint8Array(memory.buffer, ptr >>> 0, len >>> 0).slice());
+}
+
+/**
+ * Read and clear the guest panic slot after a trap
+ *
+ * The slot at `slotPtr` holds five little-endian u32 values: panicked,
+ * message ptr and len, location ptr and len. The strings stay owned by the
+ * guest, as its allocator may not be usable after a trap. Returns null when
+ * the trap was not caused by a panic.
+ */
+export function takePanic(memory: WebAssembly.Memory, slotPtr: number): PanicRecord | null {
+  const slot = new DataView(memory.buffer, slotPtr, 20);
+
+  if (slot.getUint32(0, true) === 0) {
+    return null;
+  }
+  slot.setUint32(0, 0, true);
+
+  const read = (offset: number) =>
+    readString(memory, slot.getUint32(offset, true), slot.getUint32(offset + 4, true));
+  return { message: read(4), location: read(12) };
+}
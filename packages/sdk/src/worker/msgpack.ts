@@ -0,0 +1,403 @@
+/**
+ * Minimal MessagePack encoder and decoder
+ *
+ * Covers the subset produced and accepted by `rmp-serde`: nil, booleans,
+ * integers (64-bit values as bigint), floats, strings, binary, arrays and
+ * maps. Extension types are not supported.
+ */
+
+const textEncoder = new TextEncoder();
+const textDecoder = new TextDecoder();
+
+const UINT64_MAX = (1n << 64n) - 1n;
+const INT64_MIN = -(1n << 63n);
+
+class Writer {
+  private bytes = new Uint8Array(256);
+  private view = new DataView(this.bytes.buffer);
+  private pos = 0;
+
+  // Callers must take the offset before touching `view` or `bytes`, as
+  // reserving may replace both
+  private reserve(len: number): number {
+    const needed = this.pos + len;
+    if (needed > this.bytes.length) {
+      const grown = new Uint8Array(Math.max(needed, this.bytes.length * 2));
+      grown.set(this.bytes.subarray(0, this.pos));
+      this.bytes = grown;
+      this.view = new DataView(grown.buffer);
+    }
+    const offset = this.pos;
+    this.pos = needed;
+    return offset;
+  }
+
+  u8(value: number): void {
+    const offset = this.reserve(1);
+    this.view.setUint8(offset, value);
+  }
+
+  u16(value: number): void {
+    const offset = this.reserve(2);
+    this.view.setUint16(offset, value);
+  }
+
+  u32(value: number): void {
+    const offset = this.reserve(4);
+    this.view.setUint32(offset, value);
+  }
+
+  u64(value: bigint): void {
+    const offset = this.reserve(8);
+    this.view.setBigUint64(offset, value);
+  }
+
+  i8(value: number): void {
+    const offset = this.reserve(1);
+    this.view.setInt8(offset, value);
+  }
+
+  i16(value: number): void {
+    const offset = this.reserve(2);
+    this.view.setInt16(offset, value);
+  }
+
+  i32(value: number): void {
+    const offset = this.reserve(4);
+    this.view.setInt32(offset, value);
+  }
+
+  i64(value: bigint): void {
+    const offset = this.reserve(8);
+    this.view.setBigInt64(offset, value);
+  }
+
+  f64(value: number): void {
+    const offset = this.reserve(8);
+    this.view.setFloat64(offset, value);
+  }
+
+  raw(bytes: Uint8Array): void {
+    const offset = this.reserve(bytes.byteLength);
+    this.bytes.set(bytes, offset);
+  }
+
+  finish(): Uint8Array {
+    return this.bytes.slice(0, this.pos);
+  }
+}
+
+/**
+ * Write a length header using the smallest form available: `fix | len`
+ * below `fixLimit`, then the 8, 16 and 32-bit tags (a null tag is skipped)
+ */
+function writeHeader(
+  writer: Writer,
+  len: number,
+  fix: number,
+  fixLimit: number,
+  tags: [number | null, number, number]
+): void {
+  const [tag8, tag16, tag32] = tags;
+  if (len < fixLimit) {
+    writer.u8(fix | len);
+  } else if (tag8 !== null && len <= 0xff) {
+    writer.u8(tag8);
+    writer.u8(len);
+  } else if (len <= 0xffff) {
+    writer.u8(tag16);
+    writer.u16(len);
+  } else {
+    writer.u8(tag32);
+    writer.u32(len);
+  }
+}
+
+function writeInteger(writer: Writer, value: number | bigint): void {
+  if (typeof value === 'bigint') {
+    if (value > UINT64_MAX || value < INT64_MIN) {
+      throw new RangeError(`${value} does not fit in 64 bits`);
+    }
+    if (value >= 0n) {
+      writer.u8(0xcf);
+      writer.u64(value);
+    } else {
+      writer.u8(0xd3);
+      writer.i64(value);
+    }
+    return;
+  }
+
+  if (value >= 0) {
+    if (value < 0x80) {
+      writer.u8(value);
+    } else if (value <= 0xff) {
+      writer.u8(0xcc);
+      writer.u8(value);
+    } else if (value <= 0xffff) {
+      writer.u8(0xcd);
+      writer.u16(value);
+    } else if (value <= 0xffffffff) {
+      writer.u8(0xce);
+      writer.u32(value);
+    } else {
+      writer.u8(0xcf);
+      writer.u64(BigInt(value));
+    }
+  } else if (value >= -32) {
+    writer.i8(value);
+  } else if (value >= -0x80) {
+    writer.u8(0xd0);
+    writer.i8(value);
+  } else if (value >= -0x8000) {
+    writer.u8(0xd1);
+    writer.i16(value);
+  } else if (value >= -0x80000000) {
+    writer.u8(0xd2);
+    writer.i32(value);
+  } else {
+    writer.u8(0xd3);
+    writer.i64(BigInt(value));
+  }
+}
+
+function writeBinary(writer: Writer, bytes: Uint8Array): void {
+  writeHeader(writer, bytes.byteLength, 0, 0, [0xc4, 0xc5, 0xc6]);
+  writer.raw(bytes);
+}
+
+function writeValue(writer: Writer, value: unknown): void {
+  if (value === null || value === undefined) {
+    writer.u8(0xc0);
+  } else if (typeof value === 'boolean') {
+    writer.u8(value ? 0xc3 : 0xc2);
+  } else if (typeof value === 'bigint') {
+    writeInteger(writer, value);
+  } else if (typeof value === 'number') {
+    if (Number.isSafeInteger(value)) {
+      writeInteger(writer, value);
+    } else {
+      writer.u8(0xcb);
+      writer.f64(value);
+    }
+  } else if (typeof value === 'string') {
+    const bytes = textEncoder.encode(value);
+    writeHeader(writer, bytes.byteLength, 0xa0, 32, [0xd9, 0xda, 0xdb]);
+    writer.raw(bytes);
+  } else if (value instanceof ArrayBuffer) {
+    writeBinary(writer, new Uint8Array(value));
+  } else if (value instanceof Uint8Array || value instanceof DataView) {
+    writeBinary(writer, new Uint8Array(value.buffer, value.byteOffset, value.byteLength));
+  } else if (ArrayBuffer.isView(value)) {
+    // Other typed arrays are sequences of numbers, e.g. `Vec<f64>`
+    writeValue(writer, Array.from(value as unknown as ArrayLike<number | bigint>));
+  } else if (Array.isArray(value)) {
+    writeHeader(writer, value.length, 0x90, 16, [null, 0xdc, 0xdd]);
+    for (const item of value) {
+      writeValue(writer, item);
+    }
+  } else if (value instanceof Map) {
+    writeHeader(writer, value.size, 0x80, 16, [null, 0xde, 0xdf]);
+    for (const [key, item] of value) {
+      writeValue(writer, key);
+      writeValue(writer, item);
+    }
+  } else if (typeof value === 'object') {
+    // Like JSON, properties holding `undefined` are left out
+    const entries = Object.entries(value).filter(([, item]) => item !== undefined);
+    writeHeader(writer, entries.length, 0x80, 16, [null, 0xde, 0xdf]);
+    for (const [key, item] of entries) {
+      writeValue(writer, key);
+      writeValue(writer, item);
+    }
+  } else {
+    throw new TypeError(`Cannot encode ${typeof value} as MessagePack`);
+  }
+}
+
+/**
+ * Encode a value as MessagePack
+ *
+ * Safe integers are encoded as integers and other numbers as float64.
+ * `Uint8Array`, `ArrayBuffer` and `DataView` become binary, other typed
+ * arrays become arrays of numbers.
+ */
+export function encode(value: unknown): Uint8Array {
+  const writer = new Writer();
+  writeValue(writer, value);
+  return writer.finish();
+}
+
+class Reader {
+  private readonly view: DataView;
+  pos = 0;
+
+  constructor(private readonly bytes: Uint8Array) {
+    this.view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  }
+
+  private advance(len: number): number {
+    const offset = this.pos;
+    if (offset + len > this.bytes.byteLength) {
+      throw new RangeError('Unexpected end of MessagePack data');
+    }
+    this.pos += len;
+    return offset;
+  }
+
+  get done(): boolean {
+    return this.pos >= this.bytes.byteLength;
+  }
+
+  u8(): number {
+    return this.view.getUint8(this.advance(1));
+  }
+
+  u16(): number {
+    return this.view.getUint16(this.advance(2));
+  }
+
+  u32(): number {
+    return this.view.getUint32(this.advance(4));
+  }
+
+  u64(): number | bigint {
+    return toNumber(this.view.getBigUint64(this.advance(8)));
+  }
+
+  i8(): number {
+    return this.view.getInt8(this.advance(1));
+  }
+
+  i16(): number {
+    return this.view.getInt16(this.advance(2));
+  }
+
+  i32(): number {
+    return this.view.getInt32(this.advance(4));
+  }
+
+  i64(): number | bigint {
+    return toNumber(this.view.getBigInt64(this.advance(8)));
+  }
+
+  f32(): number {
+    return this.view.getFloat32(this.advance(4));
+  }
+
+  f64(): number {
+    return this.view.getFloat64(this.advance(8));
+  }
+
+  raw(len: number): Uint8Array {
+    const offset = this.advance(len);
+    return this.bytes.subarray(offset, offset + len);
+  }
+}
+
+// 64-bit integers stay bigint only when a number would lose precision
+function toNumber(value: bigint): number | bigint {
+  return value >= BigInt(Number.MIN_SAFE_INTEGER) && value <= BigInt(Number.MAX_SAFE_INTEGER)
+    ? Number(value)
+    : value;
+}
+
+function readArray(reader: Reader, len: number): unknown[] {
+  const items = new Array(len);
+  for (let i = 0; i < len; i++) {
+    items[i] = readValue(reader);
+  }
+  return items;
+}
+
+function readMap(reader: Reader, len: number): Record<string, unknown> {
+  const object: Record<string, unknown> = {};
+  for (let i = 0; i < len; i++) {
+    const key = String(readValue(reader));
+    // Define rather than assign, so a "__proto__" key stays a plain property
+    Object.defineProperty(object, key, {
+      value: readValue(reader),
+      enumerable: true,
+      writable: true,
+      configurable: true,
+    });
+  }
+  return object;
+}
+
+function readValue(reader: Reader): unknown {
+  const tag = reader.u8();
+
+  if (tag <= 0x7f) return tag;
+  if (tag <= 0x8f) return readMap(reader, tag & 0x0f);
+  if (tag <= 0x9f) return readArray(reader, tag & 0x0f);
+  if (tag <= 0xbf) return textDecoder.decode(reader.raw(tag & 0x1f));
+  if (tag >= 0xe0) return tag - 0x100;
+
+  switch (tag) {
+    case 0xc0:
+      return null;
+    case 0xc2:
+      return false;
+    case 0xc3:
+      return true;
+    case 0xc4:
+      return reader.raw(reader.u8()).slice();
+    case 0xc5:
+      return reader.raw(reader.u16()).slice();
+    case 0xc6:
+      return reader.raw(reader.u32()).slice();
+    case 0xca:
+      return reader.f32();
+    case 0xcb:
+      return reader.f64();
+    case 0xcc:
+      return reader.u8();
+    case 0xcd:
+      return reader.u16();
+    case 0xce:
+      return reader.u32();
+    case 0xcf:
+      return reader.u64();
+    case 0xd0:
+      return reader.i8();
+    case 0xd1:
+      return reader.i16();
+    case 0xd2:
+      return reader.i32();
+    case 0xd3:
+      return reader.i64();
+    case 0xd9:
+      return textDecoder.decode(reader.raw(reader.u8()));
+    case 0xda:
+      return textDecoder.decode(reader.raw(reader.u16()));
+    case 0xdb:
+      return textDecoder.decode(reader.raw(reader.u32()));
+    case 0xdc:
+      return readArray(reader, reader.u16());
+    case 0xdd:
+      return readArray(reader, reader.u32());
+    case 0xde:
+      return readMap(reader, reader.u16());
+    case 0xdf:
+      return readMap(reader, reader.u32());
+    default:
+      throw new Error(`Unsupported MessagePack type 0x${tag.toString(16)}`);
+  }
+}
+
+/**
+ * Decode a single MessagePack value
+ *
+ * Maps become plain objects with string keys and binary becomes a
+ * `Uint8Array`. 64-bit integers outside the safe integer range are returned
+ * as bigint.
+ */
+export function decode(bytes: Uint8Array): unknown {
+  const reader = new Reader(bytes);
+  const value = readValue(reader);
+  if (!reader.done) {
+    throw new Error('Unexpected trailing bytes after MessagePack value');
+  }
+  return value;
+}
@@ -3,8 +3,52 @@ import type {
   InitMsg,
   CallMsg,
   StreamOpenMsg,
+  CancelMsg,
+  FsReadMsg,
+  FsWriteMsg,
+  ThreadInitMsg,
+  ThreadsMsg,
+  SharedAllocMsg,
+  SharedFreeMsg,
+  SharedMemoryType,
   ErrorCode,
+  ExportDescription,
+  LogLevel,
 } from '../types.js';
+import {
+  getAllocator,
+  isBinary,
+  toBytes,
+  copyIn,
+  allocRegion,
+  readString,
+  takeResult,
+  takePanic,
+  splitKind,
+  ResultKind,
+  ResultStatus,
+  type GuestAllocator,
+  type GuestBuffer,
+  type SlotResult,
+} from './memory.js';
+import { encodePayload, decodeValue } from './codec.js';
+import { readManifest } from './manifest.js';
+import { ArgumentError, checkArgs, checkArity, liftReturn, namedArgs } from './validate.js';
+import { HostStatus, createHostChannel, waitHostReply } from '../host.js';
+import { compileResponse } from '../source.js';
+import { compileCached, fetchCached, openModuleStore } from './cache.js';
+import { IntegrityError, checkIntegrity } from '../integrity.js';
+import { FsError, MemoryFs, resolvePath } from './fs.js';
+import { WASI_MODULE, Wasi, WasiExit } from './wasi.js';
+import { type BindgenGlue, glueFunctions, initGlueThreads, loadGlue, toPostable } from './bindgen.js';
+import {
+  SPAWN_FAILED,
+  THREAD_SPAWN,
+  ThreadPool,
+  createSharedMemory,
+  importsMemory,
+  sharedMemoryImport,
+} from './threads.js';
 
 /**
  * WASM runtime state
@@ -12,24 +56,89 @@ import type {
 interface RuntimeState {
   instance: WebAssembly.Instance | null;
   memory: WebAssembly.Memory | null;
+  allocator: GuestAllocator | null;
+  resultSlot: number | null;
+  // Written by the guest's panic hook before it traps
+  panicSlot: number | null;
+  // Id of the stream whose export is running, receives `ww_emit` chunks
+  streamId: string | null;
+  // Sequence number of the running call, checked by `ww_is_cancelled`
+  currentSeq: number | null;
+  // Written by the bridge when shared, by `cancel` messages otherwise
+  cancelFlags: Int32Array;
+  // Exports described by the module's manifest section
+  manifest: ExportDescription[];
+  // Replied on by the main thread after running a host function
+  hostChannel: SharedArrayBuffer | null;
+  // Reply of the last `ww_host_call`, copied out by `ww_host_result`
+  hostReply: Uint8Array | null;
+  // Files seen by WASI modules
+  fs: MemoryFs;
+  // Only for modules importing `wasi_snapshot_preview1`
+  wasi: Wasi | null;
+  // wasm-bindgen glue the module was instantiated by, calls go through it
+  bindgen: BindgenGlue | null;
+  // Helpers the threads of a threaded module run in, only in the worker
+  threads: ThreadPool | null;
+  // Allocations backing the shared regions, by region offset
+  regions: Map<number, GuestBuffer>;
   initialized: boolean;
 }
 
 const state: RuntimeState = {
   instance: null,
   memory: null,
+  allocator: null,
+  resultSlot: null,
+  panicSlot: null,
+  streamId: null,
+  currentSeq: null,
+  cancelFlags: new Int32Array(64),
+  manifest: [],
+  hostChannel: null,
+  hostReply: null,
+  fs: new MemoryFs(),
+  wasi: null,
+  bindgen: null,
+  threads: null,
+  regions: new Map(),
   initialized: false,
 };
 
+const textEncoder = new TextEncoder();
+
+/**
+ * Error raised while preparing call arguments, reported as INVALID_PAYLOAD
+ */
+class InvalidPayloadError extends Error {
+  constructor(message: string, public details?: unknown) {
+    super(message);
+    this.name = 'InvalidPayloadError';
+  }
+}
+
+/**
+ * Error returned by an export as `Err`, reported as APP_ERROR
+ */
+class AppError extends Error {
+  constructor(message: string, public details: unknown) {
+    super(message);
+    this.name = 'AppError';
+  }
+}
+
 /**
  * Send a result message back to the main thread
  */
-function sendResult(id: string, value?: unknown): void {
-  postMessage({
-    id,
-    type: 'result',
-    value,
-  });
+function sendResult(id: string, value?: unknown, transfer: Transferable[] = []): void {
+  postMessage(
+    {
+      id,
+      type: 'result',
+      value,
+    },
+    { transfer }
+  );
 }
 
 /**
@@ -43,41 +152,340 @@ function sendError(id: string, code: ErrorCode, message: string, details?: unkno
   });
 }
 
+/**
+ * Check whether the call with sequence number `seq` has been cancelled
+ */
+function isCancelled(seq: number | null | undefined): boolean {
+  if (seq === null || seq === undefined) {
+    return false;
+  }
+  return Atomics.load(state.cancelFlags, seq % state.cancelFlags.length) === seq;
+}
+
+/**
+ * Mark a call as cancelled when the bridge could not do it through shared memory
+ */
+function handleCancel(msg: CancelMsg): void {
+  Atomics.store(state.cancelFlags, msg.seq % state.cancelFlags.length, msg.seq);
+}
+
+/**
+ * Forward a chunk emitted by the guest through `ww_emit` to the main thread
+ */
+function emitChunk(kind: number, ptr: number, len: number): void {
+  if (state.streamId === null || !state.memory) {
+    throw new Error('ww_emit called outside of a streaming call');
+  }
+  if (isCancelled(state.currentSeq)) {
+    // Nobody is listening anymore
+    return;
+  }
+
+  const bytes = new Uint8Array(state.memory.buffer, ptr >>> 0, len >>> 0).slice();
+  const transfer = kind === ResultKind.Bytes ? [bytes.buffer] : [];
+  postMessage(
+    {
+      id: state.streamId,
+      type: 'stream_chunk',
+      value: decodeValue(kind, bytes),
+    },
+    { transfer }
+  );
+}
+
+// Indexed by the numeric value of Rust's `log::Level`
+const LOG_LEVELS: LogLevel[] = ['error', 'error', 'warn', 'info', 'debug', 'trace'];
+
+/**
+ * Send a log record to the main thread
+ */
+function sendLog(level: LogLevel, message: string): void {
+  postMessage({ type: 'log', level, message });
+}
+
+/**
+ * Forward a record logged by the guest through `ww_log` to the main thread
+ */
+function forwardLog(level: number, ptr: number, len: number): void {
+  if (!state.memory) {
+    return;
+  }
+
+  sendLog(LOG_LEVELS[level] ?? 'info', readString(state.memory, ptr, len));
+}
+
+/**
+ * Run a host function on the main thread, blocking until it has replied
+ */
+function callHost(name: string, args: unknown[]): { status: HostStatus; reply: string } {
+  const channel = state.hostChannel;
+  if (!channel) {
+    return { status: HostStatus.Error, reply: `No host function named "${name}"` };
+  }
+  return waitHostReply(channel, () => {
+    postMessage({ type: 'host_call', name, args, channel });
+  });
+}
+
+/**
+ * Import a host function directly, for `extern "C"` declarations taking and
+ * returning numbers
+ */
+function hostImport(name: string): (...args: unknown[]) => unknown {
+  return (...args) => {
+    const { status, reply } = callHost(name, args);
+    if (status === HostStatus.Error) {
+      throw new Error(`Host function "${name}" failed: ${reply}`);
+    }
+    const value = JSON.parse(reply);
+    // Wasm has no booleans, and `null` stands for no return value
+    return typeof value === 'boolean' ? Number(value) : value ?? undefined;
+  };
+}
+
+/**
+ * Run a host function for `wasmworker::call_host`, with JSON arguments
+ *
+ * Returns the length of the reply, or `-length - 1` when the host function
+ * failed and the reply is its error message.
+ */
+function guestHostCall(namePtr: number, nameLen: number, argsPtr: number, argsLen: number): number {
+  if (!state.memory) {
+    throw new Error('ww_host_call needs the module to export its memory');
+  }
+  const name = readString(state.memory, namePtr, nameLen);
+  const payload = JSON.parse(readString(state.memory, argsPtr, argsLen));
+  // A tuple is spread over the host function's parameters
+  const args = Array.isArray(payload) ? payload : payload === null ? [] : [payload];
+
+  const { status, reply } = callHost(name, args);
+  const bytes = textEncoder.encode(reply);
+  state.hostReply = bytes;
+  return status === HostStatus.Ok ? bytes.length : -bytes.length - 1;
+}
+
+/**
+ * Copy the reply of the last `ww_host_call` into a guest buffer
+ */
+function guestHostResult(ptr: number): void {
+  if (!state.memory || !state.hostReply) {
+    throw new Error('ww_host_result called without a host function reply');
+  }
+  new Uint8Array(state.memory.buffer, ptr >>> 0, state.hostReply.length).set(state.hostReply);
+  state.hostReply = null;
+}
+
+/**
+ * Instantiate the module with the runtime's imports, in the worker or a
+ * thread helper, and pick up the exports the runtime uses
+ *
+ * `shared` is the memory a threaded module imports.
+ */
+async function instantiate(
+  wasmModule: WebAssembly.Module,
+  options: InitMsg | ThreadInitMsg,
+  shared: { type: SharedMemoryType; memory: WebAssembly.Memory } | null
+): Promise<WebAssembly.Instance> {
+  // Host functions block the worker until the main thread replies
+  const hostFunctions = options.hostFunctions ?? [];
+  if (hostFunctions.length > 0) {
+    if (typeof SharedArrayBuffer !== 'function' || !self.crossOriginIsolated) {
+      throw new Error('host functions need a cross-origin isolated page');
+    }
+    state.hostChannel = createHostChannel();
+  }
+
+  // Modules built for wasm32-wasip1 get the WASI shim, their output goes
+  // to the log listeners
+  const wasiFunctions = WebAssembly.Module.imports(wasmModule)
+    .filter((entry) => entry.module === WASI_MODULE && entry.kind === 'function')
+    .map((entry) => entry.name);
+  if (wasiFunctions.length > 0) {
+    // Rayon can't tell how many threads there are otherwise
+    const threadEnv = options.threads ? { RAYON_NUM_THREADS: String(options.threads) } : {};
+    state.wasi = new Wasi({
+      args: options.wasi?.args ?? [],
+      env: { ...threadEnv, ...options.wasi?.env },
+      fs: state.fs,
+      memory: () => state.memory,
+      output: (fd, line) => sendLog(fd === 1 ? 'info' : 'error', line),
+    });
+  }
+
+  // Create imports object, with the host functions used by the guest crate
+  const imports: WebAssembly.Imports = {
+    env: {
+      ...(options.init as Record<string, WebAssembly.ImportValue> || {}),
+      ...Object.fromEntries(hostFunctions.map((name) => [name, hostImport(name)])),
+      ww_emit: emitChunk,
+      ww_is_cancelled: () => (isCancelled(state.currentSeq) ? 1 : 0),
+      ww_log: forwardLog,
+      ww_host_call: guestHostCall,
+      ww_host_result: guestHostResult,
+    },
+  };
+  if (state.wasi) {
+    imports[WASI_MODULE] = state.wasi.imports(wasiFunctions);
+  }
+  if (shared) {
+    imports[shared.type.module] = { ...imports[shared.type.module], [shared.type.name]: shared.memory };
+    // Helpers have no threads of their own to start
+    imports[THREAD_SPAWN.module] = {
+      [THREAD_SPAWN.name]: (arg: number) => (state.threads ? state.threads.spawn(arg) : SPAWN_FAILED),
+    };
+  }
+
+  state.instance = await WebAssembly.instantiate(wasmModule, imports);
+
+  // Exported memory, or the shared one a threaded module imports
+  const exported = state.instance.exports.memory;
+  state.memory = exported instanceof WebAssembly.Memory ? exported : (shared?.memory ?? null);
+
+  // Allocator exports are optional, only needed for buffer arguments
+  state.allocator = getAllocator(state.instance.exports);
+
+  // Result slot, used by exports that return strings or byte buffers
+  const resultFn = state.instance.exports.ww_result;
+  state.resultSlot = typeof resultFn === 'function' ? (resultFn() as number) >>> 0 : null;
+
+  // Panic slot, filled in when a Rust export panics
+  const panicFn = state.instance.exports.ww_panic;
+  state.panicSlot = typeof panicFn === 'function' ? (panicFn() as number) >>> 0 : null;
+
+  return state.instance;
+}
+
 /**
  * Initialize the WASM module
  */
 async function handleInit(msg: InitMsg): Promise<void> {
   try {
-    const response = await fetch(msg.moduleUrl);
+    // A respawned worker gets the module compiled by its predecessor
+    let wasmModule = msg.module;
+    // Kept when at hand, for the limits of a shared memory
+    let bytes: ArrayBuffer | undefined;
+    // Unread copy of a streamed module, read only if it imports a memory
+    let streamed: Response | undefined;
 
-    if (!response.ok) {
-      sendError(
-        msg.id,
-        'MODULE_FETCH_FAILED',
-        `Failed to fetch module: ${response.statusText}`,
-        { status: response.status, url: msg.moduleUrl }
-      );
+    // Opt-in, loads work the same without it
+    const cacheMode = msg.cache ?? 'none';
+    const cache = !wasmModule && cacheMode !== 'none' ? await openModuleStore() : null;
+    // Bytes are checked before they are compiled, whatever their source
+    const verify = (bytes: ArrayBuffer) =>
+      msg.integrity === undefined ? Promise.resolve() : checkIntegrity(bytes, msg.integrity);
+
+    if (!wasmModule && msg.moduleBytes) {
+      // Shared memory can't be compiled directly
+      bytes =
+        msg.moduleBytes instanceof ArrayBuffer
+          ? msg.moduleBytes
+          : new Uint8Array(msg.moduleBytes).slice().buffer;
+      await verify(bytes);
+      // The bytes are at hand, only compiling can be skipped
+      wasmModule =
+        cache && cacheMode === 'module'
+          ? await compileCached(cache, cacheMode, bytes)
+          : await WebAssembly.compile(bytes);
+    }
+
+    if (!wasmModule) {
+      const url = msg.moduleUrl!;
+      const loaded = cache ? await fetchCached(cache, cacheMode, url, msg.integrity) : await fetch(url);
+
+      if (!(loaded instanceof Response)) {
+        ({ module: wasmModule, bytes } = loaded);
+      } else if (!loaded.ok) {
+        sendError(
+          msg.id,
+          'MODULE_FETCH_FAILED',
+          `Failed to fetch module: ${loaded.statusText}`,
+          { status: loaded.status, url }
+        );
+        return;
+      } else if (msg.integrity !== undefined) {
+        bytes = await loaded.arrayBuffer();
+        await verify(bytes);
+        wasmModule = await WebAssembly.compile(bytes);
+      } else {
+        streamed = loaded.clone();
+        wasmModule = await compileResponse(loaded);
+      }
+    }
+
+    // Threaded modules import a shared memory, whose limits only the bytes
+    // tell, as the JS API has no way to read them
+    const sharedImport = !msg.bindgen && importsMemory(wasmModule);
+    if (sharedImport && !bytes && streamed) {
+      bytes = await streamed.arrayBuffer();
+    } else {
+      void streamed?.body?.cancel();
+    }
+
+    // The glue has the imports of wasm-bindgen modules, and the only
+    // usable wrappers of their exports
+    if (msg.bindgen) {
+      state.bindgen = await loadGlue(msg.bindgen.glueUrl, wasmModule);
+      if (msg.threads) {
+        await initGlueThreads(state.bindgen, msg.threads);
+      }
+      state.manifest = readManifest(wasmModule);
+      state.initialized = true;
+      sendResult(msg.id, { initialized: true, module: wasmModule, manifest: state.manifest });
       return;
     }
 
-    const wasmBuffer = await response.arrayBuffer();
-    const wasmModule = await WebAssembly.compile(wasmBuffer);
+    for (const [path, bytes] of Object.entries(msg.files ?? {})) {
+      state.fs.writeFile(resolvePath('/', path), new Uint8Array(bytes));
+    }
 
-    // Create imports object
-    const imports: WebAssembly.Imports = {
-      env: msg.init as Record<string, WebAssembly.ImportValue> || {},
-    };
+    // The helpers of a threaded module get its shared memory too
+    let memoryType = msg.memoryType ?? null;
+    if (!memoryType && sharedImport) {
+      memoryType = bytes ? sharedMemoryImport(bytes) : null;
+      if (!memoryType) {
+        sendError(
+          msg.id,
+          'WASM_INIT_FAILED',
+          bytes
+            ? 'The module imports a memory that is not shared, only shared memories can be provided'
+            : 'The limits of the shared memory the module imports are unknown, load it by URL or bytes instead of compiled',
+          { url: msg.moduleUrl }
+        );
+        return;
+      }
+    }
+    const memory = memoryType ? createSharedMemory(memoryType) : null;
+
+    if (msg.cancelBuffer) {
+      state.cancelFlags = new Int32Array(msg.cancelBuffer);
+    }
 
-    state.instance = await WebAssembly.instantiate(wasmModule, imports);
+    const instance = await instantiate(wasmModule, msg, memoryType && memory && { type: memoryType, memory });
 
-    // Try to get memory export
-    if (state.instance.exports.memory instanceof WebAssembly.Memory) {
-      state.memory = state.instance.exports.memory;
+    // Reactors, such as wasm32-wasip1 cdylibs, set up libc before any export runs
+    const initialize = instance.exports._initialize;
+    if (typeof initialize === 'function') {
+      initialize();
     }
 
+    state.manifest = readManifest(wasmModule);
+
     state.initialized = true;
-    sendResult(msg.id, { initialized: true });
+    sendResult(msg.id, {
+      initialized: true,
+      module: wasmModule,
+      manifest: state.manifest,
+      memory,
+      memoryType,
+    });
   } catch (error) {
+    if (error instanceof IntegrityError) {
+      sendError(msg.id, 'INTEGRITY_MISMATCH', error.message, {
+        ...error.details,
+        url: msg.moduleUrl,
+      });
+      return;
+    }
     const errorMsg = error instanceof Error ? error.message : String(error);
     sendError(
       msg.id,
@@ -89,14 +497,174 @@ async function handleInit(msg: InitMsg): Promise<void> {
 }
 
 /**
- * Call a WASM function
+ * Turn a call payload into a list of JS arguments
+ *
+ * Object payloads are mapped to parameters by name when the export is in
+ * the module's manifest, and taken in key order otherwise.
  */
-function handleCall(msg: CallMsg): void {
+function payloadToArgs(fnName: string, payload: unknown, entry?: ExportDescription): unknown[] {
+  if (payload === null || payload === undefined) {
+    return [];
+  }
+  if (Array.isArray(payload)) {
+    return payload;
+  }
+  if (isBinary(payload)) {
+    // A single buffer, not an object to spread
+    return [payload];
+  }
+  if (typeof payload === 'object') {
+    if (entry) {
+      return namedArgs(fnName, entry.params, payload as Record<string, unknown>);
+    }
+    return Object.values(payload);
+  }
+  // Single primitive value
+  return [payload];
+}
+
+/**
+ * Lower JS arguments to wasm values, copying buffers into guest memory
+ *
+ * Every buffer copied in is pushed onto `buffers` so the caller can release
+ * it once the call has finished.
+ */
+function lowerArgs(fnName: string, args: unknown[], buffers: GuestBuffer[]): unknown[] {
+  const lowered: unknown[] = [];
+
+  args.forEach((arg, index) => {
+    if (!isBinary(arg) && typeof arg !== 'string') {
+      lowered.push(arg);
+      return;
+    }
+
+    if (!state.memory || !state.allocator) {
+      throw new InvalidPayloadError(
+        `Function "${fnName}" received a buffer or string but the module does not export memory, ww_alloc and ww_free`,
+        { function: fnName, argument: index }
+      );
+    }
+
+    const bytes = typeof arg === 'string' ? textEncoder.encode(arg) : toBytes(arg);
+    const buffer = copyIn(state.memory, state.allocator, bytes);
+    buffers.push(buffer);
+    lowered.push(buffer.ptr, buffer.len);
+  });
+
+  return lowered;
+}
+
+/**
+ * Pick up a string or byte buffer the export left in the result slot
+ *
+ * Returns undefined when the slot is empty, in which case the export's
+ * return value is the result.
+ */
+function takeSlotResult(): SlotResult | undefined {
+  if (state.resultSlot === null || !state.memory || !state.allocator) {
+    return undefined;
+  }
+  return takeResult(state.memory, state.allocator, state.resultSlot) ?? undefined;
+}
+
+/**
+ * Decode a result slot into the value sent to the main thread
+ *
+ * Throws InvalidPayloadError when the guest rejected the payload, and
+ * AppError when the export returned `Err`.
+ */
+function decodeSlotResult(
+  fnName: string,
+  result: SlotResult
+): { value: unknown; transfer: Transferable[] } {
+  const { encoding, status } = splitKind(result.kind);
+  const value = decodeValue(encoding, result.bytes);
+
+  if (status === ResultStatus.InvalidPayload) {
+    throw new InvalidPayloadError(
+      `Invalid payload for function "${fnName}": ${String(value)}`,
+      { function: fnName, error: value }
+    );
+  }
+
+  if (status === ResultStatus.AppError) {
+    const suffix = typeof value === 'string' ? `: ${value}` : '';
+    throw new AppError(`Function "${fnName}" returned an error${suffix}`, value);
+  }
+
+  const transfer = encoding === ResultKind.Bytes ? [result.bytes.buffer as ArrayBuffer] : [];
+  return { value, transfer };
+}
+
+/**
+ * The manifest entry of a direct export, if the module has one
+ */
+function directExport(fnName: string): ExportDescription | undefined {
+  return state.manifest.find((entry) => entry.name === fnName && entry.codec === null);
+}
+
+/**
+ * Build the JS arguments for a call, encoding the payload if a codec is set
+ *
+ * Arguments to a direct export are checked against its manifest entry.
+ */
+function callArgs(msg: CallMsg | StreamOpenMsg): unknown[] {
+  if (!msg.codec) {
+    const entry = directExport(msg.fn);
+    const args = payloadToArgs(msg.fn, msg.payload, entry);
+    return entry ? checkArgs(msg.fn, entry.params, args) : args;
+  }
+
+  try {
+    return [encodePayload(msg.codec, msg.payload)];
+  } catch (error) {
+    const errorMsg = error instanceof Error ? error.message : String(error);
+    throw new InvalidPayloadError(
+      `Failed to encode payload for function "${msg.fn}": ${errorMsg}`,
+      { function: msg.fn, codec: msg.codec, error: errorMsg }
+    );
+  }
+}
+
+/**
+ * Release buffers copied into guest memory for a call
+ */
+function releaseBuffers(buffers: GuestBuffer[]): void {
+  for (const buffer of buffers) {
+    try {
+      state.allocator?.free(buffer.ptr, buffer.len);
+    } catch {
+      // The instance may be unusable after a trap; the call error is what matters
+    }
+  }
+}
+
+/**
+ * Report a call that was cancelled before or while it ran
+ */
+function sendCancelled(msg: CallMsg | StreamOpenMsg): void {
+  sendError(msg.id, 'CANCELLED', `Call to "${msg.fn}" was cancelled`, { function: msg.fn });
+}
+
+/**
+ * Run the export for a call or stream
+ *
+ * `onReturn` receives the export's return value and may throw
+ * InvalidPayloadError. Any failure is reported to the main thread.
+ */
+function invoke(msg: CallMsg | StreamOpenMsg, onReturn: (result: unknown) => void): void {
   if (!state.initialized || !state.instance) {
     sendError(msg.id, 'NOT_INITIALIZED', 'Worker not initialized with WASM module');
     return;
   }
 
+  if (isCancelled(msg.seq)) {
+    sendCancelled(msg);
+    return;
+  }
+
+  const buffers: GuestBuffer[] = [];
+
   try {
     const fn = state.instance.exports[msg.fn];
 
@@ -110,41 +678,301 @@ function handleCall(msg: CallMsg): void {
       return;
     }
 
-    let result: unknown;
+    const args = callArgs(msg);
+    const lowered = lowerArgs(msg.fn, args, buffers);
+    if (!msg.codec && !directExport(msg.fn)) {
+      // Without a manifest, the wasm function type is all there is to check
+      checkArity(msg.fn, fn.length, args, lowered);
+    }
 
-    // Handle different payload types
-    if (msg.payload === null || msg.payload === undefined) {
-      result = fn();
-    } else if (typeof msg.payload === 'object' && !Array.isArray(msg.payload)) {
-      // Object payload - extract values in order
-      const args = Object.values(msg.payload);
-      result = fn(...args);
-    } else if (Array.isArray(msg.payload)) {
-      // Array payload
-      result = fn(...msg.payload);
-    } else {
-      // Single primitive value
-      result = fn(msg.payload);
+    state.currentSeq = msg.seq ?? null;
+    const result: unknown = fn(...lowered);
+
+    if (isCancelled(msg.seq)) {
+      // The export returned early, its result is meaningless
+      takeSlotResult();
+      sendCancelled(msg);
+      return;
     }
 
-    sendResult(msg.id, result);
+    onReturn(result);
   } catch (error) {
+    if (error instanceof InvalidPayloadError || error instanceof ArgumentError) {
+      sendError(msg.id, 'INVALID_PAYLOAD', error.message, error.details);
+      return;
+    }
+
+    if (error instanceof AppError) {
+      sendError(msg.id, 'APP_ERROR', error.message, error.details);
+      return;
+    }
+
     const errorMsg = error instanceof Error ? error.message : String(error);
+    if (error instanceof WasiExit) {
+      sendError(msg.id, 'WASM_TRAP', `WASM execution error: ${errorMsg}`, {
+        function: msg.fn,
+        error: errorMsg,
+        exitCode: error.code,
+      });
+      return;
+    }
+
+    const panic = state.panicSlot !== null && state.memory ? takePanic(state.memory, state.panicSlot) : null;
+    if (panic) {
+      sendError(
+        msg.id,
+        'WASM_TRAP',
+        `WASM execution error: panicked at ${panic.location}: ${panic.message}`,
+        { function: msg.fn, error: errorMsg, panicMessage: panic.message, location: panic.location }
+      );
+      return;
+    }
+
     sendError(
       msg.id,
       'WASM_TRAP',
       `WASM execution error: ${errorMsg}`,
       { function: msg.fn, error: errorMsg }
     );
+  } finally {
+    state.currentSeq = null;
+    state.wasi?.flush();
+    releaseBuffers(buffers);
+  }
+}
+
+/**
+ * Run a function of the wasm-bindgen glue for a call or stream
+ *
+ * Like `invoke`, but the glue converts arguments and results itself, and
+ * its functions may be async. An `Err` is thrown by the glue as is, and
+ * reported as APP_ERROR.
+ */
+async function invokeGlue(
+  msg: CallMsg | StreamOpenMsg,
+  onReturn: (result: unknown) => Promise<void> | void
+): Promise<void> {
+  const glue = state.bindgen!;
+  if (isCancelled(msg.seq)) {
+    sendCancelled(msg);
+    return;
+  }
+
+  // The setup exports would instantiate the module again
+  const fn = glueFunctions(glue).includes(msg.fn) ? glue[msg.fn] : undefined;
+  if (typeof fn !== 'function') {
+    sendError(msg.id, 'FN_NOT_FOUND', `Function "${msg.fn}" not found in wasm-bindgen glue`, {
+      availableFunctions: glueFunctions(glue),
+    });
+    return;
+  }
+
+  try {
+    // The glue takes JS values as they are, so only an array is spread
+    const args = Array.isArray(msg.payload) ? msg.payload : msg.payload === undefined ? [] : [msg.payload];
+    const result: unknown = await fn(...args);
+    if (isCancelled(msg.seq)) {
+      sendCancelled(msg);
+      return;
+    }
+    await onReturn(result);
+  } catch (error) {
+    const errorMsg = error instanceof Error ? error.message : String(error);
+    if (error instanceof WebAssembly.RuntimeError) {
+      sendError(msg.id, 'WASM_TRAP', `WASM execution error: ${errorMsg}`, {
+        function: msg.fn,
+        error: errorMsg,
+      });
+      return;
+    }
+
+    if (error instanceof DOMException && error.name === 'DataCloneError') {
+      // Functions and other values structured clone rejects
+      sendError(msg.id, 'UNKNOWN_ERROR', `Result of "${msg.fn}" cannot be sent: ${errorMsg}`, {
+        function: msg.fn,
+        error: errorMsg,
+      });
+      return;
+    }
+
+    sendError(msg.id, 'APP_ERROR', `Function "${msg.fn}" returned an error: ${errorMsg}`, {
+      function: msg.fn,
+      error: error instanceof Error ? errorMsg : toPostable(error),
+    });
   }
 }
 
 /**
- * Handle streaming call (stub for now)
+ * Stream what a glue function returns, every item of an iterable or the
+ * value itself
+ */
+async function streamGlue(msg: StreamOpenMsg, result: unknown): Promise<void> {
+  const iterable =
+    typeof result === 'object' &&
+    result !== null &&
+    (Symbol.asyncIterator in result || Symbol.iterator in result);
+  const chunks = (iterable ? result : result === undefined ? [] : [result]) as AsyncIterable<unknown>;
+
+  for await (const chunk of chunks) {
+    if (isCancelled(msg.seq)) {
+      sendCancelled(msg);
+      return;
+    }
+    postMessage({ id: msg.id, type: 'stream_chunk', value: toPostable(chunk) });
+  }
+  postMessage({ id: msg.id, type: 'stream_close' });
+}
+
+/**
+ * Call a WASM function
+ */
+function handleCall(msg: CallMsg): void {
+  if (state.bindgen) {
+    void invokeGlue(msg, (result) => sendResult(msg.id, toPostable(result)));
+    return;
+  }
+
+  invoke(msg, (result) => {
+    const slotResult = takeSlotResult();
+    if (slotResult) {
+      const { value, transfer } = decodeSlotResult(msg.fn, slotResult);
+      sendResult(msg.id, value, transfer);
+      return;
+    }
+
+    const entry = directExport(msg.fn);
+    sendResult(msg.id, entry ? liftReturn(entry.returns, result) : result);
+  });
+}
+
+/**
+ * Run a stream export
+ *
+ * Chunks are forwarded by `ww_emit` while the export runs, the stream is
+ * closed once it returns.
  */
 function handleStreamOpen(msg: StreamOpenMsg): void {
-  // For PoC, streaming will be implemented later
-  sendError(msg.id, 'UNKNOWN_ERROR', 'Streaming not yet implemented');
+  if (state.bindgen) {
+    void invokeGlue(msg, (result) => streamGlue(msg, result));
+    return;
+  }
+
+  state.streamId = msg.id;
+
+  try {
+    invoke(msg, () => {
+      // A codec stream may have rejected its payload
+      const slotResult = takeSlotResult();
+      if (slotResult) {
+        decodeSlotResult(msg.fn, slotResult);
+      }
+
+      postMessage({ id: msg.id, type: 'stream_close' });
+    });
+  } finally {
+    state.streamId = null;
+  }
+}
+
+/**
+ * Run a filesystem request from `worker.fs`
+ */
+function handleFs(msg: FsWriteMsg | FsReadMsg): void {
+  const path = resolvePath('/', msg.path);
+  try {
+    if (msg.type === 'fs_write') {
+      state.fs.writeFile(path, new Uint8Array(msg.bytes));
+      sendResult(msg.id);
+      return;
+    }
+    // Copied once, so the guest can keep writing to its file
+    const bytes = state.fs.readFile(path).slice();
+    sendResult(msg.id, bytes, [bytes.buffer]);
+  } catch (error) {
+    if (error instanceof FsError) {
+      sendError(msg.id, 'FS_ERROR', error.message, { path, errno: error.errno });
+      return;
+    }
+    // Anything else would leave the caller waiting
+    sendError(msg.id, 'UNKNOWN_ERROR', String(error), { path });
+  }
+}
+
+/**
+ * Instantiate a threaded module in a helper, and run the threads the
+ * worker starts on it
+ */
+async function handleThreadInit(msg: ThreadInitMsg): Promise<void> {
+  try {
+    const instance = await instantiate(msg.module, msg, { type: msg.memoryType, memory: msg.memory });
+    const start = instance.exports.wasi_thread_start;
+    if (typeof start !== 'function') {
+      throw new Error('threaded modules must export wasi_thread_start');
+    }
+
+    const busy = new Int32Array(msg.busy);
+    msg.port.onmessage = (event: MessageEvent<{ tid: number; arg: number }>) => {
+      const { tid, arg } = event.data;
+      try {
+        start(tid, arg);
+      } catch (error) {
+        // A thread has no caller to report to
+        if (!(error instanceof WasiExit)) {
+          const errorMsg = error instanceof Error ? error.message : String(error);
+          sendLog('error', `Thread ${tid} failed: ${errorMsg}`);
+        }
+      } finally {
+        state.wasi?.flush();
+        Atomics.store(busy, msg.index, 0);
+      }
+    };
+
+    sendResult(msg.id);
+  } catch (error) {
+    const errorMsg = error instanceof Error ? error.message : String(error);
+    sendError(msg.id, 'WASM_INIT_FAILED', `Failed to start thread helper: ${errorMsg}`, {
+      error: errorMsg,
+    });
+  }
+}
+
+/**
+ * Take the helpers threads are started on
+ */
+function handleThreads(msg: ThreadsMsg): void {
+  state.threads = new ThreadPool(msg.ports, new Int32Array(msg.busy));
+  sendResult(msg.id);
+}
+
+/**
+ * Allocate or free a shared region with the guest allocator
+ */
+function handleShared(msg: SharedAllocMsg | SharedFreeMsg): void {
+  if (!state.allocator) {
+    sendError(msg.id, 'FN_NOT_FOUND', 'Shared regions need the module to export ww_alloc and ww_free');
+    return;
+  }
+
+  if (msg.type === 'shared_free') {
+    const buffer = state.regions.get(msg.offset);
+    if (buffer) {
+      state.regions.delete(msg.offset);
+      state.allocator.free(buffer.ptr, buffer.len);
+    }
+    sendResult(msg.id);
+    return;
+  }
+
+  try {
+    const { buffer, offset } = allocRegion(state.allocator, msg.byteLength);
+    state.regions.set(offset, buffer);
+    sendResult(msg.id, { offset });
+  } catch (error) {
+    const errorMsg = error instanceof Error ? error.message : String(error);
+    sendError(msg.id, 'WASM_TRAP', `Failed to allocate a shared region: ${errorMsg}`, {
+      byteLength: msg.byteLength,
+    });
+  }
 }
 
 /**
@@ -163,6 +991,23 @@ self.addEventListener('message', (event: MessageEvent<WorkerRequest>) => {
     case 'stream_open':
       handleStreamOpen(msg);
       break;
+    case 'cancel':
+      handleCancel(msg);
+      break;
+    case 'fs_write':
+    case 'fs_read':
+      handleFs(msg);
+      break;
+    case 'thread_init':
+      void handleThreadInit(msg);
+      break;
+    case 'threads':
+      handleThreads(msg);
+      break;
+    case 'shared_alloc':
+    case 'shared_free':
+      handleShared(msg);
+      break;
     default:
       // Type-safe exhaustiveness check
       const _exhaustive: never = msg;
@@ -0,0 +1,165 @@
+/**
+ * Threads of modules built for `wasm32-wasip1-threads`
+ *
+ * Such modules import a shared memory, and start threads through the
+ * `wasi.thread-spawn` import of the wasi-threads proposal. Every thread is
+ * another instance of the module against the same memory, running in a
+ * helper worker, which calls its `wasi_thread_start` export.
+ */
+
+import type { SharedMemoryType } from '../types.js';
+
+/**
+ * Import of the wasi-threads proposal that starts a thread
+ */
+export const THREAD_SPAWN = { module: 'wasi', name: 'thread-spawn' } as const;
+
+/**
+ * Negated WASI errno EAGAIN, returned by `thread-spawn` when no helper is idle
+ */
+export const SPAWN_FAILED = -6;
+
+/**
+ * Create the shared memory a threaded module imports
+ */
+export function createSharedMemory(type: SharedMemoryType): WebAssembly.Memory {
+  if (typeof SharedArrayBuffer !== 'function' || !self.crossOriginIsolated) {
+    throw new Error('threaded modules need a cross-origin isolated page');
+  }
+  return new WebAssembly.Memory({ initial: type.initial, maximum: type.maximum, shared: true });
+}
+
+/**
+ * Reads the binary format, just far enough to walk the import section
+ */
+class Reader {
+  offset = 0;
+
+  constructor(private bytes: Uint8Array) {}
+
+  get done(): boolean {
+    return this.offset >= this.bytes.length;
+  }
+
+  byte(): number {
+    if (this.done) {
+      throw new RangeError('Unexpected end of module');
+    }
+    return this.bytes[this.offset++];
+  }
+
+  // Unsigned LEB128, memory64 limits need more than 32 bits
+  uint(): number {
+    let value = 0;
+    let scale = 1;
+    for (;;) {
+      const byte = this.byte();
+      value += (byte & 0x7f) * scale;
+      if ((byte & 0x80) === 0) {
+        return value;
+      }
+      scale *= 128;
+    }
+  }
+
+  name(): string {
+    const length = this.uint();
+    const start = this.offset;
+    this.offset += length;
+    return new TextDecoder().decode(this.bytes.subarray(start, this.offset));
+  }
+
+  limits(): { flags: number; initial: number; maximum?: number } {
+    const flags = this.byte();
+    const initial = this.uint();
+    return { flags, initial, maximum: flags & 1 ? this.uint() : undefined };
+  }
+}
+
+/**
+ * Whether `module` imports its memory, as threaded modules do
+ */
+export function importsMemory(module: WebAssembly.Module): boolean {
+  return WebAssembly.Module.imports(module).some((entry) => entry.kind === 'memory');
+}
+
+/**
+ * The shared memory a module's bytes import, if any
+ *
+ * The JS API can't tell the limits of an imported memory, yet a shared one
+ * must be created with them before the module is instantiated.
+ */
+export function sharedMemoryImport(bytes: ArrayBuffer): SharedMemoryType | null {
+  const reader = new Reader(new Uint8Array(bytes));
+  // Magic number and version
+  reader.offset = 8;
+
+  while (!reader.done) {
+    const id = reader.byte();
+    const size = reader.uint();
+    // Sections are in order, imports come second
+    if (id !== 2) {
+      if (id > 2) {
+        return null;
+      }
+      reader.offset += size;
+      continue;
+    }
+
+    const count = reader.uint();
+    for (let i = 0; i < count; i++) {
+      const module = reader.name();
+      const name = reader.name();
+      const kind = reader.byte();
+      if (kind === 0) {
+        reader.uint();
+      } else if (kind === 1) {
+        reader.byte();
+        reader.limits();
+      } else if (kind === 2) {
+        const { flags, initial, maximum } = reader.limits();
+        // Shared memories always declare their maximum
+        return flags & 2 && maximum !== undefined ? { module, name, initial, maximum } : null;
+      } else if (kind === 3) {
+        reader.byte();
+        reader.byte();
+      } else if (kind === 4) {
+        reader.byte();
+        reader.uint();
+      } else {
+        return null;
+      }
+    }
+    return null;
+  }
+  return null;
+}
+
+/**
+ * Hands the threads a module starts to idle helper workers
+ *
+ * `busy` is shared with the helpers, which clear their slot once their
+ * thread has returned.
+ */
+export class ThreadPool {
+  private nextTid = 1;
+
+  constructor(private ports: MessagePort[], private busy: Int32Array) {}
+
+  /**
+   * Start a thread, returning its id or a negated errno
+   *
+   * Called while the module runs, so the helper is told through its port
+   * rather than the event loop of this worker.
+   */
+  spawn(arg: number): number {
+    for (let index = 0; index < this.ports.length; index++) {
+      if (Atomics.compareExchange(this.busy, index, 0, 1) === 0) {
+        const tid = this.nextTid++;
+        this.ports[index].postMessage({ tid, arg });
+        return tid;
+      }
+    }
+    return SPAWN_FAILED;
+  }
+}
@@ -0,0 +1,196 @@
+import type { ExportParam } from '../types.js';
+import { isBinary } from './memory.js';
+
+/**
+ * Error raised when call arguments do not match an export's signature
+ */
+export class ArgumentError extends Error {
+  constructor(message: string, public details: Record<string, unknown>) {
+    super(message);
+    this.name = 'ArgumentError';
+  }
+}
+
+/**
+ * Describe a value for error messages, e.g. `number -1` or `string`
+ */
+export function describeValue(value: unknown): string {
+  if (value === null) return 'null';
+  if (Array.isArray(value)) return 'array';
+  if (isBinary(value)) return 'binary';
+  switch (typeof value) {
+    case 'number':
+    case 'boolean':
+      return `${typeof value} ${String(value)}`;
+    case 'bigint':
+      return `bigint ${String(value)}n`;
+    default:
+      return typeof value;
+  }
+}
+
+/**
+ * Strip paths and lifetimes from a Rust type, e.g. `&'a str` to `&str`
+ */
+function normalizeType(type: string): string {
+  return type.replace(/'\w+\s*/g, '').replace(/(?:\w+::)+/g, '');
+}
+
+const INT_RANGES: Record<string, [bigint, bigint]> = {
+  i32: [-(2n ** 31n), 2n ** 31n - 1n],
+  u32: [0n, 2n ** 32n - 1n],
+  i64: [-(2n ** 63n), 2n ** 63n - 1n],
+  u64: [0n, 2n ** 64n - 1n],
+};
+
+/**
+ * Check one argument against a Rust parameter type
+ *
+ * Returns the value to pass to the export, or undefined when it does not
+ * fit. 64-bit integers may be given as safe integer numbers and are
+ * converted to bigints, which is what wasm expects.
+ */
+function checkArg(type: string, value: unknown): { value: unknown } | undefined {
+  switch (type) {
+    case 'i32':
+    case 'u32': {
+      if (typeof value !== 'number' || !Number.isInteger(value)) return undefined;
+      const [min, max] = INT_RANGES[type];
+      return BigInt(value) >= min && BigInt(value) <= max ? { value } : undefined;
+    }
+    case 'i64':
+    case 'u64': {
+      let int: bigint;
+      if (typeof value === 'bigint') {
+        int = value;
+      } else if (typeof value === 'number' && Number.isSafeInteger(value)) {
+        int = BigInt(value);
+      } else {
+        return undefined;
+      }
+      const [min, max] = INT_RANGES[type];
+      return int >= min && int <= max ? { value: int } : undefined;
+    }
+    case 'f32':
+    case 'f64':
+      return typeof value === 'number' ? { value } : undefined;
+    case 'bool':
+      return typeof value === 'boolean' || value === 0 || value === 1 ? { value } : undefined;
+    case '&str':
+    case 'String':
+      return typeof value === 'string' ? { value } : undefined;
+    case '&[u8]':
+    case 'Vec<u8>':
+      return isBinary(value) ? { value } : undefined;
+    default:
+      // Not a type direct exports accept, leave it to the export
+      return { value };
+  }
+}
+
+/**
+ * Check call arguments against the parameters from an export's manifest
+ *
+ * Returns the arguments to pass to the export. Throws ArgumentError
+ * describing the first argument that does not fit.
+ */
+export function checkArgs(fnName: string, params: ExportParam[], args: unknown[]): unknown[] {
+  if (args.length !== params.length) {
+    throw new ArgumentError(
+      `Function "${fnName}" takes ${params.length} argument(s), got ${args.length}`,
+      { function: fnName, expected: params.length, received: args.length }
+    );
+  }
+
+  return params.map((param, index) => {
+    const type = normalizeType(param.type);
+    const checked = checkArg(type, args[index]);
+    if (!checked) {
+      const received = describeValue(args[index]);
+      throw new ArgumentError(
+        `Argument "${param.name}" of "${fnName}" must be ${type}, got ${received}`,
+        { function: fnName, argument: index, name: param.name, expected: type, received }
+      );
+    }
+    return checked.value;
+  });
+}
+
+/**
+ * Read the value a direct export returned as its Rust return type
+ *
+ * Wasm integers are signed, so `u32` and `u64` values above the signed range
+ * come back negative, and `bool` comes back as `0` or `1`. The `T` of a
+ * `Result<T, E>` is what the export returns.
+ */
+export function liftReturn(returns: string | null, value: unknown): unknown {
+  const type = normalizeType(returns ?? '').replace(/^Result<([^,<]+),.*>$/, '$1');
+  switch (type) {
+    case 'u32':
+      return typeof value === 'number' ? value >>> 0 : value;
+    case 'u64':
+      return typeof value === 'bigint' ? BigInt.asUintN(64, value) : value;
+    case 'bool':
+      return typeof value === 'number' ? value !== 0 : value;
+    default:
+      return value;
+  }
+}
+
+/**
+ * Map an object payload to arguments by the names of an export's parameters
+ *
+ * Throws ArgumentError when the payload has keys the export has no
+ * parameter for, or lacks a key for one of its parameters.
+ */
+export function namedArgs(
+  fnName: string,
+  params: ExportParam[],
+  payload: Record<string, unknown>
+): unknown[] {
+  const names = params.map((param) => param.name);
+
+  const unknown = Object.keys(payload).filter((key) => !names.includes(key));
+  if (unknown.length > 0) {
+    throw new ArgumentError(
+      `Function "${fnName}" has no parameter named ${unknown.map((key) => `"${key}"`).join(', ')}`,
+      { function: fnName, unknown, expected: names }
+    );
+  }
+
+  const missing = names.filter((name) => !Object.prototype.hasOwnProperty.call(payload, name));
+  if (missing.length > 0) {
+    throw new ArgumentError(
+      `Function "${fnName}" is missing argument ${missing.map((name) => `"${name}"`).join(', ')}`,
+      { function: fnName, missing, expected: names }
+    );
+  }
+
+  return names.map((name) => payload[name]);
+}
+
+/**
+ * Check lowered arguments against the arity of the wasm function
+ *
+ * Used for modules without a manifest, where the wasm function type is
+ * all there is to go by. Buffers and strings take two wasm values.
+ */
+export function checkArity(fnName: string, arity: number, args: unknown[], lowered: unknown[]): void {
+  args.forEach((arg, index) => {
+    const type = typeof arg;
+    if (type !== 'number' && type !== 'bigint' && type !== 'boolean' && type !== 'string' && !isBinary(arg)) {
+      const received = describeValue(arg);
+      throw new ArgumentError(
+        `Argument ${index} of "${fnName}" must be a number, bigint, boolean, string or binary, got ${received}`,
+        { function: fnName, argument: index, received }
+      );
+    }
+  });
+
+  if (lowered.length !== arity) {
+    throw new ArgumentError(
+      `Function "${fnName}" takes ${arity} wasm value(s), got ${lowered.length}`,
+      { function: fnName, expected: arity, received: lowered.length }
+    );
+  }
+}
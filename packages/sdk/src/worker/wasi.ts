@@ -0,0 +1,577 @@
+import { Errno, FsError, MemoryFs, resolvePath, type FileNode } from './fs.js';
+
+/**
+ * Import module of the WASI preview1 functions
+ */
+export const WASI_MODULE = 'wasi_snapshot_preview1';
+
+const FileType = {
+  UNKNOWN: 0,
+  CHARACTER_DEVICE: 2,
+  DIRECTORY: 3,
+  REGULAR_FILE: 4,
+} as const;
+
+const Oflags = {
+  CREAT: 1,
+  DIRECTORY: 2,
+  EXCL: 4,
+  TRUNC: 8,
+} as const;
+
+const FDFLAG_APPEND = 1;
+
+const Clock = {
+  REALTIME: 0,
+  MONOTONIC: 1,
+  PROCESS_CPUTIME: 2,
+  THREAD_CPUTIME: 3,
+} as const;
+
+const Whence = {
+  SET: 0,
+  CUR: 1,
+  END: 2,
+} as const;
+
+// Every right, descriptors are not restricted
+const ALL_RIGHTS = 0xffffffffffffffffn;
+
+/**
+ * An open file descriptor
+ */
+type Descriptor =
+  | { type: 'stdio' }
+  | { type: 'dir'; path: string; preopen?: string }
+  | { type: 'file'; path: string; file: FileNode; position: number; append: boolean };
+
+/**
+ * Thrown by `proc_exit`, ends the running export
+ */
+export class WasiExit extends Error {
+  constructor(public code: number) {
+    super(`Module exited with code ${code}`);
+    this.name = 'WasiExit';
+  }
+}
+
+/**
+ * What the shim needs from the runtime
+ */
+export interface WasiHost {
+  args: string[];
+  env: Record<string, string>;
+  fs: MemoryFs;
+  // Read on every call, the memory's buffer changes when it grows
+  memory: () => WebAssembly.Memory | null;
+  // A complete line written to stdout (1) or stderr (2)
+  output: (fd: 1 | 2, line: string) => void;
+}
+
+/**
+ * Block the worker for `ms` milliseconds
+ */
+function sleep(ms: number): void {
+  if (typeof SharedArrayBuffer === 'function') {
+    Atomics.wait(new Int32Array(new SharedArrayBuffer(4)), 0, 0, ms);
+    return;
+  }
+  const end = Date.now() + ms;
+  while (Date.now() < end) {
+    // Atomics.wait needs shared memory, spin instead
+  }
+}
+
+/**
+ * WASI preview1 for modules built for `wasm32-wasip1`
+ *
+ * Stdout and stderr are forwarded line by line, files live in a `MemoryFs`
+ * preopened as `/`, and sockets are not supported.
+ */
+export class Wasi {
+  private fds = new Map<number, Descriptor>();
+  private nextFd = 4;
+  // Partial lines written to stdout and stderr
+  private lines: Record<1 | 2, string> = { 1: '', 2: '' };
+  private decoders: Record<1 | 2, TextDecoder> = { 1: new TextDecoder(), 2: new TextDecoder() };
+  private encoder = new TextEncoder();
+
+  constructor(private host: WasiHost) {
+    this.fds.set(0, { type: 'stdio' });
+    this.fds.set(1, { type: 'stdio' });
+    this.fds.set(2, { type: 'stdio' });
+    this.fds.set(3, { type: 'dir', path: '/', preopen: '/' });
+  }
+
+  /**
+   * Import object for the functions in `names`, as imported by the module
+   *
+   * Functions the shim doesn't implement return `ENOSYS`.
+   */
+  imports(names: string[]): Record<string, (...args: any[]) => number> {
+    const syscalls = this.syscalls() as Record<string, (...args: any[]) => number>;
+    return Object.fromEntries(
+      names.map((name) => [name, syscalls[name] ? this.guard(syscalls[name]) : () => Errno.NOSYS])
+    );
+  }
+
+  /**
+   * Forward partial lines left on stdout and stderr
+   */
+  flush(): void {
+    for (const fd of [1, 2] as const) {
+      if (this.lines[fd]) {
+        this.host.output(fd, this.lines[fd]);
+        this.lines[fd] = '';
+      }
+    }
+  }
+
+  /**
+   * Report filesystem errors as their errno
+   */
+  private guard(syscall: (...args: any[]) => number): (...args: any[]) => number {
+    return (...args) => {
+      try {
+        return syscall(...args);
+      } catch (error) {
+        if (error instanceof FsError) {
+          return error.errno;
+        }
+        throw error;
+      }
+    };
+  }
+
+  private get view(): DataView {
+    return new DataView(this.memory.buffer);
+  }
+
+  private get bytes(): Uint8Array {
+    return new Uint8Array(this.memory.buffer);
+  }
+
+  private get memory(): WebAssembly.Memory {
+    const memory = this.host.memory();
+    if (!memory) {
+      throw new Error('WASI needs the module to export its memory');
+    }
+    return memory;
+  }
+
+  private string(ptr: number, len: number): string {
+    // Shared memory cannot be decoded directly
+    return new TextDecoder().decode(this.bytes.slice(ptr >>> 0, (ptr >>> 0) + (len >>> 0)));
+  }
+
+  private descriptor(fd: number): Descriptor {
+    const descriptor = this.fds.get(fd);
+    if (!descriptor) {
+      throw new FsError(Errno.BADF, `Bad file descriptor ${fd}`);
+    }
+    return descriptor;
+  }
+
+  private file(fd: number): Extract<Descriptor, { type: 'file' }> {
+    const descriptor = this.descriptor(fd);
+    if (descriptor.type === 'dir') {
+      throw new FsError(Errno.ISDIR, `File descriptor ${fd} is a directory`);
+    }
+    if (descriptor.type !== 'file') {
+      throw new FsError(Errno.SPIPE, `File descriptor ${fd} is not seekable`);
+    }
+    return descriptor;
+  }
+
+  private dirPath(fd: number, ptr: number, len: number): string {
+    const descriptor = this.descriptor(fd);
+    if (descriptor.type !== 'dir') {
+      throw new FsError(Errno.NOTDIR, `File descriptor ${fd} is not a directory`);
+    }
+    return resolvePath(descriptor.path, this.string(ptr, len));
+  }
+
+  /**
+   * Copy the buffers of an iovec array out of guest memory
+   */
+  private gather(iovs: number, count: number): Uint8Array {
+    const view = this.view;
+    const parts: Uint8Array[] = [];
+    for (let i = 0; i < count; i++) {
+      const ptr = view.getUint32(iovs + i * 8, true);
+      const len = view.getUint32(iovs + i * 8 + 4, true);
+      parts.push(this.bytes.slice(ptr, ptr + len));
+    }
+    const joined = new Uint8Array(parts.reduce((total, part) => total + part.length, 0));
+    let offset = 0;
+    for (const part of parts) {
+      joined.set(part, offset);
+      offset += part.length;
+    }
+    return joined;
+  }
+
+  /**
+   * Copy `data` into the buffers of an iovec array, returning the bytes written
+   */
+  private scatter(iovs: number, count: number, data: Uint8Array): number {
+    const view = this.view;
+    const memory = this.bytes;
+    let copied = 0;
+    for (let i = 0; i < count && copied < data.length; i++) {
+      const ptr = view.getUint32(iovs + i * 8, true);
+      const len = view.getUint32(iovs + i * 8 + 4, true);
+      const chunk = data.subarray(copied, copied + len);
+      memory.set(chunk, ptr);
+      copied += chunk.length;
+    }
+    return copied;
+  }
+
+  /**
+   * Write NUL terminated strings and a pointer to each, for args and environ
+   */
+  private writeStrings(strings: string[], ptrs: number, buf: number): number {
+    const view = this.view;
+    const memory = this.bytes;
+    let offset = buf >>> 0;
+    strings.forEach((value, i) => {
+      view.setUint32((ptrs >>> 0) + i * 4, offset, true);
+      const encoded = this.encoder.encode(value);
+      memory.set(encoded, offset);
+      memory[offset + encoded.length] = 0;
+      offset += encoded.length + 1;
+    });
+    return Errno.SUCCESS;
+  }
+
+  private writeSizes(strings: string[], countPtr: number, sizePtr: number): number {
+    const view = this.view;
+    const size = strings.reduce((total, value) => total + this.encoder.encode(value).length + 1, 0);
+    view.setUint32(countPtr >>> 0, strings.length, true);
+    view.setUint32(sizePtr >>> 0, size, true);
+    return Errno.SUCCESS;
+  }
+
+  private writeFilestat(ptr: number, type: number, size: number, mtime = 0n): number {
+    const view = this.view;
+    ptr >>>= 0;
+    this.bytes.fill(0, ptr, ptr + 64);
+    view.setUint8(ptr + 16, type);
+    view.setBigUint64(ptr + 24, 1n, true);
+    view.setBigUint64(ptr + 32, BigInt(size), true);
+    view.setBigUint64(ptr + 40, mtime, true);
+    view.setBigUint64(ptr + 48, mtime, true);
+    view.setBigUint64(ptr + 56, mtime, true);
+    return Errno.SUCCESS;
+  }
+
+  private stat(path: string, ptr: number): number {
+    const kind = this.host.fs.kind(path);
+    if (kind === 'dir') {
+      return this.writeFilestat(ptr, FileType.DIRECTORY, 0);
+    }
+    const file = this.host.fs.open(path);
+    return this.writeFilestat(ptr, FileType.REGULAR_FILE, file.size, file.mtime);
+  }
+
+  private writeOutput(fd: 1 | 2, data: Uint8Array): void {
+    const text = this.lines[fd] + this.decoders[fd].decode(data, { stream: true });
+    const lines = text.split('\n');
+    this.lines[fd] = lines.pop()!;
+    for (const line of lines) {
+      this.host.output(fd, line);
+    }
+  }
+
+  private syscalls() {
+    const fs = this.host.fs;
+    const env = Object.entries(this.host.env).map(([key, value]) => `${key}=${value}`);
+
+    return {
+      args_get: (argv: number, buf: number) => this.writeStrings(this.host.args, argv, buf),
+      args_sizes_get: (count: number, size: number) => this.writeSizes(this.host.args, count, size),
+      environ_get: (environ: number, buf: number) => this.writeStrings(env, environ, buf),
+      environ_sizes_get: (count: number, size: number) => this.writeSizes(env, count, size),
+
+      clock_res_get: (id: number, ptr: number) => {
+        if (id > Clock.THREAD_CPUTIME) {
+          return Errno.INVAL;
+        }
+        this.view.setBigUint64(ptr >>> 0, id === Clock.REALTIME ? 1_000_000n : 1_000n, true);
+        return Errno.SUCCESS;
+      },
+      clock_time_get: (id: number, _precision: bigint, ptr: number) => {
+        if (id > Clock.THREAD_CPUTIME) {
+          return Errno.INVAL;
+        }
+        const ms = id === Clock.REALTIME ? performance.timeOrigin + performance.now() : performance.now();
+        this.view.setBigUint64(ptr >>> 0, BigInt(Math.round(ms * 1_000_000)), true);
+        return Errno.SUCCESS;
+      },
+
+      random_get: (ptr: number, len: number) => {
+        // getRandomValues fills at most 64KiB, and not shared memory
+        const chunk = new Uint8Array(Math.min(len >>> 0, 65536));
+        for (let offset = 0; offset < len >>> 0; offset += chunk.length) {
+          const part = chunk.subarray(0, Math.min(chunk.length, (len >>> 0) - offset));
+          crypto.getRandomValues(part);
+          this.bytes.set(part, (ptr >>> 0) + offset);
+        }
+        return Errno.SUCCESS;
+      },
+
+      fd_write: (fd: number, iovs: number, count: number, written: number) => {
+        const descriptor = this.descriptor(fd);
+        const data = this.gather(iovs >>> 0, count >>> 0);
+        if (fd === 1 || fd === 2) {
+          this.writeOutput(fd, data);
+        } else if (descriptor.type === 'file') {
+          const offset = descriptor.append ? descriptor.file.size : descriptor.position;
+          fs.write(descriptor.file, offset, data);
+          descriptor.position = offset + data.length;
+        } else {
+          return Errno.BADF;
+        }
+        this.view.setUint32(written >>> 0, data.length, true);
+        return Errno.SUCCESS;
+      },
+      fd_pwrite: (fd: number, iovs: number, count: number, offset: bigint, written: number) => {
+        const descriptor = this.file(fd);
+        const data = this.gather(iovs >>> 0, count >>> 0);
+        fs.write(descriptor.file, Number(offset), data);
+        this.view.setUint32(written >>> 0, data.length, true);
+        return Errno.SUCCESS;
+      },
+      fd_read: (fd: number, iovs: number, count: number, read: number) => {
+        const descriptor = this.descriptor(fd);
+        let copied = 0;
+        if (descriptor.type === 'file') {
+          const { file } = descriptor;
+          copied = this.scatter(iovs >>> 0, count >>> 0, file.data.subarray(descriptor.position, file.size));
+          descriptor.position += copied;
+        } else if (descriptor.type === 'dir') {
+          return Errno.ISDIR;
+        }
+        // Stdin is always at its end
+        this.view.setUint32(read >>> 0, copied, true);
+        return Errno.SUCCESS;
+      },
+      fd_pread: (fd: number, iovs: number, count: number, offset: bigint, read: number) => {
+        const { file } = this.file(fd);
+        const start = Math.min(Number(offset), file.size);
+        const copied = this.scatter(iovs >>> 0, count >>> 0, file.data.subarray(start, file.size));
+        this.view.setUint32(read >>> 0, copied, true);
+        return Errno.SUCCESS;
+      },
+      fd_seek: (fd: number, offset: bigint, whence: number, result: number) => {
+        const descriptor = this.file(fd);
+        const base =
+          whence === Whence.SET ? 0 : whence === Whence.CUR ? descriptor.position : descriptor.file.size;
+        const position = base + Number(offset);
+        if (whence > Whence.END || position < 0) {
+          return Errno.INVAL;
+        }
+        descriptor.position = position;
+        this.view.setBigUint64(result >>> 0, BigInt(position), true);
+        return Errno.SUCCESS;
+      },
+      fd_tell: (fd: number, result: number) => {
+        this.view.setBigUint64(result >>> 0, BigInt(this.file(fd).position), true);
+        return Errno.SUCCESS;
+      },
+      fd_close: (fd: number) => {
+        this.descriptor(fd);
+        this.fds.delete(fd);
+        return Errno.SUCCESS;
+      },
+      fd_renumber: (fd: number, to: number) => {
+        this.fds.set(to, this.descriptor(fd));
+        this.fds.delete(fd);
+        return Errno.SUCCESS;
+      },
+      fd_sync: (fd: number) => (this.descriptor(fd), Errno.SUCCESS),
+      fd_datasync: (fd: number) => (this.descriptor(fd), Errno.SUCCESS),
+      fd_advise: (fd: number) => (this.descriptor(fd), Errno.SUCCESS),
+      fd_allocate: (fd: number, offset: bigint, len: bigint) => {
+        const { file } = this.file(fd);
+        fs.resize(file, Math.max(file.size, Number(offset + len)));
+        return Errno.SUCCESS;
+      },
+
+      fd_fdstat_get: (fd: number, ptr: number) => {
+        const descriptor = this.descriptor(fd);
+        const view = this.view;
+        ptr >>>= 0;
+        const type =
+          descriptor.type === 'file'
+            ? FileType.REGULAR_FILE
+            : descriptor.type === 'dir'
+              ? FileType.DIRECTORY
+              : FileType.CHARACTER_DEVICE;
+        view.setUint8(ptr, type);
+        view.setUint16(ptr + 2, descriptor.type === 'file' && descriptor.append ? FDFLAG_APPEND : 0, true);
+        view.setBigUint64(ptr + 8, ALL_RIGHTS, true);
+        view.setBigUint64(ptr + 16, ALL_RIGHTS, true);
+        return Errno.SUCCESS;
+      },
+      fd_fdstat_set_flags: (fd: number, flags: number) => {
+        const descriptor = this.file(fd);
+        descriptor.append = (flags & FDFLAG_APPEND) !== 0;
+        return Errno.SUCCESS;
+      },
+      fd_filestat_get: (fd: number, ptr: number) => {
+        const descriptor = this.descriptor(fd);
+        if (descriptor.type === 'stdio') {
+          return this.writeFilestat(ptr, FileType.CHARACTER_DEVICE, 0);
+        }
+        return this.stat(descriptor.path, ptr);
+      },
+      fd_filestat_set_size: (fd: number, size: bigint) => {
+        fs.resize(this.file(fd).file, Number(size));
+        return Errno.SUCCESS;
+      },
+      fd_filestat_set_times: (fd: number) => (this.descriptor(fd), Errno.SUCCESS),
+
+      fd_prestat_get: (fd: number, ptr: number) => {
+        const descriptor = this.fds.get(fd);
+        if (descriptor?.type !== 'dir' || descriptor.preopen === undefined) {
+          return Errno.BADF;
+        }
+        const view = this.view;
+        view.setUint8(ptr >>> 0, 0);
+        view.setUint32((ptr >>> 0) + 4, this.encoder.encode(descriptor.preopen).length, true);
+        return Errno.SUCCESS;
+      },
+      fd_prestat_dir_name: (fd: number, ptr: number, len: number) => {
+        const descriptor = this.fds.get(fd);
+        if (descriptor?.type !== 'dir' || descriptor.preopen === undefined) {
+          return Errno.BADF;
+        }
+        this.bytes.set(this.encoder.encode(descriptor.preopen).subarray(0, len >>> 0), ptr >>> 0);
+        return Errno.SUCCESS;
+      },
+      fd_readdir: (fd: number, buf: number, len: number, cookie: bigint, used: number) => {
+        const descriptor = this.descriptor(fd);
+        if (descriptor.type !== 'dir') {
+          return Errno.NOTDIR;
+        }
+        const entries = ['.', '..', ...fs.readdir(descriptor.path)];
+        const out = new Uint8Array(len >>> 0);
+        let offset = 0;
+        for (let i = Number(cookie); i < entries.length && offset < out.length; i++) {
+          const name = this.encoder.encode(entries[i]);
+          const kind = i < 2 ? 'dir' : fs.kind(resolvePath(descriptor.path, entries[i]));
+          // Entries that don't fit are cut, the guest then reads on from the cookie
+          const entry = new Uint8Array(24 + name.length);
+          const entryView = new DataView(entry.buffer);
+          entryView.setBigUint64(0, BigInt(i + 1), true);
+          entryView.setBigUint64(8, BigInt(i + 1), true);
+          entryView.setUint32(16, name.length, true);
+          entryView.setUint8(20, kind === 'dir' ? FileType.DIRECTORY : FileType.REGULAR_FILE);
+          entry.set(name, 24);
+          const part = entry.subarray(0, out.length - offset);
+          out.set(part, offset);
+          offset += part.length;
+        }
+        this.bytes.set(out.subarray(0, offset), buf >>> 0);
+        this.view.setUint32(used >>> 0, offset, true);
+        return Errno.SUCCESS;
+      },
+
+      path_open: (
+        fd: number,
+        _dirflags: number,
+        ptr: number,
+        len: number,
+        oflags: number,
+        _rightsBase: bigint,
+        _rightsInheriting: bigint,
+        fdflags: number,
+        opened: number
+      ) => {
+        const path = this.dirPath(fd, ptr, len);
+        const kind = fs.kind(path);
+        if (kind && oflags & Oflags.CREAT && oflags & Oflags.EXCL) {
+          return Errno.EXIST;
+        }
+
+        let descriptor: Descriptor;
+        if (kind === 'dir' || oflags & Oflags.DIRECTORY) {
+          if (kind !== 'dir') {
+            return kind ? Errno.NOTDIR : Errno.NOENT;
+          }
+          descriptor = { type: 'dir', path };
+        } else {
+          const file = fs.open(path, (oflags & Oflags.CREAT) !== 0);
+          if (oflags & Oflags.TRUNC) {
+            fs.resize(file, 0);
+          }
+          descriptor = { type: 'file', path, file, position: 0, append: (fdflags & FDFLAG_APPEND) !== 0 };
+        }
+
+        const newFd = this.nextFd++;
+        this.fds.set(newFd, descriptor);
+        this.view.setUint32(opened >>> 0, newFd, true);
+        return Errno.SUCCESS;
+      },
+      path_filestat_get: (fd: number, _flags: number, ptr: number, len: number, buf: number) =>
+        this.stat(this.dirPath(fd, ptr, len), buf),
+      path_filestat_set_times: () => Errno.SUCCESS,
+      path_create_directory: (fd: number, ptr: number, len: number) => {
+        fs.mkdir(this.dirPath(fd, ptr, len));
+        return Errno.SUCCESS;
+      },
+      path_remove_directory: (fd: number, ptr: number, len: number) => {
+        fs.rmdir(this.dirPath(fd, ptr, len));
+        return Errno.SUCCESS;
+      },
+      path_unlink_file: (fd: number, ptr: number, len: number) => {
+        fs.unlink(this.dirPath(fd, ptr, len));
+        return Errno.SUCCESS;
+      },
+      path_rename: (fd: number, ptr: number, len: number, newFd: number, newPtr: number, newLen: number) => {
+        fs.rename(this.dirPath(fd, ptr, len), this.dirPath(newFd, newPtr, newLen));
+        return Errno.SUCCESS;
+      },
+      path_link: () => Errno.NOTSUP,
+      path_readlink: () => Errno.NOTSUP,
+      path_symlink: () => Errno.NOTSUP,
+
+      poll_oneoff: (input: number, output: number, count: number, events: number) => {
+        const view = this.view;
+        // The earliest clock ends the wait
+        let timeoutMs = Infinity;
+        let written = 0;
+        for (let i = 0; i < count; i++) {
+          const sub = (input >>> 0) + i * 48;
+          const tag = view.getUint8(sub + 8);
+          if (tag === 0) {
+            // Clock, relative unless the abstime flag is set
+            const clock = view.getUint32(sub + 16, true);
+            const timeout = Number(view.getBigUint64(sub + 24, true)) / 1_000_000;
+            const absolute = (view.getUint16(sub + 40, true) & 1) !== 0;
+            const current = clock === Clock.REALTIME ? performance.timeOrigin + performance.now() : performance.now();
+            timeoutMs = Math.min(timeoutMs, absolute ? timeout - current : timeout);
+          }
+          // Files and stdio are always ready
+          const event = (output >>> 0) + written * 32;
+          this.bytes.fill(0, event, event + 32);
+          view.setBigUint64(event, view.getBigUint64(sub, true), true);
+          view.setUint8(event + 10, tag);
+          written++;
+        }
+        if (timeoutMs > 0 && timeoutMs !== Infinity) {
+          sleep(timeoutMs);
+        }
+        view.setUint32(events >>> 0, written, true);
+        return Errno.SUCCESS;
+      },
+      sched_yield: () => Errno.SUCCESS,
+      proc_exit: (code: number): number => {
+        this.flush();
+        throw new WasiExit(code);
+      },
+    };
+  }
+}
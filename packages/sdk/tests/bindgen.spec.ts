@@ -0,0 +1,83 @@
+import { describe, it, expect, vi } from 'vitest';
+import { glueFunctions, initGlue, initGlueThreads, toPostable } from '../src/worker/bindgen';
+
+describe('wasm-bindgen glue', () => {
+  const module = {} as WebAssembly.Module;
+
+  describe('initGlue', () => {
+    it('should instantiate the compiled module through the default export', async () => {
+      const calls: unknown[] = [];
+      // Shaped like the glue of wasm-bindgen 0.2.93 and later
+      async function init(module_or_path: unknown) {
+        calls.push(module_or_path);
+      }
+      const glue = { default: init, greet: (name: string) => `Hello, ${name}!` };
+
+      expect(await initGlue(glue, module)).toBe(glue);
+      expect(calls).toEqual([{ module_or_path: module }]);
+    });
+
+    it('should pass the module itself to older glue', async () => {
+      const calls: unknown[] = [];
+      // Shaped like the glue of wasm-bindgen before 0.2.93
+      async function init(input: unknown) {
+        calls.push(input);
+      }
+
+      await initGlue({ default: init }, module);
+      expect(calls).toEqual([module]);
+    });
+
+    it('should reject glue without a default export', async () => {
+      await expect(initGlue({ greet: () => '' }, module)).rejects.toThrow(/--target web/);
+    });
+  });
+
+  describe('initGlueThreads', () => {
+    it('should start the wasm-bindgen-rayon thread pool', async () => {
+      const initThreadPool = vi.fn(async () => undefined);
+
+      await initGlueThreads({ default: () => {}, initThreadPool }, 4);
+      expect(initThreadPool).toHaveBeenCalledWith(4);
+    });
+
+    it('should reject glue built without wasm-bindgen-rayon', async () => {
+      await expect(initGlueThreads({ default: () => {} }, 4)).rejects.toThrow(/wasm-bindgen-rayon/);
+    });
+  });
+
+  describe('glueFunctions', () => {
+    it('should list the wrapped functions only', () => {
+      const glue = {
+        default: () => {},
+        initSync: () => {},
+        initThreadPool: () => {},
+        greet: () => {},
+        Point: class {},
+        VERSION: '1',
+      };
+
+      expect(glueFunctions(glue)).toEqual(['greet', 'Point']);
+    });
+  });
+
+  describe('toPostable', () => {
+    it('should turn struct wrappers into plain objects and free them', () => {
+      const free = vi.fn();
+      const point = { toJSON: () => ({ x: 1, y: 2 }), free };
+
+      expect(toPostable([point, 'label'])).toEqual([{ x: 1, y: 2 }, 'label']);
+      expect(free).toHaveBeenCalledTimes(1);
+    });
+
+    it('should leave other values as they are', () => {
+      const date = new Date(0);
+      const bytes = new Uint8Array([1, 2]);
+
+      expect(toPostable(date)).toBe(date);
+      expect(toPostable(bytes)).toBe(bytes);
+      expect(toPostable({ x: 1 })).toEqual({ x: 1 });
+      expect(toPostable(null)).toBeNull();
+    });
+  });
+});
@@ -1,5 +1,32 @@
 import { describe, it, expect, beforeEach, afterEach, vi } from 'vitest';
 import { WasmWorker } from '../src/bridge';
+import { HostStatus, createHostChannel, waitHostReply } from '../src/host';
+
+// A WasmWorker with its fields set as after load, posting to a mock Worker
+function createMockWorker(fields: Record<string, unknown> = {}): any {
+  return Object.assign(Object.create(WasmWorker.prototype), {
+    worker: { postMessage: vi.fn(), terminate: vi.fn() },
+    helpers: [],
+    pendingRequests: new Map(),
+    streamingRequests: new Map(),
+    initialized: true,
+    nextSeq: 1,
+    cancelFlags: null,
+    loadOptions: { moduleUrl: '/test.wasm' },
+    module: null,
+    memory: null,
+    regions: new Map(),
+    backlog: null,
+    listeners: { log: new Set() },
+    ...fields,
+  });
+}
+
+// Answer the last message posted to the worker
+function reply(mockWorker: any, message: object) {
+  const [posted] = mockWorker.worker.postMessage.mock.calls.at(-1);
+  mockWorker.handleMessage({ data: { id: posted.id, ...message } } as MessageEvent);
+}
 
 describe('WasmWorker', () => {
   let worker: WasmWorker | null = null;
@@ -38,18 +65,399 @@ describe('WasmWorker', () => {
     });
   });
 
+  describe('log', () => {
+    function receive(mockWorker: any, data: unknown) {
+      mockWorker.handleMessage({ data });
+    }
+
+    it('should deliver guest log records to listeners until removed', () => {
+      const mockWorker = createMockWorker();
+      const listener = vi.fn();
+      const remove = mockWorker.on('log', listener);
+
+      receive(mockWorker, { type: 'log', level: 'warn', message: 'low memory' });
+      remove();
+      receive(mockWorker, { type: 'log', level: 'info', message: 'ignored' });
+
+      expect(listener).toHaveBeenCalledTimes(1);
+      expect(listener).toHaveBeenCalledWith({ level: 'warn', message: 'low memory' });
+    });
+
+    it('should write to the console only when enabled', () => {
+      const debug = vi.spyOn(console, 'debug').mockImplementation(() => {});
+
+      receive(createMockWorker(), { type: 'log', level: 'trace', message: 'quiet' });
+      expect(debug).not.toHaveBeenCalled();
+
+      const loud = createMockWorker({ loadOptions: { moduleUrl: '/test.wasm', logToConsole: true } });
+      receive(loud, { type: 'log', level: 'trace', message: 'loud' });
+      expect(debug).toHaveBeenCalledWith('loud');
+
+      debug.mockRestore();
+    });
+  });
+
+  describe('host functions', () => {
+    function hostWorker(init: Record<string, unknown>) {
+      return createMockWorker({ loadOptions: { moduleUrl: '/test.wasm', init } });
+    }
+
+    // Runs the host call like the worker would, without blocking on the reply
+    async function hostCall(mockWorker: WasmWorker, name: string, args: unknown[]) {
+      const channel = createHostChannel(64);
+      await (mockWorker as any).runHostFunction({ type: 'host_call', name, args, channel });
+      return waitHostReply(channel, () => {});
+    }
+
+    it('should reply with the JSON encoded result of async functions', async () => {
+      const fetchConfig = vi.fn(async (key: string) => (key === 'scale' ? 2.5 : null));
+      const mockWorker = hostWorker({ fetch_config: fetchConfig });
+
+      expect(await hostCall(mockWorker, 'fetch_config', ['scale'])).toEqual({
+        status: HostStatus.Ok,
+        reply: '2.5',
+      });
+      expect(fetchConfig).toHaveBeenCalledWith('scale');
+      expect(await hostCall(mockWorker, 'fetch_config', ['other'])).toEqual({
+        status: HostStatus.Ok,
+        reply: 'null',
+      });
+    });
+
+    it('should reply with the error message when the function throws', async () => {
+      const mockWorker = hostWorker({
+        fail: () => {
+          throw new Error('offline');
+        },
+        limit: 10,
+      });
+
+      expect(await hostCall(mockWorker, 'fail', [])).toEqual({
+        status: HostStatus.Error,
+        reply: 'offline',
+      });
+      expect(await hostCall(mockWorker, 'limit', [])).toEqual({
+        status: HostStatus.Error,
+        reply: 'No host function named "limit"',
+      });
+    });
+  });
+
+  describe('fs', () => {
+    it('should transfer a copy of the bytes it writes', async () => {
+      const mockWorker = createMockWorker();
+      const bytes = new Uint8Array([1, 2, 3]).buffer;
+
+      const written = mockWorker.fs.write('input.png', bytes);
+      reply(mockWorker, { type: 'result' });
+
+      await expect(written).resolves.toBeUndefined();
+      expect(mockWorker.worker.postMessage).toHaveBeenCalledWith(
+        expect.objectContaining({ type: 'fs_write', path: 'input.png', bytes }),
+        [bytes]
+      );
+      expect(mockWorker.worker.postMessage.mock.calls[0][0].bytes).not.toBe(bytes);
+    });
+
+    it('should move the bytes it writes when asked to', async () => {
+      const mockWorker = createMockWorker();
+      const bytes = new Uint8Array([1, 2, 3]).buffer;
+
+      const written = mockWorker.fs.write('input.png', bytes, [bytes]);
+      reply(mockWorker, { type: 'result' });
+
+      await expect(written).resolves.toBeUndefined();
+      const [message, transfer] = mockWorker.worker.postMessage.mock.calls[0];
+      expect(message.bytes).toBe(bytes);
+      expect(transfer).toEqual([bytes]);
+    });
+
+    it('should resolve reads with the file contents', async () => {
+      const mockWorker = createMockWorker();
+
+      const read = mockWorker.fs.read('/out/result.png');
+      reply(mockWorker, { type: 'result', value: new Uint8Array([4, 5]) });
+
+      expect(await read).toEqual(new Uint8Array([4, 5]));
+    });
+
+    it('should reject with FS_ERROR for missing files', async () => {
+      const mockWorker = createMockWorker();
+
+      const read = mockWorker.fs.read('missing');
+      reply(mockWorker, {
+        type: 'error',
+        error: { code: 'FS_ERROR', message: '/missing does not exist', details: { path: '/missing', errno: 44 } },
+      });
+
+      await expect(read).rejects.toMatchObject({ code: 'FS_ERROR', details: { errno: 44 } });
+    });
+  });
+
+  describe('shared regions', () => {
+    it('should view the region in the module memory', async () => {
+      const memory = new WebAssembly.Memory({ initial: 1 });
+      const mockWorker = createMockWorker({ memory });
+
+      const allocated = mockWorker.allocShared(64, 'frame');
+      reply(mockWorker, { type: 'result', value: { offset: 1024 } });
+      const region = await allocated;
+
+      expect(mockWorker.worker.postMessage).toHaveBeenCalledWith(
+        expect.objectContaining({ type: 'shared_alloc', byteLength: 64 }),
+        []
+      );
+      expect(region).toMatchObject({ name: 'frame', offset: 1024, byteLength: 64 });
+      region.bytes[0] = 7;
+      expect(new Uint8Array(memory.buffer)[1024]).toBe(7);
+      expect(mockWorker.sharedRegion('frame')).toBe(region);
+    });
+
+    it('should free regions by name', async () => {
+      const mockWorker = createMockWorker({ memory: new WebAssembly.Memory({ initial: 1 }) });
+      mockWorker.regions.set('frame', { name: 'frame', offset: 1024 });
+
+      const freed = mockWorker.freeShared('frame');
+      reply(mockWorker, { type: 'result' });
+
+      await expect(freed).resolves.toBeUndefined();
+      expect(mockWorker.worker.postMessage).toHaveBeenCalledWith(
+        expect.objectContaining({ type: 'shared_free', offset: 1024 }),
+        []
+      );
+      expect(mockWorker.sharedRegion('frame')).toBeUndefined();
+    });
+
+    it('should reject duplicate names', async () => {
+      const mockWorker = createMockWorker({ memory: new WebAssembly.Memory({ initial: 1 }) });
+      mockWorker.regions.set('frame', {});
+
+      await expect(mockWorker.allocShared(64, 'frame')).rejects.toThrow('already exists');
+      expect(mockWorker.worker.postMessage).not.toHaveBeenCalled();
+    });
+
+    it('should need a module with shared memory', async () => {
+      const mockWorker = createMockWorker();
+
+      await expect(mockWorker.allocShared(64)).rejects.toThrow('shared memory');
+      await expect(mockWorker.allocShared(-1)).rejects.toThrow(TypeError);
+    });
+  });
+
+  describe('stream', () => {
+    async function collect(iterable: AsyncIterable<unknown>, chunks: unknown[]) {
+      for await (const chunk of iterable) {
+        chunks.push(chunk);
+      }
+    }
+
+    it('should yield every chunk in order and finish on close', async () => {
+      const mockWorker = createMockWorker();
+      const chunks: unknown[] = [];
+      const done = collect(mockWorker.stream('fib_sequence', { n: 5 }), chunks);
+
+      const opened = mockWorker.worker.postMessage.mock.calls[0][0];
+      expect(opened).toMatchObject({ type: 'stream_open', fn: 'fib_sequence', payload: { n: 5 } });
+
+      for (const value of [0n, 1n, 1n, 2n, 3n]) {
+        mockWorker.handleMessage({ data: { id: opened.id, type: 'stream_chunk', value } });
+      }
+      mockWorker.handleMessage({ data: { id: opened.id, type: 'stream_close' } });
+      await done;
+
+      expect(chunks).toEqual([0n, 1n, 1n, 2n, 3n]);
+      expect(mockWorker.streamingRequests.size).toBe(0);
+    });
+
+    it('should throw errors after the chunks received before them', async () => {
+      const mockWorker = createMockWorker();
+      const chunks: unknown[] = [];
+      const done = collect(mockWorker.stream('fib_sequence', { n: 5 }), chunks);
+      const { id } = mockWorker.worker.postMessage.mock.calls[0][0];
+
+      mockWorker.handleMessage({ data: { id, type: 'stream_chunk', value: 1 } });
+      mockWorker.handleMessage({
+        data: { id, type: 'error', error: { code: 'WASM_TRAP', message: 'unreachable' } },
+      });
+
+      await expect(done).rejects.toThrow('unreachable');
+      expect(chunks).toEqual([1]);
+      expect(mockWorker.streamingRequests.size).toBe(0);
+    });
+
+    it('should cancel the export when the consumer stops early', async () => {
+      const mockWorker = createMockWorker();
+      const iterator = mockWorker.stream('fib_sequence', { n: 50 })[Symbol.asyncIterator]();
+      const next = iterator.next();
+      const { id, seq } = mockWorker.worker.postMessage.mock.calls[0][0];
+
+      mockWorker.handleMessage({ data: { id, type: 'stream_chunk', value: 0n } });
+      await next;
+      await iterator.return();
+
+      expect(mockWorker.worker.postMessage).toHaveBeenLastCalledWith({ id, type: 'cancel', seq }, []);
+      expect(mockWorker.streamingRequests.size).toBe(0);
+    });
+
+    it('should throw CANCELLED when the signal aborts', async () => {
+      const mockWorker = createMockWorker();
+      const controller = new AbortController();
+      const done = collect(
+        mockWorker.stream('fib_sequence', { n: 50 }, { signal: controller.signal }),
+        []
+      );
+      const { id, seq } = mockWorker.worker.postMessage.mock.calls[0][0];
+
+      controller.abort();
+
+      await expect(done).rejects.toMatchObject({ code: 'CANCELLED' });
+      expect(mockWorker.worker.postMessage).toHaveBeenLastCalledWith({ id, type: 'cancel', seq }, []);
+    });
+  });
+
+  describe('cancellation', () => {
+    it('should reject without calling when the signal is already aborted', async () => {
+      const mockWorker = createMockWorker({ cancelFlags: new Int32Array(4) });
+      const controller = new AbortController();
+      controller.abort();
+
+      await expect(
+        mockWorker.call('fib', 45, { signal: controller.signal })
+      ).rejects.toMatchObject({ code: 'CANCELLED' });
+      expect(mockWorker.worker.postMessage).not.toHaveBeenCalled();
+    });
+
+    it('should reject in-flight calls and flag them for the worker', async () => {
+      const mockWorker = createMockWorker({ cancelFlags: new Int32Array(4) });
+      const controller = new AbortController();
+      const result = mockWorker.call('fib', 45, { signal: controller.signal });
+      const { id, seq } = mockWorker.worker.postMessage.mock.calls[0][0];
+
+      controller.abort('user gave up');
+
+      await expect(result).rejects.toMatchObject({
+        code: 'CANCELLED',
+        details: { function: 'fib', reason: 'user gave up' },
+      });
+      expect(mockWorker.pendingRequests.size).toBe(0);
+      expect(mockWorker.cancelFlags[seq % 4]).toBe(seq);
+      expect(mockWorker.worker.postMessage).toHaveBeenLastCalledWith({ id, type: 'cancel', seq }, []);
+    });
+
+    it('should ignore the signal once the call has settled', async () => {
+      const mockWorker = createMockWorker({ cancelFlags: new Int32Array(4) });
+      const controller = new AbortController();
+      const result = mockWorker.call('fib', 10, { signal: controller.signal });
+      const { id } = mockWorker.worker.postMessage.mock.calls[0][0];
+
+      mockWorker.handleMessage({ data: { id, type: 'result', value: 55 } });
+      controller.abort();
+
+      await expect(result).resolves.toBe(55);
+      expect(mockWorker.worker.postMessage).toHaveBeenCalledTimes(1);
+    });
+  });
+
+  describe('timeouts', () => {
+    function timedWorker() {
+      return createMockWorker({
+        cancelFlags: new Int32Array(4),
+        loadOptions: { moduleUrl: '/test.wasm', timeoutMs: 100 },
+      });
+    }
+
+    beforeEach(() => {
+      vi.useFakeTimers();
+    });
+
+    afterEach(() => {
+      vi.useRealTimers();
+    });
+
+    it('should only cancel a queued call that times out', async () => {
+      const mockWorker = timedWorker();
+      const running = mockWorker.call('fib', 45, { timeoutMs: 0 });
+      const queued = mockWorker.call('fib', 10);
+      const { id, seq } = mockWorker.worker.postMessage.mock.calls[1][0];
+
+      vi.advanceTimersByTime(100);
+
+      await expect(queued).rejects.toMatchObject({
+        code: 'TIMEOUT',
+        details: { function: 'fib', timeoutMs: 100 },
+      });
+      expect(mockWorker.worker.terminate).not.toHaveBeenCalled();
+      expect(mockWorker.worker.postMessage).toHaveBeenLastCalledWith({ id, type: 'cancel', seq }, []);
+      expect(mockWorker.pendingRequests.size).toBe(1);
+      void running;
+    });
+
+    it('should respawn the worker when the running call times out', async () => {
+      const mockWorker = timedWorker();
+      const stuck = mockWorker.worker;
+      const respawned = { postMessage: vi.fn() };
+      mockWorker.module = {};
+      mockWorker.spawn = vi.fn(() => {
+        mockWorker.worker = respawned;
+        return Promise.resolve();
+      });
+
+      const running = mockWorker.call('fib', 45, { timeoutMs: 50 });
+      const replayed = mockWorker.call('fib', 10, { timeoutMs: 0 });
+      const transferred = mockWorker.call('sum', new ArrayBuffer(8), {
+        timeoutMs: 0,
+        transfer: [new ArrayBuffer(8)],
+      });
+      const replayedMsg = stuck.postMessage.mock.calls[1][0];
+
+      vi.advanceTimersByTime(50);
+
+      await expect(running).rejects.toMatchObject({ code: 'TIMEOUT' });
+      await expect(transferred).rejects.toMatchObject({
+        code: 'TIMEOUT',
+        details: { function: 'fib', restarted: true },
+      });
+      expect(stuck.terminate).toHaveBeenCalled();
+      expect(mockWorker.spawn).toHaveBeenCalledWith(mockWorker.loadOptions, mockWorker.module);
+
+      await Promise.resolve();
+      expect(respawned.postMessage).toHaveBeenCalledWith(replayedMsg, []);
+
+      mockWorker.handleMessage({ data: { id: replayedMsg.id, type: 'result', value: 55 } });
+      await expect(replayed).resolves.toBe(55);
+    });
+
+    it('should not time out a call that settles in time', async () => {
+      const mockWorker = timedWorker();
+      const result = mockWorker.call('fib', 10);
+      const { id } = mockWorker.worker.postMessage.mock.calls[0][0];
+
+      mockWorker.handleMessage({ data: { id, type: 'result', value: 55 } });
+      vi.advanceTimersByTime(100);
+
+      await expect(result).resolves.toBe(55);
+      expect(mockWorker.worker.terminate).not.toHaveBeenCalled();
+    });
+  });
+
   describe('terminate', () => {
     it('should clean up resources', () => {
       const mockWorker = Object.create(WasmWorker.prototype);
       mockWorker.worker = {
         terminate: vi.fn(),
       };
+      const helper = { terminate: vi.fn() };
+      mockWorker.helpers = [helper];
+      mockWorker.regions = new Map([['frame', {}]]);
       mockWorker.pendingRequests = new Map();
       mockWorker.streamingRequests = new Map();
 
       mockWorker.terminate();
 
       expect(mockWorker.worker).toBeNull();
+      expect(helper.terminate).toHaveBeenCalled();
+      expect(mockWorker.helpers).toEqual([]);
+      expect(mockWorker.regions.size).toBe(0);
       expect(mockWorker.pendingRequests.size).toBe(0);
       expect(mockWorker.streamingRequests.size).toBe(0);
     });
@@ -0,0 +1,149 @@
+import { describe, it, expect, afterEach, vi } from 'vitest';
+import { compileCached, fetchCached, sha256, type CachedModule, type ModuleStore } from '../src/worker/cache';
+
+function memoryStore(): ModuleStore & { records: Map<string, CachedModule> } {
+  const records = new Map<string, CachedModule>();
+  return {
+    records,
+    get: async (key) => records.get(key),
+    put: async (key, record) => {
+      records.set(key, record);
+    },
+  };
+}
+
+function bytesOf(...values: number[]): ArrayBuffer {
+  return new Uint8Array(values).buffer;
+}
+
+async function integrityOf(bytes: ArrayBuffer): Promise<string> {
+  const digest = new Uint8Array(await crypto.subtle.digest('SHA-256', bytes));
+  return `sha256-${btoa(String.fromCharCode(...digest))}`;
+}
+
+describe('Module cache', () => {
+  afterEach(() => {
+    vi.restoreAllMocks();
+    vi.unstubAllGlobals();
+  });
+
+  describe('sha256', () => {
+    it('should hex encode the digest', async () => {
+      expect(await sha256(new TextEncoder().encode('abc').buffer)).toBe(
+        'ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad'
+      );
+    });
+  });
+
+  describe('compileCached', () => {
+    it('should compile unchanged content only once', async () => {
+      const module = {} as WebAssembly.Module;
+      const compile = vi.spyOn(WebAssembly, 'compile').mockResolvedValue(module);
+      const store = memoryStore();
+
+      expect(await compileCached(store, 'module', bytesOf(1, 2), '/a.wasm', '"v1"')).toBe(module);
+      expect(await compileCached(store, 'module', bytesOf(1, 2), '/a.wasm', '"v1"')).toBe(module);
+
+      expect(compile).toHaveBeenCalledTimes(1);
+      expect(store.records.get('/a.wasm')).toMatchObject({ etag: '"v1"', module });
+    });
+
+    it('should recompile and replace changed content', async () => {
+      const compile = vi.spyOn(WebAssembly, 'compile').mockResolvedValue({} as WebAssembly.Module);
+      const store = memoryStore();
+
+      await compileCached(store, 'bytes', bytesOf(1), '/a.wasm');
+      await compileCached(store, 'bytes', bytesOf(2), '/a.wasm');
+
+      expect(compile).toHaveBeenCalledTimes(2);
+      expect(new Uint8Array(store.records.get('/a.wasm')!.bytes)).toEqual(new Uint8Array([2]));
+    });
+
+    it('should key bytes by their hash and only keep modules in module mode', async () => {
+      vi.spyOn(WebAssembly, 'compile').mockResolvedValue({} as WebAssembly.Module);
+      const store = memoryStore();
+
+      await compileCached(store, 'bytes', bytesOf(1, 2, 3));
+
+      const [[key, record]] = store.records;
+      expect(key).toBe(`sha256:${record.hash}`);
+      expect(record.module).toBeUndefined();
+    });
+
+    it('should keep the bytes when the module cannot be stored', async () => {
+      vi.spyOn(WebAssembly, 'compile').mockResolvedValue({} as WebAssembly.Module);
+      const store = memoryStore();
+      const put = store.put;
+      store.put = async (key, record) => {
+        if (record.module) {
+          throw new DOMException('could not be cloned', 'DataCloneError');
+        }
+        return put(key, record);
+      };
+
+      await compileCached(store, 'module', bytesOf(1), '/a.wasm');
+
+      expect(store.records.get('/a.wasm')).toMatchObject({ module: undefined });
+    });
+  });
+
+  describe('fetchCached', () => {
+    function stubFetch(...responses: Response[]) {
+      const fetch = vi.fn(async () => responses.shift()!);
+      vi.stubGlobal('fetch', fetch);
+      return fetch;
+    }
+
+    it('should download modules without an ETag again', async () => {
+      // Responses first, undici compiles a module of its own on first use
+      const fetch = stubFetch(new Response(bytesOf(1, 2)), new Response(bytesOf(1, 2)));
+      vi.spyOn(WebAssembly, 'compile').mockResolvedValue({} as WebAssembly.Module);
+      const store = memoryStore();
+
+      await fetchCached(store, 'bytes', '/a.wasm');
+      const loaded = await fetchCached(store, 'bytes', '/a.wasm');
+
+      expect(fetch).toHaveBeenCalledTimes(2);
+      expect(fetch).toHaveBeenLastCalledWith('/a.wasm', undefined);
+      expect(store.records.get('/a.wasm')).toMatchObject({ etag: null });
+      expect(new Uint8Array((loaded as { bytes: ArrayBuffer }).bytes)).toEqual(new Uint8Array([1, 2]));
+    });
+
+    it('should revalidate with the stored ETag', async () => {
+      const fetch = stubFetch(
+        new Response(bytesOf(1), { headers: { ETag: '"v1"' } }),
+        new Response(null, { status: 304 })
+      );
+      vi.spyOn(WebAssembly, 'compile').mockResolvedValue({} as WebAssembly.Module);
+      const store = memoryStore();
+
+      await fetchCached(store, 'bytes', '/a.wasm');
+      const loaded = await fetchCached(store, 'bytes', '/a.wasm');
+
+      expect(fetch).toHaveBeenLastCalledWith('/a.wasm', { headers: { 'If-None-Match': '"v1"' } });
+      expect(new Uint8Array((loaded as { bytes: ArrayBuffer }).bytes)).toEqual(new Uint8Array([1]));
+    });
+
+    it('should not request modules stored under their integrity', async () => {
+      const fetch = stubFetch(new Response(bytesOf(1, 2)));
+      vi.spyOn(WebAssembly, 'compile').mockResolvedValue({} as WebAssembly.Module);
+      const store = memoryStore();
+      const integrity = await integrityOf(bytesOf(1, 2));
+
+      await fetchCached(store, 'bytes', '/a.wasm', integrity);
+      await fetchCached(store, 'bytes', '/a.wasm', integrity);
+
+      expect(fetch).toHaveBeenCalledTimes(1);
+      expect(store.records.has(`sri:${integrity}`)).toBe(true);
+    });
+
+    it('should return failed responses', async () => {
+      stubFetch(new Response(null, { status: 404 }));
+
+      const loaded = await fetchCached(memoryStore(), 'bytes', '/missing.wasm');
+
+      expect(loaded).toBeInstanceOf(Response);
+      expect((loaded as Response).status).toBe(404);
+    });
+  });
+});
@@ -0,0 +1,70 @@
+import { describe, it, expect } from 'vitest';
+import { encodePayload, decodeValue } from '../src/worker/codec';
+import { ResultKind } from '../src/worker/memory';
+
+describe('Payload codecs', () => {
+  describe('json', () => {
+    it('should encode payloads as UTF-8 JSON', () => {
+      const bytes = encodePayload('json', { points: [{ x: 1, y: 2 }] });
+
+      expect(new TextDecoder().decode(bytes)).toBe('{"points":[{"x":1,"y":2}]}');
+    });
+
+    it('should encode a missing payload as null', () => {
+      expect(new TextDecoder().decode(encodePayload('json', undefined))).toBe('null');
+    });
+
+    it('should reject values JSON cannot represent', () => {
+      expect(() => encodePayload('json', { big: 1n })).toThrow();
+    });
+
+    it('should decode JSON results', () => {
+      const bytes = new TextEncoder().encode('{"min":{"x":-1,"y":0}}');
+
+      expect(decodeValue(ResultKind.Json, bytes)).toEqual({ min: { x: -1, y: 0 } });
+    });
+  });
+
+  describe('msgpack', () => {
+    it('should encode payloads as MessagePack', () => {
+      const bytes = encodePayload('msgpack', { x: 1 });
+
+      expect(Array.from(bytes)).toEqual([0x81, 0xa1, 0x78, 0x01]);
+    });
+
+    it('should decode MessagePack results', () => {
+      const bytes = encodePayload('msgpack', { length: 2.5, id: 1n << 60n });
+
+      expect(decodeValue(ResultKind.MsgPack, bytes)).toEqual({ length: 2.5, id: 1n << 60n });
+    });
+  });
+
+  it('should reject unknown codecs', () => {
+    expect(() => encodePayload('yaml' as any, {})).toThrow('Unknown codec "yaml"');
+  });
+
+  it('should decode strings and bytes', () => {
+    const bytes = new TextEncoder().encode('hello');
+
+    expect(decodeValue(ResultKind.String, bytes)).toBe('hello');
+    expect(decodeValue(ResultKind.Bytes, bytes)).toBe(bytes);
+  });
+
+  it('should decode little-endian scalars', () => {
+    const bytes = (write: (view: DataView) => void, len: number) => {
+      const buffer = new Uint8Array(len);
+      write(new DataView(buffer.buffer));
+      return buffer;
+    };
+
+    expect(decodeValue(ResultKind.I32, bytes((v) => v.setInt32(0, -7, true), 4))).toBe(-7);
+    expect(decodeValue(ResultKind.U32, bytes((v) => v.setUint32(0, 4e9, true), 4))).toBe(4e9);
+    expect(decodeValue(ResultKind.U64, bytes((v) => v.setBigUint64(0, 1n << 63n, true), 8))).toBe(1n << 63n);
+    expect(decodeValue(ResultKind.F64, bytes((v) => v.setFloat64(0, 0.25, true), 8))).toBe(0.25);
+    expect(decodeValue(ResultKind.Bool, new Uint8Array([1]))).toBe(true);
+  });
+
+  it('should reject unknown encodings', () => {
+    expect(() => decodeValue(42, new Uint8Array())).toThrow('Unknown result encoding 42');
+  });
+});
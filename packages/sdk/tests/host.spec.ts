@@ -0,0 +1,37 @@
+import { describe, it, expect } from 'vitest';
+import { HostStatus, createHostChannel, writeHostReply, waitHostReply } from '../src/host';
+
+describe('Host channel', () => {
+  it('should return a reply written before the wait', () => {
+    const channel = createHostChannel(64);
+
+    const result = waitHostReply(channel, () => {
+      writeHostReply(channel, HostStatus.Ok, '{"scale":2}');
+    });
+
+    expect(result).toEqual({ status: HostStatus.Ok, reply: '{"scale":2}' });
+  });
+
+  it('should reset the status before sending', () => {
+    const channel = createHostChannel(64);
+    writeHostReply(channel, HostStatus.Error, 'stale');
+
+    let status: number | null = null;
+    waitHostReply(channel, () => {
+      status = Atomics.load(new Int32Array(channel, 0, 1), 0);
+      writeHostReply(channel, HostStatus.Ok, 'null');
+    });
+
+    expect(status).toBe(HostStatus.Pending);
+  });
+
+  it('should turn replies that do not fit into errors', () => {
+    const channel = createHostChannel(4);
+
+    const result = waitHostReply(channel, () => {
+      writeHostReply(channel, HostStatus.Ok, '"too long"');
+    });
+
+    expect(result.status).toBe(HostStatus.Error);
+  });
+});
@@ -0,0 +1,44 @@
+import { describe, it, expect } from 'vitest';
+import { IntegrityError, checkIntegrity, parseIntegrity } from '../src/integrity';
+
+const abc = new TextEncoder().encode('abc').buffer;
+const SHA256_ABC = 'sha256-ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=';
+const SHA384_ABC = 'sha384-ywB1P0WjXou1oD1pmsZQBycsMqsO3tFjGotgWkP/W+2AhgcroefMI1i67KE0yCWn';
+
+describe('Integrity', () => {
+  describe('parseIntegrity', () => {
+    it('should keep the digests of the strongest algorithm', () => {
+      expect(parseIntegrity(`${SHA384_ABC} ${SHA256_ABC} md5-abc`)).toEqual({
+        algorithm: 'sha384',
+        digests: [SHA384_ABC],
+      });
+    });
+
+    it('should ignore options', () => {
+      expect(parseIntegrity(`${SHA256_ABC}?ct=application/wasm`).digests).toEqual([SHA256_ABC]);
+    });
+
+    it('should reject strings without a supported digest', () => {
+      expect(() => parseIntegrity('md5-abc')).toThrow(TypeError);
+      expect(() => parseIntegrity('sha384-')).toThrow(TypeError);
+    });
+  });
+
+  describe('checkIntegrity', () => {
+    it('should accept matching bytes', async () => {
+      await expect(checkIntegrity(abc, SHA256_ABC)).resolves.toBeUndefined();
+      await expect(checkIntegrity(abc, `sha384-other ${SHA384_ABC}`)).resolves.toBeUndefined();
+    });
+
+    it('should report the actual digest of mismatching bytes', async () => {
+      const error = await checkIntegrity(new TextEncoder().encode('abd').buffer, SHA256_ABC).catch(
+        (error) => error
+      );
+
+      expect(error).toBeInstanceOf(IntegrityError);
+      expect(error.details.expected).toEqual([SHA256_ABC]);
+      expect(error.details.actual).toMatch(/^sha256-/);
+      expect(error.details.actual).not.toBe(SHA256_ABC);
+    });
+  });
+});
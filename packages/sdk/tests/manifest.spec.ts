@@ -0,0 +1,73 @@
+import { describe, it, expect } from 'vitest';
+import { readManifest, MANIFEST_SECTION } from '../src/worker/manifest';
+
+const encoder = new TextEncoder();
+
+/**
+ * Build an empty module with the given custom sections
+ */
+function moduleWith(sections: Array<[string, string]>): WebAssembly.Module {
+  const bytes = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+  for (const [name, content] of sections) {
+    const nameBytes = encoder.encode(name);
+    const body = [...leb128(nameBytes.length), ...nameBytes, ...encoder.encode(content)];
+    bytes.push(0x00, ...leb128(body.length), ...body);
+  }
+  return new WebAssembly.Module(new Uint8Array(bytes));
+}
+
+function leb128(value: number): number[] {
+  const out: number[] = [];
+  do {
+    let byte = value & 0x7f;
+    value >>>= 7;
+    if (value !== 0) byte |= 0x80;
+    out.push(byte);
+  } while (value !== 0);
+  return out;
+}
+
+const add = {
+  name: 'add',
+  params: [
+    { name: 'a', type: 'i32' },
+    { name: 'b', type: 'i32' },
+  ],
+  returns: 'i32',
+  codec: null,
+  stream: false,
+  docs: 'Add two 32-bit integers',
+};
+
+const bounds = {
+  name: 'bounds',
+  params: [{ name: 'points', type: 'Vec<Point>' }],
+  returns: 'Bounds',
+  codec: 'json',
+  stream: false,
+  docs: '',
+};
+
+describe('readManifest', () => {
+  it('should return no exports without a manifest section', () => {
+    expect(readManifest(moduleWith([]))).toEqual([]);
+  });
+
+  it('should read one record per line', () => {
+    const module = moduleWith([
+      [MANIFEST_SECTION, `${JSON.stringify(add)}\n${JSON.stringify(bounds)}\n`],
+    ]);
+
+    expect(readManifest(module)).toEqual([add, bounds]);
+  });
+
+  it('should combine sections that were not merged', () => {
+    const module = moduleWith([
+      [MANIFEST_SECTION, `${JSON.stringify(add)}\n`],
+      ['other', 'ignored'],
+      [MANIFEST_SECTION, `${JSON.stringify(bounds)}\n`],
+    ]);
+
+    expect(readManifest(module).map((entry) => entry.name)).toEqual(['add', 'bounds']);
+  });
+});
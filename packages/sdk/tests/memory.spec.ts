@@ -0,0 +1,186 @@
+import { describe, it, expect, vi } from 'vitest';
+import {
+  getAllocator,
+  isBinary,
+  toBytes,
+  copyIn,
+  allocRegion,
+  takeResult,
+  takePanic,
+  readString,
+  ResultKind,
+} from '../src/worker/memory';
+
+describe('Guest memory helpers', () => {
+  describe('getAllocator', () => {
+    it('should return null when allocator exports are missing', () => {
+      expect(getAllocator({})).toBeNull();
+      expect(getAllocator({ ww_alloc: () => 8 })).toBeNull();
+    });
+
+    it('should wrap ww_alloc and ww_free', () => {
+      const free = vi.fn();
+      const allocator = getAllocator({ ww_alloc: () => 16, ww_free: free });
+
+      expect(allocator).not.toBeNull();
+      expect(allocator!.alloc(4)).toBe(16);
+      allocator!.free(16, 4);
+      expect(free).toHaveBeenCalledWith(16, 4);
+    });
+
+    it('should treat pointers as unsigned', () => {
+      const allocator = getAllocator({ ww_alloc: () => -2147483648, ww_free: () => {} });
+
+      expect(allocator!.alloc(1)).toBe(2147483648);
+    });
+  });
+
+  describe('isBinary', () => {
+    it('should accept buffers and typed arrays', () => {
+      expect(isBinary(new ArrayBuffer(4))).toBe(true);
+      expect(isBinary(new Uint8Array(4))).toBe(true);
+      expect(isBinary(new Float64Array(2))).toBe(true);
+      expect(isBinary(new DataView(new ArrayBuffer(4)))).toBe(true);
+    });
+
+    it('should reject other values', () => {
+      expect(isBinary([1, 2, 3])).toBe(false);
+      expect(isBinary({ a: 1 })).toBe(false);
+      expect(isBinary('bytes')).toBe(false);
+      expect(isBinary(42)).toBe(false);
+    });
+  });
+
+  describe('toBytes', () => {
+    it('should respect typed array offsets', () => {
+      const source = new Uint8Array([0, 1, 2, 3, 4, 5]);
+      const bytes = toBytes(source.subarray(2, 4));
+
+      expect(Array.from(bytes)).toEqual([2, 3]);
+    });
+
+    it('should view non-byte typed arrays as raw bytes', () => {
+      const bytes = toBytes(new Uint32Array([1]));
+
+      expect(bytes.byteLength).toBe(4);
+    });
+  });
+
+  describe('copyIn', () => {
+    it('should copy bytes to the allocated pointer', () => {
+      const memory = new WebAssembly.Memory({ initial: 1 });
+      const allocator = { alloc: vi.fn(() => 64), free: vi.fn() };
+
+      const buffer = copyIn(memory, allocator, new Uint8Array([9, 8, 7]));
+
+      expect(buffer).toEqual({ ptr: 64, len: 3 });
+      expect(allocator.alloc).toHaveBeenCalledWith(3);
+      expect(Array.from(new Uint8Array(memory.buffer, 64, 3))).toEqual([9, 8, 7]);
+    });
+
+    it('should fail when the allocator returns null', () => {
+      const memory = new WebAssembly.Memory({ initial: 1 });
+      const allocator = { alloc: () => 0, free: () => {} };
+
+      expect(() => copyIn(memory, allocator, new Uint8Array([1]))).toThrow('ww_alloc failed');
+    });
+  });
+
+  describe('allocRegion', () => {
+    it('should align the region within a padded allocation', () => {
+      const allocator = { alloc: vi.fn(() => 65), free: vi.fn() };
+
+      const region = allocRegion(allocator, 100);
+
+      expect(allocator.alloc).toHaveBeenCalledWith(115);
+      expect(region).toEqual({ buffer: { ptr: 65, len: 115 }, offset: 80 });
+    });
+
+    it('should fail when the allocator returns null', () => {
+      const allocator = { alloc: () => 0, free: () => {} };
+
+      expect(() => allocRegion(allocator, 0)).toThrow('ww_alloc failed');
+    });
+  });
+
+  describe('takeResult', () => {
+    function writeSlot(memory: WebAssembly.Memory, slotPtr: number, kind: number, ptr: number, len: number) {
+      const view = new DataView(memory.buffer, slotPtr, 12);
+      view.setUint32(0, kind, true);
+      view.setUint32(4, ptr, true);
+      view.setUint32(8, len, true);
+    }
+
+    it('should return null for an empty slot', () => {
+      const memory = new WebAssembly.Memory({ initial: 1 });
+      const allocator = { alloc: vi.fn(), free: vi.fn() };
+
+      expect(takeResult(memory, allocator, 16)).toBeNull();
+      expect(allocator.free).not.toHaveBeenCalled();
+    });
+
+    it('should copy out the bytes, clear the slot and free the buffer', () => {
+      const memory = new WebAssembly.Memory({ initial: 1 });
+      const allocator = { alloc: vi.fn(), free: vi.fn() };
+      new Uint8Array(memory.buffer, 256, 2).set([104, 105]);
+      writeSlot(memory, 16, ResultKind.String, 256, 2);
+
+      const result = takeResult(memory, allocator, 16);
+
+      expect(result!.kind).toBe(ResultKind.String);
+      expect(new TextDecoder().decode(result!.bytes)).toBe('hi');
+      expect(result!.bytes.buffer).not.toBe(memory.buffer);
+      expect(allocator.free).toHaveBeenCalledWith(256, 2);
+      expect(new DataView(memory.buffer).getUint32(16, true)).toBe(ResultKind.None);
+    });
+  });
+
+  describe('takePanic', () => {
+    const encoder = new TextEncoder();
+
+    it('should return null when the guest has not panicked', () => {
+      const memory = new WebAssembly.Memory({ initial: 1 });
+
+      expect(takePanic(memory, 32)).toBeNull();
+    });
+
+    it('should read the message and location and clear the slot', () => {
+      const memory = new WebAssembly.Memory({ initial: 1 });
+      const message = encoder.encode('attempt to multiply with overflow');
+      const location = encoder.encode('src/lib.rs:30:5');
+      new Uint8Array(memory.buffer, 256).set(message);
+      new Uint8Array(memory.buffer, 512).set(location);
+      const view = new DataView(memory.buffer, 32, 20);
+      [1, 256, message.length, 512, location.length].forEach((value, index) =>
+        view.setUint32(index * 4, value, true)
+      );
+
+      expect(takePanic(memory, 32)).toEqual({
+        message: 'attempt to multiply with overflow',
+        location: 'src/lib.rs:30:5',
+      });
+      expect(takePanic(memory, 32)).toBeNull();
+    });
+
+    it('should read panics from shared memory', () => {
+      const memory = new WebAssembly.Memory({ initial: 1, maximum: 1, shared: true });
+      const message = encoder.encode('index out of bounds');
+      new Uint8Array(memory.buffer, 256).set(message);
+      const view = new DataView(memory.buffer, 32, 20);
+      [1, 256, message.length, 256, 0].forEach((value, index) => view.setUint32(index * 4, value, true));
+
+      expect(takePanic(memory, 32)).toEqual({ message: 'index out of bounds', location: '' });
+    });
+  });
+
+  describe('readString', () => {
+    // What `ww_log` and `ww_host_call` read, from threaded modules too
+    it('should decode strings in shared memory', () => {
+      const memory = new WebAssembly.Memory({ initial: 1, maximum: 1, shared: true });
+      new Uint8Array(memory.buffer, 64).set(new TextEncoder().encode('fetch_config'));
+
+      expect(readString(memory, 64, 12)).toBe('fetch_config');
+      expect(readString(memory, 64, 5)).toBe('fetch');
+    });
+  });
+});
@@ -0,0 +1,85 @@
+import { describe, it, expect } from 'vitest';
+import { encode, decode } from '../src/worker/msgpack';
+
+function hex(bytes: Uint8Array): string {
+  return Array.from(bytes, (byte) => byte.toString(16).padStart(2, '0')).join('');
+}
+
+describe('MessagePack', () => {
+  describe('encode', () => {
+    it('should use the smallest integer form', () => {
+      expect(hex(encode(5))).toBe('05');
+      expect(hex(encode(-3))).toBe('fd');
+      expect(hex(encode(200))).toBe('ccc8');
+      expect(hex(encode(-200))).toBe('d1ff38');
+      expect(hex(encode(70000))).toBe('ce00011170');
+    });
+
+    it('should encode non-integers as float64', () => {
+      expect(hex(encode(2.5))).toBe('cb4004000000000000');
+    });
+
+    it('should encode bigint as 64-bit integers', () => {
+      expect(hex(encode(1n))).toBe('cf0000000000000001');
+      expect(hex(encode(-1n))).toBe('d3ffffffffffffffff');
+      expect(() => encode(1n << 64n)).toThrow(RangeError);
+    });
+
+    it('should encode objects as maps and skip undefined properties', () => {
+      expect(hex(encode({ x: 1, y: undefined }))).toBe('81a17801');
+    });
+
+    it('should encode byte arrays as binary and other typed arrays as arrays', () => {
+      expect(hex(encode(new Uint8Array([1, 2])))).toBe('c4020102');
+      expect(hex(encode(new Int16Array([1, -1])))).toBe('9201ff');
+    });
+
+    it('should grow its buffer for large payloads', () => {
+      const points = Array.from({ length: 5000 }, (_, i) => ({ x: i + 0.5, y: -i }));
+
+      expect(decode(encode(points))).toEqual(points);
+    });
+
+    it('should reject values without a MessagePack form', () => {
+      expect(() => encode(() => {})).toThrow('Cannot encode function');
+    });
+  });
+
+  describe('decode', () => {
+    it('should round-trip nested values', () => {
+      const value = {
+        name: 'é'.repeat(40),
+        tags: ['a', 'b'],
+        nested: { ok: true, missing: null },
+        bytes: new Uint8Array([9, 8, 7]),
+      };
+
+      expect(decode(encode(value))).toEqual(value);
+    });
+
+    it('should keep 64-bit integers as numbers when they are safe', () => {
+      expect(decode(encode(42n))).toBe(42);
+      expect(decode(encode(1n << 60n))).toBe(1n << 60n);
+    });
+
+    it('should copy binary out of the input', () => {
+      const input = encode(new Uint8Array([1, 2, 3]));
+      const bytes = decode(input) as Uint8Array;
+
+      expect(bytes.buffer).not.toBe(input.buffer);
+    });
+
+    it('should keep "__proto__" keys as plain properties', () => {
+      const value = decode(encode(new Map([['__proto__', 1]]))) as Record<string, unknown>;
+
+      expect(Object.getPrototypeOf(value)).toBe(Object.prototype);
+      expect(Object.keys(value)).toEqual(['__proto__']);
+    });
+
+    it('should reject truncated and malformed input', () => {
+      expect(() => decode(new Uint8Array([0x92, 0x01]))).toThrow('Unexpected end');
+      expect(() => decode(new Uint8Array([0xc1]))).toThrow('Unsupported MessagePack type 0xc1');
+      expect(() => decode(new Uint8Array([0x01, 0x02]))).toThrow('trailing bytes');
+    });
+  });
+});
@@ -0,0 +1,151 @@
+import { describe, it, expect, afterEach, vi } from 'vitest';
+import { WasmWorker } from '../src/bridge';
+import { WasmWorkerPool } from '../src/pool';
+
+function mockWorker(module: unknown = null) {
+  const worker = Object.create(WasmWorker.prototype);
+  Object.defineProperty(worker, 'compiledModule', { value: module });
+  worker.call = vi.fn();
+  worker.terminate = vi.fn();
+  return worker;
+}
+
+describe('WasmWorkerPool', () => {
+  afterEach(() => {
+    vi.restoreAllMocks();
+  });
+
+  describe('load', () => {
+    it('should compile the module once and share it', async () => {
+      const module = {};
+      const load = vi.spyOn(WasmWorker, 'load').mockResolvedValue(mockWorker(module));
+      const fromModule = vi
+        .spyOn(WasmWorker, 'fromModule')
+        .mockImplementation(async () => mockWorker());
+
+      const options = { moduleUrl: '/test.wasm', size: 3 };
+      const pool = await WasmWorkerPool.load(options);
+
+      expect(pool.size).toBe(3);
+      expect(load).toHaveBeenCalledTimes(1);
+      expect(fromModule).toHaveBeenCalledTimes(2);
+      expect(fromModule).toHaveBeenCalledWith(options, module);
+    });
+
+    it('should give every worker the files, which each copies', async () => {
+      const module = {};
+      const load = vi.spyOn(WasmWorker, 'load').mockResolvedValue(mockWorker(module));
+      const fromModule = vi
+        .spyOn(WasmWorker, 'fromModule')
+        .mockImplementation(async () => mockWorker());
+      const input = new Uint8Array([1, 2, 3]);
+
+      await WasmWorkerPool.load({ moduleUrl: '/test.wasm', size: 2, files: { 'input.bin': input } });
+
+      expect(load.mock.calls[0][0].files!['input.bin']).toBe(input);
+      expect(fromModule.mock.calls[0][0].files!['input.bin']).toBe(input);
+    });
+
+    it('should build the api on the pool rather than its workers', async () => {
+      const load = vi.spyOn(WasmWorker, 'load').mockResolvedValue(mockWorker(null));
+      const bindings = vi.fn((target) => ({ add: (a: number, b: number) => target.call('add', [a, b]) }));
+
+      const pool = await WasmWorkerPool.load({ moduleUrl: '/test.wasm', size: 1, bindings });
+
+      expect(bindings).toHaveBeenCalledWith(pool);
+      expect(load.mock.calls[0][0].bindings).toBeUndefined();
+      expect(typeof pool.api.add).toBe('function');
+    });
+
+    it('should reject invalid sizes', async () => {
+      const load = vi.spyOn(WasmWorker, 'load');
+
+      await expect(WasmWorkerPool.load({ moduleUrl: '/test.wasm', size: 0 })).rejects.toThrow(
+        RangeError
+      );
+      expect(load).not.toHaveBeenCalled();
+    });
+
+    it('should terminate started workers when one fails to load', async () => {
+      const first = mockWorker({});
+      vi.spyOn(WasmWorker, 'load').mockResolvedValue(first);
+      vi.spyOn(WasmWorker, 'fromModule').mockRejectedValue(new Error('boom'));
+
+      await expect(WasmWorkerPool.load({ moduleUrl: '/test.wasm', size: 2 })).rejects.toThrow(
+        'boom'
+      );
+      expect(first.terminate).toHaveBeenCalled();
+    });
+
+    it('should terminate the workers that loaded when another fails', async () => {
+      const first = mockWorker({});
+      const loaded = [mockWorker(), mockWorker()];
+      vi.spyOn(WasmWorker, 'load').mockResolvedValue(first);
+      vi.spyOn(WasmWorker, 'fromModule')
+        .mockResolvedValueOnce(loaded[0])
+        .mockRejectedValueOnce(new Error('boom'))
+        .mockResolvedValueOnce(loaded[1]);
+
+      await expect(WasmWorkerPool.load({ moduleUrl: '/test.wasm', size: 4 })).rejects.toThrow(
+        'boom'
+      );
+      for (const worker of [first, ...loaded]) {
+        expect(worker.terminate).toHaveBeenCalled();
+      }
+    });
+  });
+
+  describe('call', () => {
+    function poolOf(workers: WasmWorker[]) {
+      const pool = Object.create(WasmWorkerPool.prototype);
+      pool.entries = workers.map((worker) => ({ worker, busy: 0 }));
+      return pool;
+    }
+
+    it('should dispatch to the least busy worker', async () => {
+      const workers = [mockWorker(), mockWorker()];
+      const resolvers: Array<(value: number) => void> = [];
+      for (const worker of workers) {
+        worker.call.mockImplementation(
+          () => new Promise<number>((resolve) => resolvers.push(resolve))
+        );
+      }
+      const pool = poolOf(workers);
+
+      const first = pool.call('fib', 40);
+      const second = pool.call('fib', 40);
+      expect(workers[0].call).toHaveBeenCalledTimes(1);
+      expect(workers[1].call).toHaveBeenCalledTimes(1);
+
+      // The first worker finishes, so it gets the next call
+      resolvers[0](1);
+      await first;
+      const third = pool.call('fib', 40);
+      expect(workers[0].call).toHaveBeenCalledTimes(2);
+
+      resolvers[1](2);
+      resolvers[2](3);
+      await expect(Promise.all([second, third])).resolves.toEqual([2, 3]);
+    });
+
+    it('should release the worker when a call fails', async () => {
+      const worker = mockWorker();
+      worker.call.mockRejectedValue(new Error('trap'));
+      const pool = poolOf([worker]);
+
+      await expect(pool.call('fib', 40)).rejects.toThrow('trap');
+      expect(pool.entries[0].busy).toBe(0);
+    });
+
+    it('should reject calls once terminated', async () => {
+      const workers = [mockWorker(), mockWorker()];
+      const pool = poolOf(workers);
+
+      pool.terminate();
+
+      expect(workers[0].terminate).toHaveBeenCalled();
+      expect(workers[1].terminate).toHaveBeenCalled();
+      await expect(pool.call('fib', 40)).rejects.toThrow('Worker pool terminated');
+    });
+  });
+});
@@ -0,0 +1,158 @@
+import { describe, it, expect, afterEach, vi } from 'vitest';
+import {
+  absoluteUrl,
+  checkSource,
+  compileResponse,
+  postableBytes,
+  postableFiles,
+} from '../src/source';
+
+describe('Module sources', () => {
+  afterEach(() => {
+    vi.restoreAllMocks();
+  });
+
+  describe('checkSource', () => {
+    it('should accept exactly one source', () => {
+      expect(() => checkSource({ moduleUrl: '/test.wasm' })).not.toThrow();
+      expect(() => checkSource({ moduleBytes: new Uint8Array(8) })).not.toThrow();
+      expect(() => checkSource({ moduleResponse: new Response('') })).not.toThrow();
+    });
+
+    it('should reject integrity it cannot check', () => {
+      expect(() => checkSource({ moduleUrl: '/test.wasm', integrity: 'md5-abc' })).toThrow(TypeError);
+      expect(() =>
+        checkSource({ module: {} as WebAssembly.Module, integrity: 'sha384-abc' })
+      ).toThrow(/already compiled/);
+    });
+
+    it('should reject init with bindgen', () => {
+      expect(() =>
+        checkSource({ moduleUrl: '/pkg/app_bg.wasm', bindgen: { glueUrl: '/pkg/app.js' }, init: {} })
+      ).toThrow(/glue provides the imports/);
+    });
+
+    it('should reject thread counts helpers cannot be started for', () => {
+      expect(() => checkSource({ moduleUrl: '/test.wasm', threads: 4 })).not.toThrow();
+      expect(() => checkSource({ moduleUrl: '/test.wasm', threads: -1 })).toThrow(RangeError);
+      expect(() =>
+        checkSource({ moduleUrl: '/pkg/app_bg.wasm', bindgen: { glueUrl: '/pkg/app.js' }, threads: 4 })
+      ).not.toThrow();
+      expect(() =>
+        checkSource({ moduleUrl: '/test.wasm', threads: 4, files: { 'in.txt': new ArrayBuffer(1) } })
+      ).toThrow(/files cannot be used with threads/);
+      expect(() => checkSource({ moduleUrl: '/test.wasm', threads: 0, files: {} })).not.toThrow();
+    });
+
+    it('should reject no source or several', () => {
+      expect(() => checkSource({})).toThrow(/got none/);
+      expect(() => checkSource({ moduleUrl: '/test.wasm', moduleBytes: new ArrayBuffer(8) })).toThrow(
+        /got moduleUrl, moduleBytes/
+      );
+    });
+  });
+
+  describe('absoluteUrl', () => {
+    it('should resolve against the page', () => {
+      expect(absoluteUrl('pkg/app.js')).toBe(new URL('pkg/app.js', location.href).href);
+      expect(absoluteUrl('https://cdn.example.com/app.js')).toBe('https://cdn.example.com/app.js');
+    });
+  });
+
+  describe('postableBytes', () => {
+    it('should transfer a copy of array buffers', () => {
+      const buffer = new Uint8Array([1, 2]).buffer;
+
+      const { bytes, transfer } = postableBytes(buffer);
+
+      expect(bytes).not.toBe(buffer);
+      expect(new Uint8Array(bytes)).toEqual(new Uint8Array([1, 2]));
+      expect(transfer).toEqual([bytes]);
+    });
+
+    it('should share shared array buffers', () => {
+      const buffer = new SharedArrayBuffer(8);
+
+      expect(postableBytes(buffer)).toEqual({ bytes: buffer, transfer: [] });
+    });
+
+    it('should copy the bytes of views', () => {
+      const whole = new Uint8Array([0, 1, 2, 3, 4]);
+      const view = whole.subarray(1, 4);
+
+      const { bytes, transfer } = postableBytes(view);
+
+      expect(new Uint8Array(bytes)).toEqual(new Uint8Array([1, 2, 3]));
+      expect(transfer).toEqual([bytes]);
+      expect(bytes).not.toBe(whole.buffer);
+    });
+
+    it('should move buffers listed in the transfer list', () => {
+      const buffer = new Uint8Array([1, 2]).buffer;
+      const whole = new Uint8Array(buffer);
+
+      expect(postableBytes(buffer, [buffer])).toEqual({ bytes: buffer, transfer: [buffer] });
+      expect(postableBytes(whole, [buffer]).bytes).toBe(buffer);
+      // Part of a buffer can't be moved
+      expect(postableBytes(whole.subarray(1), [buffer]).bytes).not.toBe(buffer);
+    });
+  });
+
+  describe('postableFiles', () => {
+    it('should copy every file, leaving the caller its buffers', () => {
+      const buffer = new ArrayBuffer(8);
+      const shared = new SharedArrayBuffer(4);
+
+      const { files, transfer } = postableFiles({ 'a.bin': buffer, 'b.bin': buffer, 'c.bin': shared });
+
+      expect(files['a.bin']).not.toBe(buffer);
+      expect(files['a.bin']).not.toBe(files['b.bin']);
+      expect(files['c.bin']).toBe(shared);
+      expect(transfer).toEqual([files['a.bin'], files['b.bin']]);
+      expect(buffer.byteLength).toBe(8);
+    });
+
+    it('should move the buffers listed in the transfer list once', () => {
+      const buffer = new ArrayBuffer(8);
+      const other = new ArrayBuffer(4);
+
+      const { files, transfer } = postableFiles(
+        { 'a.bin': buffer, 'b.bin': buffer, 'c.bin': other },
+        [buffer]
+      );
+
+      expect(files['a.bin']).toBe(buffer);
+      expect(files['b.bin']).toBe(buffer);
+      expect(files['c.bin']).not.toBe(other);
+      expect(transfer).toEqual([buffer, files['c.bin']]);
+    });
+  });
+
+  describe('compileResponse', () => {
+    const module = {} as WebAssembly.Module;
+
+    it('should compile application/wasm responses while streaming', async () => {
+      const compileStreaming = vi.spyOn(WebAssembly, 'compileStreaming').mockResolvedValue(module);
+      const response = new Response(new Uint8Array(8), {
+        headers: { 'Content-Type': 'application/wasm' },
+      });
+
+      expect(await compileResponse(response)).toBe(module);
+      expect(compileStreaming).toHaveBeenCalledWith(response);
+    });
+
+    it('should read other responses whole', async () => {
+      const compileStreaming = vi.spyOn(WebAssembly, 'compileStreaming');
+      const compile = vi.spyOn(WebAssembly, 'compile').mockResolvedValue(module);
+      const response = new Response(new Uint8Array([0, 97, 115, 109]), {
+        headers: { 'Content-Type': 'application/octet-stream' },
+      });
+
+      expect(await compileResponse(response)).toBe(module);
+      expect(compileStreaming).not.toHaveBeenCalled();
+      expect(new Uint8Array(compile.mock.calls[0][0] as ArrayBuffer)).toEqual(
+        new Uint8Array([0, 97, 115, 109])
+      );
+    });
+  });
+});
@@ -0,0 +1,84 @@
+import { describe, it, expect, vi } from 'vitest';
+import { SPAWN_FAILED, ThreadPool, importsMemory, sharedMemoryImport } from '../src/worker/threads';
+
+// Build a module from its sections
+function moduleBytes(...sections: [number, number[]][]): ArrayBuffer {
+  const bytes = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+  for (const [id, content] of sections) {
+    bytes.push(id, content.length, ...content);
+  }
+  return new Uint8Array(bytes).buffer;
+}
+
+function name(value: string): number[] {
+  return [value.length, ...new TextEncoder().encode(value)];
+}
+
+// One function type, taking and returning an i32
+const TYPES: [number, number[]] = [1, [1, 0x60, 1, 0x7f, 1, 0x7f]];
+
+describe('Threads', () => {
+  describe('sharedMemoryImport', () => {
+    it('should read the limits of an imported shared memory', () => {
+      const bytes = moduleBytes(TYPES, [
+        2,
+        [
+          2,
+          ...name('wasi'), ...name('thread-spawn'), 0x00, 0,
+          // Shared, 17 pages up to 16384
+          ...name('env'), ...name('memory'), 0x02, 0x03, 17, 0x80, 0x80, 0x01,
+        ],
+      ]);
+
+      expect(WebAssembly.validate(bytes)).toBe(true);
+      expect(sharedMemoryImport(bytes)).toEqual({ module: 'env', name: 'memory', initial: 17, maximum: 16384 });
+    });
+
+    it('should ignore memories that are not shared', () => {
+      const bytes = moduleBytes([2, [1, ...name('env'), ...name('memory'), 0x02, 0x01, 1, 2]]);
+
+      expect(WebAssembly.validate(bytes)).toBe(true);
+      expect(sharedMemoryImport(bytes)).toBeNull();
+    });
+
+    it('should return null for modules without imports', () => {
+      expect(sharedMemoryImport(moduleBytes(TYPES))).toBeNull();
+    });
+  });
+
+  describe('importsMemory', () => {
+    it('should tell compiled modules importing a memory apart', async () => {
+      const imported = moduleBytes([2, [1, ...name('env'), ...name('memory'), 0x02, 0x00, 1]]);
+
+      expect(importsMemory(await WebAssembly.compile(imported))).toBe(true);
+      expect(importsMemory(await WebAssembly.compile(moduleBytes(TYPES)))).toBe(false);
+    });
+  });
+
+  describe('ThreadPool', () => {
+    it('should hand threads to idle helpers', () => {
+      const ports = [{ postMessage: vi.fn() }, { postMessage: vi.fn() }] as unknown as MessagePort[];
+      const busy = new Int32Array(new SharedArrayBuffer(8));
+      const pool = new ThreadPool(ports, busy);
+
+      expect(pool.spawn(100)).toBe(1);
+      expect(pool.spawn(200)).toBe(2);
+      expect(ports[0].postMessage).toHaveBeenCalledWith({ tid: 1, arg: 100 });
+      expect(ports[1].postMessage).toHaveBeenCalledWith({ tid: 2, arg: 200 });
+      expect(Array.from(busy)).toEqual([1, 1]);
+    });
+
+    it('should fail with EAGAIN until a helper is idle again', () => {
+      const ports = [{ postMessage: vi.fn() }] as unknown as MessagePort[];
+      const busy = new Int32Array(new SharedArrayBuffer(4));
+      const pool = new ThreadPool(ports, busy);
+
+      pool.spawn(1);
+      expect(pool.spawn(2)).toBe(SPAWN_FAILED);
+
+      // Cleared by the helper once its thread returns
+      Atomics.store(busy, 0, 0);
+      expect(pool.spawn(3)).toBe(2);
+    });
+  });
+});
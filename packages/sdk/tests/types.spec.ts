@@ -34,6 +34,23 @@ describe('Types', () => {
 
       expect(opts.transfer).toHaveLength(1);
     });
+
+    it('should accept an abort signal', () => {
+      const controller = new AbortController();
+      const opts: CallOptions = {
+        signal: controller.signal,
+      };
+
+      expect(opts.signal?.aborted).toBe(false);
+    });
+
+    it('should accept a codec', () => {
+      const opts: CallOptions = {
+        codec: 'json',
+      };
+
+      expect(opts.codec).toBe('json');
+    });
   });
 
   describe('ErrorCode', () => {
@@ -45,6 +62,11 @@ describe('Types', () => {
         'INVALID_PAYLOAD',
         'WASM_TRAP',
         'NOT_INITIALIZED',
+        'CANCELLED',
+        'TIMEOUT',
+        'APP_ERROR',
+        'INTEGRITY_MISMATCH',
+        'FS_ERROR',
         'UNKNOWN_ERROR',
       ];
 
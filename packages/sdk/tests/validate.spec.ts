@@ -0,0 +1,142 @@
+import { describe, it, expect } from 'vitest';
+import {
+  ArgumentError,
+  checkArgs,
+  checkArity,
+  describeValue,
+  liftReturn,
+  namedArgs,
+} from '../src/worker/validate';
+
+const add = [
+  { name: 'a', type: 'i32' },
+  { name: 'b', type: 'i32' },
+];
+
+function argumentError(run: () => unknown): ArgumentError {
+  try {
+    run();
+  } catch (error) {
+    expect(error).toBeInstanceOf(ArgumentError);
+    return error as ArgumentError;
+  }
+  throw new Error('expected an ArgumentError');
+}
+
+describe('checkArgs', () => {
+  it('should pass matching arguments through', () => {
+    expect(checkArgs('add', add, [2, 3])).toEqual([2, 3]);
+    expect(
+      checkArgs('scale', [
+        { name: 'data', type: '&[u8]' },
+        { name: 'name', type: "&'a str" },
+        { name: 'factor', type: 'f64' },
+        { name: 'flag', type: 'bool' },
+      ], [new Uint8Array(2), 'x', 1.5, true])
+    ).toHaveLength(4);
+  });
+
+  it('should reject the wrong number of arguments', () => {
+    const error = argumentError(() => checkArgs('add', add, [1]));
+    expect(error.details).toEqual({ function: 'add', expected: 2, received: 1 });
+  });
+
+  it('should reject arguments of the wrong type', () => {
+    const error = argumentError(() => checkArgs('add', add, [1, '2']));
+    expect(error.message).toBe('Argument "b" of "add" must be i32, got string');
+    expect(error.details).toEqual({
+      function: 'add',
+      argument: 1,
+      name: 'b',
+      expected: 'i32',
+      received: 'string',
+    });
+  });
+
+  it('should check integer ranges', () => {
+    const fib = [{ name: 'n', type: 'u32' }];
+    expect(checkArgs('fib', fib, [2 ** 32 - 1])).toEqual([2 ** 32 - 1]);
+    expect(argumentError(() => checkArgs('fib', fib, [-1])).details).toMatchObject({
+      expected: 'u32',
+      received: 'number -1',
+    });
+    expect(() => checkArgs('fib', fib, [2 ** 32])).toThrow(ArgumentError);
+    expect(() => checkArgs('fib', fib, [1.5])).toThrow(ArgumentError);
+    expect(() => checkArgs('add', add, [2 ** 31, 0])).toThrow(ArgumentError);
+  });
+
+  it('should accept 0 and 1 for bool parameters', () => {
+    const params = [{ name: 'flag', type: 'bool' }];
+    expect(checkArgs('f', params, [1])).toEqual([1]);
+    expect(() => checkArgs('f', params, [2])).toThrow(ArgumentError);
+  });
+
+  it('should convert safe integers to bigints for 64-bit parameters', () => {
+    const params = [{ name: 'n', type: 'u64' }];
+    expect(checkArgs('f', params, [5])).toEqual([5n]);
+    expect(checkArgs('f', params, [2n ** 64n - 1n])).toEqual([2n ** 64n - 1n]);
+    expect(() => checkArgs('f', params, [-1n])).toThrow(ArgumentError);
+    expect(() => checkArgs('f', params, [2 ** 60])).toThrow(ArgumentError);
+  });
+});
+
+describe('namedArgs', () => {
+  it('should map keys to parameters by name', () => {
+    expect(namedArgs('subtract', add, { b: 3, a: 5 })).toEqual([5, 3]);
+  });
+
+  it('should reject unknown keys', () => {
+    const error = argumentError(() => namedArgs('subtract', add, { a: 5, b: 3, c: 1 }));
+    expect(error.message).toBe('Function "subtract" has no parameter named "c"');
+    expect(error.details).toEqual({ function: 'subtract', unknown: ['c'], expected: ['a', 'b'] });
+  });
+
+  it('should reject missing keys', () => {
+    const error = argumentError(() => namedArgs('subtract', add, { b: 3 }));
+    expect(error.message).toBe('Function "subtract" is missing argument "a"');
+    expect(error.details).toEqual({ function: 'subtract', missing: ['a'], expected: ['a', 'b'] });
+  });
+});
+
+describe('checkArity', () => {
+  it('should count buffers and strings as two wasm values', () => {
+    expect(() => checkArity('greet', 2, ['bob'], [8, 3])).not.toThrow();
+  });
+
+  it('should reject a mismatched arity', () => {
+    const error = argumentError(() => checkArity('add', 2, ['1', '2'], [8, 1, 16, 1]));
+    expect(error.details).toEqual({ function: 'add', expected: 2, received: 4 });
+  });
+
+  it('should reject values wasm cannot take', () => {
+    const error = argumentError(() => checkArity('add', 2, [1, { b: 2 }], [1, { b: 2 }]));
+    expect(error.details).toEqual({ function: 'add', argument: 1, received: 'object' });
+  });
+});
+
+describe('liftReturn', () => {
+  it('should read unsigned values above the signed range', () => {
+    // Adler-32 of a long input, as wasm hands it back
+    expect(liftReturn('u32', -1_829_104_431)).toBe(2_465_862_865);
+    expect(liftReturn('u64', -(2n ** 63n))).toBe(2n ** 63n);
+    expect(liftReturn('Result<u32, String>', -1)).toBe(2 ** 32 - 1);
+  });
+
+  it('should read booleans and leave other types alone', () => {
+    expect(liftReturn('bool', 1)).toBe(true);
+    expect(liftReturn('bool', 0)).toBe(false);
+    expect(liftReturn('i32', -1)).toBe(-1);
+    expect(liftReturn(null, undefined)).toBeUndefined();
+  });
+});
+
+describe('describeValue', () => {
+  it('should describe values for error messages', () => {
+    expect(describeValue(null)).toBe('null');
+    expect(describeValue(3)).toBe('number 3');
+    expect(describeValue(3n)).toBe('bigint 3n');
+    expect(describeValue([1])).toBe('array');
+    expect(describeValue(new ArrayBuffer(1))).toBe('binary');
+    expect(describeValue(undefined)).toBe('undefined');
+  });
+});
@@ -0,0 +1,149 @@
+import { describe, it, expect, beforeEach } from 'vitest';
+import { Errno, MemoryFs } from '../src/worker/fs';
+import { WASI_MODULE, Wasi, WasiExit } from '../src/worker/wasi';
+
+const NAMES = [
+  'args_sizes_get',
+  'args_get',
+  'clock_time_get',
+  'random_get',
+  'fd_write',
+  'fd_read',
+  'fd_close',
+  'fd_prestat_get',
+  'fd_prestat_dir_name',
+  'fd_readdir',
+  'path_open',
+  'proc_exit',
+  'sock_accept',
+];
+
+describe('WASI shim', () => {
+  let memory: WebAssembly.Memory;
+  let fs: MemoryFs;
+  let lines: [number, string][];
+  let wasi: Record<string, (...args: any[]) => any>;
+  let shim: Wasi;
+
+  const view = () => new DataView(memory.buffer);
+  const bytes = () => new Uint8Array(memory.buffer);
+
+  // Write `text` at `ptr` and an iovec pointing at it at `iov`
+  function iovec(iov: number, ptr: number, text: string): void {
+    const encoded = new TextEncoder().encode(text);
+    bytes().set(encoded, ptr);
+    view().setUint32(iov, ptr, true);
+    view().setUint32(iov + 4, encoded.length, true);
+  }
+
+  function open(path: string, oflags: number): number {
+    bytes().set(new TextEncoder().encode(path), 200);
+    expect(wasi.path_open(3, 0, 200, path.length, oflags, 0n, 0n, 0, 300)).toBe(Errno.SUCCESS);
+    return view().getUint32(300, true);
+  }
+
+  beforeEach(() => {
+    memory = new WebAssembly.Memory({ initial: 1 });
+    fs = new MemoryFs();
+    lines = [];
+    shim = new Wasi({
+      args: ['app', '--fast'],
+      env: {},
+      fs,
+      memory: () => memory,
+      output: (fd, line) => lines.push([fd, line]),
+    });
+    wasi = shim.imports(NAMES);
+  });
+
+  it('should be imported as wasi_snapshot_preview1', () => {
+    expect(WASI_MODULE).toBe('wasi_snapshot_preview1');
+  });
+
+  it('should pass args', () => {
+    expect(wasi.args_sizes_get(0, 4)).toBe(Errno.SUCCESS);
+    expect(view().getUint32(0, true)).toBe(2);
+    expect(view().getUint32(4, true)).toBe(11);
+
+    expect(wasi.args_get(16, 64)).toBe(Errno.SUCCESS);
+    const second = view().getUint32(20, true);
+    expect(new TextDecoder().decode(bytes().subarray(second, second + 6))).toBe('--fast');
+    expect(bytes()[second + 6]).toBe(0);
+  });
+
+  it('should read the clocks and random bytes', () => {
+    expect(wasi.clock_time_get(0, 0n, 0)).toBe(Errno.SUCCESS);
+    const ms = Number(view().getBigUint64(0, true) / 1_000_000n);
+    expect(Math.abs(ms - Date.now())).toBeLessThan(1000);
+
+    expect(wasi.random_get(64, 32)).toBe(Errno.SUCCESS);
+    expect(bytes().subarray(64, 96).some((byte) => byte !== 0)).toBe(true);
+  });
+
+  it('should forward stdout and stderr line by line', () => {
+    iovec(0, 100, 'hello\nwor');
+    expect(wasi.fd_write(1, 0, 1, 8)).toBe(Errno.SUCCESS);
+    expect(view().getUint32(8, true)).toBe(9);
+    iovec(0, 100, 'oops\n');
+    wasi.fd_write(2, 0, 1, 8);
+
+    expect(lines).toEqual([
+      [1, 'hello'],
+      [2, 'oops'],
+    ]);
+
+    shim.flush();
+    expect(lines.at(-1)).toEqual([1, 'wor']);
+  });
+
+  it('should create files with O_CREAT and read them back', () => {
+    fs.mkdir('/data');
+    const writer = open('data/out.txt', 1);
+    iovec(0, 100, 'saved');
+    expect(wasi.fd_write(writer, 0, 1, 8)).toBe(Errno.SUCCESS);
+    expect(wasi.fd_close(writer)).toBe(Errno.SUCCESS);
+    expect(new TextDecoder().decode(fs.readFile('/data/out.txt'))).toBe('saved');
+
+    const reader = open('data/out.txt', 0);
+    view().setUint32(0, 400, true);
+    view().setUint32(4, 16, true);
+    expect(wasi.fd_read(reader, 0, 1, 8)).toBe(Errno.SUCCESS);
+    expect(view().getUint32(8, true)).toBe(5);
+    expect(new TextDecoder().decode(bytes().subarray(400, 405))).toBe('saved');
+  });
+
+  it('should report missing files and closed descriptors', () => {
+    bytes().set(new TextEncoder().encode('missing'), 200);
+    expect(wasi.path_open(3, 0, 200, 7, 0, 0n, 0n, 0, 300)).toBe(Errno.NOENT);
+    expect(wasi.fd_close(42)).toBe(Errno.BADF);
+  });
+
+  it('should preopen the root directory', () => {
+    expect(wasi.fd_prestat_get(3, 0)).toBe(Errno.SUCCESS);
+    expect(view().getUint32(4, true)).toBe(1);
+    expect(wasi.fd_prestat_dir_name(3, 16, 1)).toBe(Errno.SUCCESS);
+    expect(bytes()[16]).toBe('/'.charCodeAt(0));
+    expect(wasi.fd_prestat_get(4, 0)).toBe(Errno.BADF);
+  });
+
+  it('should list directories', () => {
+    fs.writeFile('/a.txt', new Uint8Array(1));
+    fs.mkdir('/b');
+
+    // Resume after `.` and `..`
+    expect(wasi.fd_readdir(3, 1000, 256, 2n, 8)).toBe(Errno.SUCCESS);
+    const used = view().getUint32(8, true);
+    // Two 24 byte dirents, each followed by its name
+    expect(used).toBe(24 + 5 + 24 + 1);
+    expect(view().getBigUint64(1000, true)).toBe(3n);
+    expect(new TextDecoder().decode(bytes().subarray(1024, 1029))).toBe('a.txt');
+  });
+
+  it('should end the call on proc_exit', () => {
+    expect(() => wasi.proc_exit(3)).toThrow(WasiExit);
+  });
+
+  it('should return ENOSYS for functions it does not implement', () => {
+    expect(wasi.sock_accept(0, 0, 0)).toBe(Errno.NOSYS);
+  });
+});